@@ -7,6 +7,9 @@ const SAMPLE_RATE: u32 = 16000;
 /// Number of samples per VAD frame (required by Silero VAD at 16kHz).
 const VAD_FRAME_SAMPLES: usize = 512;
 
+/// Source label of chunks captured from the local input device.
+pub const SOURCE_MICROPHONE: &str = "microphone";
+
 /// A chunk of 16kHz mono audio ready for STT processing.
 #[derive(Debug, Clone)]
 pub struct AudioChunk {
@@ -14,6 +17,8 @@ pub struct AudioChunk {
     pub samples_i16: Vec<i16>,
     pub timestamp: DateTime<Utc>,
     pub duration_secs: f32,
+    /// Where the audio came from, e.g. [`SOURCE_MICROPHONE`].
+    pub source: &'static str,
 }
 
 impl AudioChunk {
@@ -33,6 +38,7 @@ impl AudioChunk {
             samples_i16,
             timestamp,
             duration_secs,
+            source: SOURCE_MICROPHONE,
         }
     }
 }
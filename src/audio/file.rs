@@ -0,0 +1,106 @@
+use std::path::Path;
+use std::process::Command;
+
+use crate::error::{HooverError, Result};
+
+use super::resample::Resampler;
+
+/// Load an audio file as 16kHz mono f32 samples.
+///
+/// WAV files are decoded natively.  Any other format (FLAC, MP3, Ogg, ...) is
+/// decoded by running `ffmpeg`, which must be installed and on `$PATH`.
+pub fn load_audio_file(path: &Path) -> Result<Vec<f32>> {
+    let is_wav = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("wav"));
+
+    if is_wav {
+        load_wav(path)
+    } else {
+        load_with_ffmpeg(path)
+    }
+}
+
+fn load_wav(path: &Path) -> Result<Vec<f32>> {
+    let mut reader = hound::WavReader::open(path).map_err(|e| {
+        HooverError::Audio(format!("failed to open WAV file {}: {e}", path.display()))
+    })?;
+
+    let spec = reader.spec();
+    let interleaved: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Float => reader
+            .samples::<f32>()
+            .map(|s| s.map_err(|e| HooverError::Audio(format!("WAV read error: {e}"))))
+            .collect::<Result<Vec<f32>>>()?,
+        hound::SampleFormat::Int => {
+            let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|s| {
+                    s.map(|v| v as f32 / scale)
+                        .map_err(|e| HooverError::Audio(format!("WAV read error: {e}")))
+                })
+                .collect::<Result<Vec<f32>>>()?
+        }
+    };
+
+    let mut resampler = Resampler::new(spec.sample_rate, spec.channels)?;
+    resampler.process(&interleaved)
+}
+
+fn load_with_ffmpeg(path: &Path) -> Result<Vec<f32>> {
+    let output = Command::new("ffmpeg")
+        .args(["-nostdin", "-loglevel", "error", "-i"])
+        .arg(path)
+        .args(["-f", "f32le", "-ac", "1", "-ar", "16000", "-"])
+        .output()
+        .map_err(|e| {
+            HooverError::Audio(format!(
+                "failed to run ffmpeg to decode {} (is it installed?): {e}",
+                path.display()
+            ))
+        })?;
+
+    if !output.status.success() {
+        return Err(HooverError::Audio(format!(
+            "ffmpeg failed to decode {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(output
+        .stdout
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_16k_mono_wav() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let path = dir.path().join("tone.wav");
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 16000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap_or_else(|e| panic!("{e}"));
+        for _ in 0..16000 {
+            writer
+                .write_sample(i16::MAX / 2)
+                .unwrap_or_else(|e| panic!("{e}"));
+        }
+        writer.finalize().unwrap_or_else(|e| panic!("{e}"));
+
+        let samples = load_audio_file(&path).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(samples.len(), 16000);
+        assert!((samples[0] - 0.5).abs() < 1e-3);
+    }
+}
@@ -1,5 +1,6 @@
 pub mod buffer;
 pub mod capture;
+pub mod file;
 pub mod resample;
 pub mod vad;
 
@@ -19,7 +20,7 @@ const VAD_MODEL_URL: &str =
     "https://huggingface.co/onnx-community/silero-vad/resolve/main/onnx/model.onnx";
 
 /// Resolve the Silero VAD ONNX model, downloading it if necessary.
-fn resolve_vad_model() -> Result<PathBuf> {
+pub(crate) fn resolve_vad_model() -> Result<PathBuf> {
     let data_dir = dirs::data_dir()
         .ok_or_else(|| HooverError::Audio("could not determine data directory".to_string()))?;
 
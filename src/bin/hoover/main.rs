@@ -1,12 +1,19 @@
+use std::collections::BTreeSet;
 use std::io::Write;
 use std::path::PathBuf;
 
-use clap::{CommandFactory, Parser, Subcommand};
+use chrono::{NaiveDate, NaiveTime};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
 use clap_complete::{generate, Shell};
 use tracing_subscriber::EnvFilter;
 
-use hoover::config::Config;
+use hoover::config::{Config, StdoutFormat};
+use hoover::corrections::{self, Correction, CorrectionRule};
 use hoover::error::HooverError;
+use hoover::export::{ExportFilter, ExportFormat};
+use hoover::output::calendar::Calendar;
+use hoover::output::layout::Layout;
+use hoover::vault::Vault;
 
 #[derive(Parser)]
 #[command(
@@ -46,7 +53,19 @@ enum Command {
     /// results to a daily markdown file. If speaker identification is
     /// enabled, segments are tagged with the recognized speaker name.
     /// Runs until interrupted with Ctrl+C.
-    Record,
+    ///
+    /// With --emit, every segment is also written to stdout as it is
+    /// transcribed, so hoover can feed other programs; add --no-files to
+    /// skip the daily files. Logs always go to stderr.
+    Record {
+        /// Stream segments to stdout as text or JSON lines
+        #[arg(long, value_enum)]
+        emit: Option<EmitFormatArg>,
+
+        /// Don't write the daily markdown files or JSONL log
+        #[arg(long)]
+        no_files: bool,
+    },
 
     /// Manually push the transcription repository
     ///
@@ -65,14 +84,25 @@ enum Command {
 
     /// Enroll a speaker voice profile
     ///
-    /// Records a short audio sample and computes an ECAPA-TDNN voice
-    /// embedding that is saved as a speaker profile. Once enrolled,
-    /// hoover can identify this speaker during transcription and tag
-    /// their segments accordingly. Speak for 10-30 seconds, then press
-    /// Ctrl+C to finish enrollment.
+    /// Records a short audio sample (or reads existing recordings with
+    /// --file) and computes an ECAPA-TDNN voice embedding that is saved as
+    /// a speaker profile. Non-speech and outlier windows are discarded and
+    /// a quality score is printed. Once enrolled, hoover can identify this
+    /// speaker during transcription and tag their segments accordingly.
+    /// When recording, speak for 10-30 seconds, then press Ctrl+C to finish
+    /// enrollment.
     Enroll {
         /// Name of the speaker to enroll
         name: String,
+
+        /// Enroll from an audio file instead of the microphone (repeatable).
+        /// WAV is read directly; other formats are decoded with ffmpeg.
+        #[arg(long = "file", value_name = "PATH")]
+        files: Vec<PathBuf>,
+
+        /// Blend into the speaker's existing profile instead of replacing it
+        #[arg(long)]
+        append: bool,
     },
 
     /// Send audio to a remote hoover instance via encrypted UDP
@@ -98,9 +128,12 @@ enum Command {
 
     /// List or manage enrolled speaker profiles
     ///
-    /// Shows all enrolled speaker profiles. Use --remove to delete a
-    /// speaker's profile by name.
+    /// Shows all enrolled speaker profiles and today's anonymous speaker
+    /// clusters. Use --remove to delete a speaker's profile by name.
     Speakers {
+        #[command(subcommand)]
+        action: Option<SpeakersAction>,
+
         /// Remove an enrolled speaker profile by name
         #[arg(long)]
         remove: Option<String>,
@@ -128,6 +161,118 @@ enum Command {
     /// and version control. Writes the result to the config file.
     Init,
 
+    /// Manage the full-text search index
+    ///
+    /// The index is kept up to date while recording when a `sqlite` sink is
+    /// listed in `output.sinks`, and is used by the MCP search tool.
+    Index {
+        #[command(subcommand)]
+        action: IndexAction,
+    },
+
+    /// Export transcripts as subtitles, text, HTML or JSON
+    ///
+    /// Reconstructs segments from the daily files, using each day's JSONL
+    /// log when present for exact times and durations. Subtitle times are
+    /// offsets from the first exported segment.
+    Export {
+        /// First day to export (YYYY-MM-DD)
+        ///
+        /// Defaults to today.
+        #[arg(long)]
+        from: Option<NaiveDate>,
+
+        /// Last day to export (YYYY-MM-DD)
+        ///
+        /// Defaults to the first day.
+        #[arg(long)]
+        to: Option<NaiveDate>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = ExportFormatArg::Txt)]
+        format: ExportFormatArg,
+
+        /// Only segments at or after this time of day (HH:MM)
+        #[arg(long, value_parser = parse_time_of_day)]
+        since: Option<NaiveTime>,
+
+        /// Only segments before this time of day (HH:MM)
+        #[arg(long, value_parser = parse_time_of_day)]
+        until: Option<NaiveTime>,
+
+        /// Only segments from this speaker, or "unknown" (repeatable)
+        #[arg(long = "speaker", value_name = "NAME")]
+        speakers: Vec<String>,
+
+        /// Write to this file instead of stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+
+    /// Print a day's transcript, decrypting it if needed
+    Cat {
+        /// Day to print (YYYY-MM-DD)
+        ///
+        /// Defaults to today.
+        date: Option<NaiveDate>,
+    },
+
+    /// Encrypt daily files written before `output.encryption` was set
+    ///
+    /// Each plaintext daily file and JSONL log is replaced by its
+    /// encrypted form. Speaker profiles are encrypted too when
+    /// `output.encryption.profiles` is set.
+    Encrypt,
+
+    /// Apply the retention policy to old daily files
+    ///
+    /// Days older than `retention.keep_days` are archived, moved or
+    /// deleted, and days older than `retention.summaries_after_days` are
+    /// cut down to their summary. Runs automatically at day rollover when
+    /// `retention.auto` is set.
+    Prune {
+        /// Show what would change without touching any file
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Correct a word or phrase throughout past transcripts
+    ///
+    /// Replaces PATTERN with REPLACEMENT in the daily files, their JSONL
+    /// logs and summaries, and the search index. Shows the changes and asks
+    /// before writing them. The rule is recorded in `corrections.json` and
+    /// applied to new segments too. With VCS enabled, the result is
+    /// committed.
+    Fix(FixArgs),
+
+    /// Summarize a day with the language model in `summary`
+    ///
+    /// Writes a `## Summary` and `## Action items` section to the top of
+    /// the daily file, or to a sidecar with `summary.target: sidecar`.
+    /// Runs automatically for the finished day at day rollover when
+    /// `summary.auto` is set.
+    Summarize {
+        /// Day to summarize (YYYY-MM-DD)
+        ///
+        /// Defaults to today.
+        date: Option<NaiveDate>,
+
+        /// Replace an existing summary
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Switch dictation on or off in the running recorder
+    ///
+    /// Talks to `hoover record` over its control socket. Bind `hoover
+    /// dictate toggle` to a key for push-to-talk with a `dictation` sink
+    /// that has `push_to_talk: true`.
+    Dictate {
+        /// What to do with dictation
+        #[arg(value_enum, default_value_t = DictateAction::Toggle)]
+        action: DictateAction,
+    },
+
     /// Start the MCP server (stdio transport)
     ///
     /// Exposes transcription data over the Model Context Protocol,
@@ -145,6 +290,216 @@ enum Command {
     },
 }
 
+#[derive(clap::Args)]
+#[allow(clippy::struct_excessive_bools)]
+struct FixArgs {
+    /// Text to replace, matched as whole words
+    pattern: String,
+
+    /// What to replace it with
+    replacement: String,
+
+    /// Treat PATTERN as a regular expression; REPLACEMENT may use $1
+    #[arg(long)]
+    regex: bool,
+
+    /// Match regardless of case
+    #[arg(long, short)]
+    ignore_case: bool,
+
+    /// First day to correct (YYYY-MM-DD)
+    ///
+    /// Defaults to the first day there is.
+    #[arg(long)]
+    from: Option<NaiveDate>,
+
+    /// Last day to correct (YYYY-MM-DD)
+    ///
+    /// Defaults to the last day there is.
+    #[arg(long)]
+    to: Option<NaiveDate>,
+
+    /// Show the changes without writing them or recording the rule
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Apply without asking
+    #[arg(long, short)]
+    yes: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum DictateAction {
+    /// Start typing what is said
+    On,
+    /// Stop typing what is said
+    Off,
+    /// Switch between on and off
+    Toggle,
+    /// Print whether dictation is on
+    Status,
+}
+
+#[derive(Subcommand)]
+enum SpeakersAction {
+    /// Name an anonymous speaker cluster
+    ///
+    /// Promotes a cluster recorded with `speaker.cluster_unknown` (e.g.
+    /// "Speaker A") to an enrolled speaker profile, and rewrites the
+    /// cluster's labels in that day's transcript to the new name.
+    Name {
+        /// Cluster label, e.g. "A" or "Speaker A"
+        cluster: String,
+
+        /// Name to give the speaker
+        name: String,
+
+        /// Day the cluster was recorded (YYYY-MM-DD)
+        ///
+        /// Defaults to today.
+        #[arg(long)]
+        date: Option<NaiveDate>,
+    },
+
+    /// Score labelled recordings against the enrolled profiles
+    ///
+    /// Splits each file into speech windows, scores every window against
+    /// every enrolled profile, and reports a confusion matrix at the current
+    /// `speaker.min_confidence`, per-speaker score distributions, the equal
+    /// error rate and a recommended threshold. Include recordings of people
+    /// who are not enrolled to measure false accepts.
+    Test {
+        /// Labelled audio file as NAME=PATH (repeatable)
+        #[arg(long = "file", value_name = "NAME=PATH")]
+        files: Vec<String>,
+
+        /// Directory of NAME/*.wav subdirectories, one per speaker
+        #[arg(long)]
+        dir: Option<PathBuf>,
+
+        /// Write the recommended threshold to `speaker.min_confidence`
+        #[arg(long)]
+        write_config: bool,
+    },
+
+    /// Rename a speaker profile
+    Rename {
+        /// Current name
+        from: String,
+
+        /// New name
+        to: String,
+    },
+
+    /// Merge one speaker profile into another
+    ///
+    /// Adds the voice data of FROM to INTO and deletes FROM. Useful when the
+    /// same person was enrolled twice, or named from two clusters.
+    Merge {
+        /// Profile to merge and delete
+        from: String,
+
+        /// Profile to keep
+        into: String,
+    },
+
+    /// Export a speaker profile to a portable JSON file
+    Export {
+        /// Name of the profile to export
+        name: String,
+
+        /// Output file
+        ///
+        /// Defaults to NAME.json in the current directory.
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+
+    /// Import a speaker profile exported with `hoover speakers export`
+    Import {
+        /// Exported profile file
+        path: PathBuf,
+
+        /// Save under this name instead of the one in the file
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Replace an existing profile with the same name
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Freeze a profile so continuous training no longer changes it
+    Lock {
+        /// Name of the profile to lock
+        name: String,
+    },
+
+    /// Re-enable continuous training for a locked profile
+    Unlock {
+        /// Name of the profile to unlock
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum IndexAction {
+    /// Rebuild the index from the existing daily files
+    ///
+    /// Reads every day's JSONL log when present, or its markdown otherwise,
+    /// and replaces the contents of the index.
+    Rebuild,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormatArg {
+    /// SRT subtitles
+    Srt,
+    /// VTT subtitles with speaker voice tags
+    Vtt,
+    /// Plain text, one line per segment
+    Txt,
+    /// Standalone HTML page
+    Html,
+    /// JSON array of segments
+    Json,
+}
+
+impl From<ExportFormatArg> for ExportFormat {
+    fn from(format: ExportFormatArg) -> Self {
+        match format {
+            ExportFormatArg::Srt => Self::Srt,
+            ExportFormatArg::Vtt => Self::Vtt,
+            ExportFormatArg::Txt => Self::Txt,
+            ExportFormatArg::Html => Self::Html,
+            ExportFormatArg::Json => Self::Json,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum EmitFormatArg {
+    /// `HH:MM:SS Speaker: text`
+    Text,
+    /// One JSON object per segment, as in the JSONL log
+    Jsonl,
+}
+
+impl From<EmitFormatArg> for StdoutFormat {
+    fn from(format: EmitFormatArg) -> Self {
+        match format {
+            EmitFormatArg::Text => Self::Text,
+            EmitFormatArg::Jsonl => Self::Jsonl,
+        }
+    }
+}
+
+fn parse_time_of_day(value: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(value, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(value, "%H:%M:%S"))
+        .map_err(|_| format!("expected HH:MM, got {value:?}"))
+}
+
 fn load_config(cli: &Cli) -> Result<Config, HooverError> {
     let path = cli.config.clone().unwrap_or_else(Config::default_path);
     Config::load(&path)
@@ -161,7 +516,11 @@ fn init_logging(verbose: bool) {
         EnvFilter::new("hoover=info,warn")
     };
 
-    tracing_subscriber::fmt().with_env_filter(filter).init();
+    // Logs go to stderr so they never mix with the `stdout` output sink.
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .init();
 }
 
 fn main() {
@@ -310,20 +669,24 @@ fn run_devices(cli: &Cli, set: Option<&str>, pick: bool) -> Result<(), HooverErr
 
 fn run_with_config(cli: Cli) -> Result<(), HooverError> {
     let config = load_config(&cli)?;
+    let path = config_path(&cli);
 
     match cli.command {
-        Command::Record => {
-            let rt = tokio::runtime::Runtime::new()?;
-            rt.block_on(hoover::recording::run_recording(config))
-        }
+        Command::Record { emit, no_files } => run_record(config, emit, no_files),
         Command::Push => hoover::vcs::push(&config),
         Command::Trigger => {
             let rt = tokio::runtime::Runtime::new()?;
             rt.block_on(hoover::vcs::trigger(&config))
         }
-        Command::Enroll { name } => {
+        Command::Enroll {
+            name,
+            files,
+            append,
+        } => {
             let rt = tokio::runtime::Runtime::new()?;
-            rt.block_on(hoover::speaker::enroll::run_enrollment(&config, &name))
+            rt.block_on(hoover::speaker::enroll::run_enrollment(
+                &config, &name, &files, append,
+            ))
         }
         Command::Send {
             target,
@@ -342,13 +705,256 @@ fn run_with_config(cli: Cli) -> Result<(), HooverError> {
             let rt = tokio::runtime::Runtime::new()?;
             rt.block_on(hoover::mcp::run_mcp_server(config))
         }
-        Command::Speakers { remove } => run_speakers(&config, remove.as_deref()),
+        Command::Speakers { action, remove } => {
+            run_speakers(&config, &path, action, remove.as_deref())
+        }
+        Command::Index {
+            action: IndexAction::Rebuild,
+        } => {
+            let (index_path, summary) = hoover::index::rebuild_index(&config)?;
+            println!(
+                "Indexed {} segments from {} daily files into {}",
+                summary.segments,
+                summary.days,
+                index_path.display()
+            );
+            Ok(())
+        }
+        Command::Export {
+            from,
+            to,
+            format,
+            since,
+            until,
+            speakers,
+            output,
+        } => {
+            let from = match from {
+                Some(from) => from,
+                None => Calendar::new(&config.output)?.today(),
+            };
+            let filter = ExportFilter {
+                from,
+                to: to.unwrap_or(from),
+                since,
+                until,
+                speakers,
+            };
+            run_export(&config, &filter, format.into(), output.as_deref())
+        }
+        Command::Cat { date } => run_cat(&config, date),
+        Command::Encrypt => {
+            let sealed = hoover::vault::seal_existing(&config)?;
+            println!("Encrypted {sealed} files");
+            Ok(())
+        }
+        Command::Prune { dry_run } => run_prune(&config, dry_run),
+        Command::Summarize { date, force } => run_summarize(&config, date, force),
+        Command::Fix(args) => run_fix(&config, args),
+        Command::Dictate { action } => {
+            let action = match action {
+                DictateAction::On => "on",
+                DictateAction::Off => "off",
+                DictateAction::Toggle => "toggle",
+                DictateAction::Status => "status",
+            };
+            let reply = hoover::control::send(
+                &hoover::control::socket_path()?,
+                &format!("dictation {action}"),
+            )?;
+            println!("{reply}");
+            Ok(())
+        }
         Command::Devices { .. } | Command::Init | Command::Completions { .. } => unreachable!(),
     }
 }
 
-fn run_speakers(config: &Config, remove: Option<&str>) -> Result<(), HooverError> {
+fn run_export(
+    config: &Config,
+    filter: &ExportFilter,
+    format: ExportFormat,
+    output: Option<&std::path::Path>,
+) -> Result<(), HooverError> {
+    let segments = hoover::export::collect_segments(&config.output, filter)?;
+    let rendered = hoover::export::render(&segments, format)?;
+
+    if let Some(path) = output {
+        std::fs::write(path, rendered)?;
+        eprintln!("Exported {} segments to {}", segments.len(), path.display());
+    } else {
+        std::io::stdout().write_all(rendered.as_bytes())?;
+    }
+    Ok(())
+}
+
+fn run_cat(config: &Config, date: Option<NaiveDate>) -> Result<(), HooverError> {
+    let date = match date {
+        Some(date) => date,
+        None => Calendar::new(&config.output)?.today(),
+    };
+    let layout = Layout::new(&config.output.layout)?;
+    let path = layout.day_file(&Config::expand_path(&config.output.directory), date);
+    let content = Vault::new(&config.output)?
+        .read(&path)?
+        .ok_or_else(|| HooverError::Output(format!("no transcript for {date}")))?;
+    std::io::stdout().write_all(content.as_bytes())?;
+    Ok(())
+}
+
+fn run_record(
+    mut config: Config,
+    emit: Option<EmitFormatArg>,
+    no_files: bool,
+) -> Result<(), HooverError> {
+    config.output.override_sinks(emit.map(Into::into), no_files);
+    if config.output.sinks().is_empty() {
+        return Err(HooverError::Config(
+            "--no-files leaves no output sinks; add --emit or configure output.sinks".to_string(),
+        ));
+    }
+    let rt = tokio::runtime::Runtime::new()?;
+    rt.block_on(hoover::recording::run_recording(config))
+}
+
+fn run_summarize(config: &Config, date: Option<NaiveDate>, force: bool) -> Result<(), HooverError> {
+    let date = match date {
+        Some(date) => date,
+        None => Calendar::new(&config.output)?.today(),
+    };
+    let written = hoover::summary::summarize_day(config, date, force)?;
+    if written.is_empty() {
+        println!("Nothing to summarize for {date}; use --force to replace a summary");
+        return Ok(());
+    }
+    for path in &written {
+        println!("Summarized {date} into {}", path.display());
+    }
+    hoover::vcs::auto_commit(config)
+}
+
+fn run_prune(config: &Config, dry_run: bool) -> Result<(), HooverError> {
+    let today = Calendar::new(&config.output)?.today();
+    let report = hoover::retention::prune(config, today, dry_run)?;
+    if report.is_empty() && report.unsummarized.is_empty() {
+        println!("Nothing to prune");
+        return Ok(());
+    }
+
+    let (expired, summarized) = if dry_run {
+        ("Would prune", "Would summarize")
+    } else {
+        ("Pruned", "Summarized")
+    };
+    let list = |dates: &[NaiveDate]| {
+        dates
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    if !report.expired.is_empty() {
+        println!(
+            "{expired} {} days: {}",
+            report.expired.len(),
+            list(&report.expired)
+        );
+    }
+    for archive in &report.archives {
+        println!("  into {}", archive.display());
+    }
+    if !report.summarized.is_empty() {
+        println!(
+            "{summarized} {} days: {}",
+            report.summarized.len(),
+            list(&report.summarized)
+        );
+    }
+    if !report.unsummarized.is_empty() {
+        println!(
+            "Kept {} days without a summary: {}",
+            report.unsummarized.len(),
+            list(&report.unsummarized)
+        );
+    }
+    if report.rewritten_commits > 0 {
+        println!(
+            "Rewrote {} commits; push with --force to update the remote",
+            report.rewritten_commits
+        );
+    }
+    Ok(())
+}
+
+fn run_fix(config: &Config, args: FixArgs) -> Result<(), HooverError> {
+    let FixArgs {
+        pattern,
+        replacement,
+        regex,
+        ignore_case,
+        from,
+        to,
+        dry_run,
+        yes,
+    } = args;
+    let rule = CorrectionRule {
+        pattern,
+        replacement,
+        regex,
+        ignore_case,
+    };
+    let correction = Correction::new(rule)?;
+    let changes = corrections::plan(config, &correction, from, to)?;
+    for change in &changes {
+        println!("--- {}", change.path.display());
+        for line in &change.lines {
+            println!("{:>5} - {}", line.line, line.before);
+            println!("{:>5} + {}", line.line, line.after);
+        }
+    }
+
+    let days: BTreeSet<NaiveDate> = changes.iter().map(|change| change.date).collect();
+    let lines: usize = changes.iter().map(|change| change.lines.len()).sum();
+    if changes.is_empty() {
+        println!("No matches");
+    } else {
+        println!(
+            "{lines} lines in {} files over {} days",
+            changes.len(),
+            days.len()
+        );
+    }
+    if dry_run {
+        return Ok(());
+    }
+    let question = if changes.is_empty() {
+        "Record the rule for new segments?"
+    } else {
+        "Apply these changes and record the rule for new segments?"
+    };
+    if !yes && !prompt_yes_no(question, false)? {
+        return Ok(());
+    }
+
+    let days = corrections::apply(config, &correction, &changes)?;
+    if !days.is_empty() {
+        println!("Corrected {} days", days.len());
+    }
+    println!("Recorded the rule in {}", corrections::RULES_FILE);
+    Ok(())
+}
+
+fn run_speakers(
+    config: &Config,
+    config_path: &std::path::Path,
+    action: Option<SpeakersAction>,
+    remove: Option<&str>,
+) -> Result<(), HooverError> {
     let profiles_dir = Config::expand_path(&config.speaker.profiles_dir);
+    let vault = Vault::for_profiles(&config.output)?;
+
+    if let Some(action) = action {
+        return run_speakers_action(config, config_path, &profiles_dir, &vault, action);
+    }
 
     if let Some(name) = remove {
         hoover::speaker::enroll::remove_profile(&profiles_dir, name)?;
@@ -356,7 +962,7 @@ fn run_speakers(config: &Config, remove: Option<&str>) -> Result<(), HooverError
         return Ok(());
     }
 
-    let names = hoover::speaker::enroll::list_profiles(&profiles_dir)?;
+    let names = hoover::speaker::enroll::list_profiles(&profiles_dir, &vault)?;
     if names.is_empty() {
         println!("No enrolled speakers. Use `hoover enroll <name>` to add one.");
     } else {
@@ -365,6 +971,99 @@ fn run_speakers(config: &Config, remove: Option<&str>) -> Result<(), HooverError
             println!("  {name}");
         }
     }
+
+    let today = Calendar::new(&config.output)?.today();
+    let clusters = hoover::speaker::cluster::list_clusters(&profiles_dir, &vault, today)?;
+    if !clusters.is_empty() {
+        println!("Unnamed speakers today (name with `hoover speakers name <cluster> <name>`):");
+        for cluster in &clusters {
+            println!("  {} ({} segments)", cluster.display_name(), cluster.count);
+        }
+    }
+    Ok(())
+}
+
+fn run_speakers_action(
+    config: &Config,
+    config_path: &std::path::Path,
+    profiles_dir: &std::path::Path,
+    vault: &Vault,
+    action: SpeakersAction,
+) -> Result<(), HooverError> {
+    match action {
+        SpeakersAction::Name {
+            cluster,
+            name,
+            date,
+        } => {
+            let date = match date {
+                Some(date) => date,
+                None => Calendar::new(&config.output)?.today(),
+            };
+            let rewritten =
+                hoover::speaker::cluster::promote_cluster(config, date, &cluster, &name)?;
+            println!("Saved speaker profile: {name}");
+            println!("Rewrote {rewritten} transcript labels for {date}");
+        }
+        SpeakersAction::Test {
+            files,
+            dir,
+            write_config,
+        } => {
+            let inputs =
+                hoover::speaker::calibrate::collect_labelled_files(&files, dir.as_deref())?;
+            let report = hoover::speaker::calibrate::run_speaker_test(config, &inputs)?;
+            print!("{report}");
+            if write_config {
+                let eer = report.equal_error_rate().ok_or_else(|| {
+                    HooverError::Speaker(
+                        "cannot recommend a threshold from this test audio".to_string(),
+                    )
+                })?;
+                Config::set_speaker_min_confidence(config_path, eer.threshold)?;
+                println!(
+                    "Set speaker.min_confidence to {:.2} in {}",
+                    eer.threshold,
+                    config_path.display()
+                );
+            }
+        }
+        SpeakersAction::Rename { from, to } => {
+            hoover::speaker::manage::rename_profile(profiles_dir, vault, &from, &to)?;
+            println!("Renamed speaker profile: {from} -> {to}");
+        }
+        SpeakersAction::Merge { from, into } => {
+            let merged =
+                hoover::speaker::manage::merge_profiles(profiles_dir, vault, &from, &into)?;
+            println!(
+                "Merged {from} into {into} ({} centroid(s))",
+                merged.centroids.len()
+            );
+        }
+        SpeakersAction::Export { name, output } => {
+            let output = output.unwrap_or_else(|| PathBuf::from(format!("{name}.json")));
+            hoover::speaker::manage::export_profile(profiles_dir, vault, &name, &output)?;
+            println!("Exported speaker profile {name} to {}", output.display());
+        }
+        SpeakersAction::Import { path, name, force } => {
+            let name = hoover::speaker::manage::import_profile(
+                profiles_dir,
+                vault,
+                &path,
+                name.as_deref(),
+                force,
+            )?;
+            println!("Imported speaker profile: {name}");
+        }
+        SpeakersAction::Lock { name } => {
+            hoover::speaker::manage::set_locked(profiles_dir, vault, &name, true)?;
+            println!("Locked speaker profile: {name}");
+        }
+        SpeakersAction::Unlock { name } => {
+            hoover::speaker::manage::set_locked(profiles_dir, vault, &name, false)?;
+            println!("Unlocked speaker profile: {name}");
+        }
+    }
     Ok(())
 }
 
@@ -583,14 +1282,17 @@ fn run_init(cli: &Cli) -> Result<(), HooverError> {
             Value::String("enabled".to_string()),
             Value::Bool(true),
         );
-        let filter = prompt_yes_no(
-            "Filter out unrecognized speakers?",
-            false,
-        )?;
-        if filter {
+        let policy = loop {
+            let policy = prompt_default("Unrecognized speakers (keep, drop, redact)", "keep")?;
+            if matches!(policy.as_str(), "keep" | "drop" | "redact") {
+                break policy;
+            }
+            println!("Please enter keep, drop or redact.");
+        };
+        if policy != "keep" {
             speaker.insert(
-                Value::String("filter_unknown".to_string()),
-                Value::Bool(true),
+                Value::String("unknown_policy".to_string()),
+                Value::String(policy),
             );
         }
     }
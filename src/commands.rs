@@ -0,0 +1,547 @@
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+
+use crate::config::{CommandAction, Config};
+use crate::error::Result;
+use crate::output::calendar::Calendar;
+use crate::output::{ChunkContext, Marker};
+use crate::speaker::AttributedSegment;
+use crate::vault::Vault;
+
+/// How long a turn is held back for `scratch that` when nothing follows it.
+pub const HOLD_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// What the command stage passes on to the output sinks, in order.
+#[derive(Debug, Clone)]
+pub enum Output {
+    /// Turns from one chunk.
+    Turns(ChunkContext, Vec<AttributedSegment>),
+    /// A marker placed by the command in the turn.
+    Marker(Marker, AttributedSegment),
+}
+
+/// A trigger phrase found in a turn's text.
+struct Found {
+    action: CommandAction,
+    /// Byte offset of the phrase's first word.
+    start: usize,
+    /// Byte offset just past the phrase's last word.
+    end: usize,
+}
+
+/// Recognizes spoken commands in transcribed turns, carries them out and
+/// strips them from the transcript.
+///
+/// With a `scratch` trigger configured, the latest turn is held back until
+/// the next one arrives, [`HOLD_TIMEOUT`] passes or recording stops, so that
+/// `scratch that` can still drop it.
+pub struct VoiceCommands {
+    /// Each phrase as lowercase words.
+    triggers: Vec<(Vec<String>, CommandAction)>,
+    todo_file: PathBuf,
+    notes_file: PathBuf,
+    pause_minutes: u32,
+    calendar: Calendar,
+    vault: Vault,
+    holds: bool,
+    held: Option<(ChunkContext, AttributedSegment, Instant)>,
+    /// Start and end of the current pause.
+    paused: Option<(DateTime<Utc>, DateTime<Utc>)>,
+}
+
+impl VoiceCommands {
+    /// Build the recognizer configured in `commands`; it passes every turn
+    /// through unchanged unless `commands.enabled` is set.
+    pub fn new(config: &Config) -> Result<Self> {
+        let commands = &config.commands;
+        let triggers: Vec<_> = if commands.enabled {
+            commands
+                .triggers
+                .iter()
+                .map(|trigger| {
+                    let words = words(&trigger.phrase)
+                        .into_iter()
+                        .map(|(start, end)| trigger.phrase[start..end].to_lowercase())
+                        .collect::<Vec<_>>();
+                    (words, trigger.action)
+                })
+                .filter(|(words, _)| !words.is_empty())
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let output_dir = Config::expand_path(&config.output.directory);
+
+        Ok(Self {
+            holds: triggers
+                .iter()
+                .any(|(_, action)| *action == CommandAction::Scratch),
+            triggers,
+            todo_file: output_dir.join(Config::expand_path(&commands.todo_file)),
+            notes_file: output_dir.join(Config::expand_path(&commands.notes_file)),
+            pause_minutes: commands.pause_minutes,
+            calendar: Calendar::new(&config.output)?,
+            vault: Vault::new(&config.output)?,
+            held: None,
+            paused: None,
+        })
+    }
+
+    /// Whether audio from `at` falls in a pause and must be dropped.
+    pub fn is_paused(&mut self, at: DateTime<Utc>) -> bool {
+        let Some((start, end)) = self.paused else {
+            return false;
+        };
+        if at >= end {
+            tracing::info!("pause over, recording again");
+            self.paused = None;
+            return false;
+        }
+        at >= start
+    }
+
+    /// Handle the commands in a batch of turns, returning what is to be
+    /// written.
+    pub fn process(
+        &mut self,
+        context: &ChunkContext,
+        turns: Vec<AttributedSegment>,
+    ) -> Vec<Output> {
+        let mut outputs = Vec::new();
+        for turn in turns {
+            if self.is_paused(turn.segment.timestamp) {
+                continue;
+            }
+            self.process_turn(context, turn, &mut outputs);
+        }
+        outputs
+    }
+
+    /// Release a turn held back for longer than [`HOLD_TIMEOUT`].
+    pub fn release_idle(&mut self) -> Vec<Output> {
+        if self
+            .held
+            .as_ref()
+            .is_some_and(|(_, _, since)| since.elapsed() >= HOLD_TIMEOUT)
+        {
+            self.finish()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Release the turn held back, if any.
+    pub fn finish(&mut self) -> Vec<Output> {
+        let mut outputs = Vec::new();
+        if let Some((context, turn, _)) = self.held.take() {
+            push_turn(&mut outputs, &context, turn);
+        }
+        outputs
+    }
+
+    fn process_turn(
+        &mut self,
+        context: &ChunkContext,
+        turn: AttributedSegment,
+        outputs: &mut Vec<Output>,
+    ) {
+        if turn.redacted {
+            self.pass(context, turn, outputs);
+            return;
+        }
+
+        let punctuation = |c: char| is_separator(c) || matches!(c, '.' | '!' | '?');
+        let mut text = turn.segment.text.clone();
+        while let Some(found) = self.find(&text) {
+            let before = text[..found.start].trim_end_matches(is_separator);
+            let rest = text[found.end..]
+                .trim_start_matches(punctuation)
+                .to_string();
+            if found.action != CommandAction::Scratch {
+                if !before.is_empty() {
+                    self.pass(context, with_text(&turn, before), outputs);
+                }
+                self.run(
+                    found.action,
+                    rest.trim_end_matches(punctuation),
+                    &turn,
+                    outputs,
+                );
+                return;
+            }
+
+            if before.is_empty() {
+                if self.held.take().is_some() {
+                    tracing::info!("scratched the previous segment");
+                }
+            } else {
+                let kept = without_last_sentence(before);
+                if !kept.is_empty() {
+                    self.pass(context, with_text(&turn, kept), outputs);
+                }
+            }
+            text = rest;
+        }
+
+        if !text.is_empty() {
+            self.pass(context, with_text(&turn, &text), outputs);
+        }
+    }
+
+    /// Pass a turn on, holding it back in place of the one held before.
+    fn pass(&mut self, context: &ChunkContext, turn: AttributedSegment, outputs: &mut Vec<Output>) {
+        if !self.holds {
+            push_turn(outputs, context, turn);
+            return;
+        }
+        if let Some((context, turn, _)) = self.held.replace((context.clone(), turn, Instant::now()))
+        {
+            push_turn(outputs, &context, turn);
+        }
+    }
+
+    fn run(
+        &mut self,
+        action: CommandAction,
+        argument: &str,
+        turn: &AttributedSegment,
+        outputs: &mut Vec<Output>,
+    ) {
+        let at = self
+            .calendar
+            .local(turn.segment.timestamp)
+            .format("%Y-%m-%d %H:%M");
+        let result = match action {
+            CommandAction::Todo | CommandAction::Note if argument.is_empty() => {
+                tracing::warn!("ignoring {action:?} command without text");
+                Ok(())
+            }
+            CommandAction::Todo => self
+                .vault
+                .append(&self.todo_file, &format!("- [ ] {argument} ({at})\n")),
+            CommandAction::Note => {
+                let speaker = turn
+                    .speaker
+                    .as_deref()
+                    .map_or_else(String::new, |name| format!("{name}: "));
+                self.vault
+                    .append(&self.notes_file, &format!("- {at} {speaker}{argument}\n"))
+            }
+            CommandAction::Bookmark | CommandAction::Tag => {
+                let marker = if action == CommandAction::Bookmark {
+                    Marker::Bookmark(argument.to_string())
+                } else {
+                    let tag = tag_name(argument);
+                    if tag.is_empty() {
+                        tracing::warn!("ignoring tag command without a tag");
+                        return;
+                    }
+                    Marker::Tag(tag)
+                };
+                // Keep the marker after the turn it follows.
+                outputs.extend(self.finish());
+                outputs.push(Output::Marker(marker, turn.clone()));
+                Ok(())
+            }
+            CommandAction::Scratch => Ok(()),
+            CommandAction::Pause => {
+                let minutes = spoken_minutes(argument).unwrap_or(self.pause_minutes);
+                let start = turn.segment.timestamp;
+                self.paused = Some((start, start + chrono::Duration::minutes(minutes.into())));
+                tracing::info!("pausing recording for {minutes} minutes");
+                Ok(())
+            }
+        };
+        if let Err(e) = result {
+            tracing::error!("{action:?} command failed: {e}");
+        }
+    }
+
+    /// The earliest trigger phrase in `text`, the longest one on a tie.
+    fn find(&self, text: &str) -> Option<Found> {
+        let spans = words(text);
+        let lower: Vec<String> = spans
+            .iter()
+            .map(|&(start, end)| text[start..end].to_lowercase())
+            .collect();
+        (0..lower.len()).find_map(|i| {
+            self.triggers
+                .iter()
+                .filter(|(phrase, _)| lower[i..].starts_with(phrase))
+                .max_by_key(|(phrase, _)| phrase.len())
+                .map(|(phrase, action)| Found {
+                    action: *action,
+                    start: spans[i].0,
+                    end: spans[i + phrase.len() - 1].1,
+                })
+        })
+    }
+}
+
+/// Append `turn` to the outputs, batched with the turns before it from the
+/// same chunk.
+fn push_turn(outputs: &mut Vec<Output>, context: &ChunkContext, turn: AttributedSegment) {
+    if let Some(Output::Turns(last, turns)) = outputs.last_mut()
+        && last.chunk_id == context.chunk_id
+    {
+        turns.push(turn);
+        return;
+    }
+    outputs.push(Output::Turns(context.clone(), vec![turn]));
+}
+
+fn with_text(turn: &AttributedSegment, text: &str) -> AttributedSegment {
+    let mut turn = turn.clone();
+    turn.segment.text = text.to_string();
+    turn
+}
+
+/// Byte ranges of the words in `text`: runs of letters, digits and
+/// apostrophes.
+fn words(text: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        let in_word = c.is_alphanumeric() || (c == '\'' && start.is_some());
+        match (in_word, start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                spans.push((s, i));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        spans.push((s, text.len()));
+    }
+    spans
+}
+
+/// Whether `c` separates a trigger phrase from the text around it, such as
+/// the comma in "Hoover, note: ...".
+const fn is_separator(c: char) -> bool {
+    c.is_whitespace() || matches!(c, ',' | ':' | ';' | '-' | '—')
+}
+
+/// `text` without its last sentence, for `scratch that` after it in the same
+/// turn.
+fn without_last_sentence(text: &str) -> &str {
+    let text = text.trim_end_matches(|c: char| c.is_whitespace() || matches!(c, '.' | '!' | '?'));
+    text.rfind(['.', '!', '?'])
+        .map_or("", |i| text[..=i].trim())
+}
+
+/// The argument of a tag command as a tag: lowercase words joined by dashes.
+fn tag_name(argument: &str) -> String {
+    words(argument)
+        .into_iter()
+        .map(|(start, end)| argument[start..end].to_lowercase())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// A duration spoken after `pause`, in minutes: "for 20 minutes", "five",
+/// "an hour".
+fn spoken_minutes(argument: &str) -> Option<u32> {
+    const NUMBERS: [&str; 21] = [
+        "zero",
+        "one",
+        "two",
+        "three",
+        "four",
+        "five",
+        "six",
+        "seven",
+        "eight",
+        "nine",
+        "ten",
+        "eleven",
+        "twelve",
+        "thirteen",
+        "fourteen",
+        "fifteen",
+        "sixteen",
+        "seventeen",
+        "eighteen",
+        "nineteen",
+        "twenty",
+    ];
+    let words: Vec<String> = words(argument)
+        .into_iter()
+        .map(|(start, end)| argument[start..end].to_lowercase())
+        .collect();
+    words.iter().enumerate().find_map(|(i, word)| {
+        let unit = words.get(i + 1).map(String::as_str);
+        let hours = unit.is_some_and(|unit| unit.starts_with("hour"));
+        let number = word
+            .parse::<u32>()
+            .ok()
+            .or_else(|| {
+                NUMBERS
+                    .iter()
+                    .position(|n| n == word)
+                    .and_then(|n| u32::try_from(n).ok())
+            })
+            .or_else(|| match word.as_str() {
+                "thirty" => Some(30),
+                "forty" => Some(40),
+                "fifty" => Some(50),
+                "sixty" => Some(60),
+                "a" | "an" if hours || unit.is_some_and(|unit| unit.starts_with("min")) => Some(1),
+                _ => None,
+            })?;
+        Some(if hours { number * 60 } else { number })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stt::TranscriptionSegment;
+
+    fn commands(dir: &std::path::Path) -> VoiceCommands {
+        let yaml = format!(
+            "output:\n  directory: {}\ncommands:\n  enabled: true\n",
+            dir.display()
+        );
+        let config: Config = serde_yaml_ng::from_str(&yaml).unwrap_or_else(|e| panic!("{e}"));
+        VoiceCommands::new(&config).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    fn turn(text: &str, at: DateTime<Utc>) -> AttributedSegment {
+        let mut turn = AttributedSegment::unattributed(TranscriptionSegment {
+            text: text.to_string(),
+            timestamp: at,
+            duration_secs: 1.0,
+            confidence: None,
+        });
+        turn.speaker = Some("Alice".to_string());
+        turn
+    }
+
+    fn context(id: &str) -> ChunkContext {
+        ChunkContext {
+            chunk_id: id.to_string(),
+            ..ChunkContext::default()
+        }
+    }
+
+    /// The text of every turn written, with markers shown in brackets.
+    fn written(outputs: &[Output]) -> Vec<String> {
+        outputs
+            .iter()
+            .flat_map(|output| match output {
+                Output::Turns(_, turns) => turns.iter().map(|t| t.segment.text.clone()).collect(),
+                Output::Marker(marker, _) => vec![format!("[{marker:?}]")],
+            })
+            .collect()
+    }
+
+    #[test]
+    fn scratch_that_drops_the_previous_segment() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let mut commands = commands(dir.path());
+        let now = Utc::now();
+
+        let mut outputs = commands.process(&context("1"), vec![turn("We meet on Monday.", now)]);
+        assert!(outputs.is_empty());
+        outputs.extend(commands.process(
+            &context("2"),
+            vec![turn("Scratch that. We meet on Tuesday.", now)],
+        ));
+        outputs.extend(commands.process(
+            &context("3"),
+            vec![turn(
+                "Lunch at noon. Dinner at six, scratch that! Done.",
+                now,
+            )],
+        ));
+        outputs.extend(commands.finish());
+        assert_eq!(
+            written(&outputs),
+            ["We meet on Tuesday.", "Lunch at noon.", "Done."]
+        );
+    }
+
+    #[test]
+    fn notes_and_todos_are_appended_to_their_files() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let mut commands = commands(dir.path());
+        let now = Utc::now();
+
+        let mut outputs = commands.process(
+            &context("1"),
+            vec![
+                turn("That's settled. Hoover, to-do: send the invoice.", now),
+                turn("Hoover note the printer is broken", now),
+            ],
+        );
+        outputs.extend(commands.finish());
+        assert_eq!(written(&outputs), ["That's settled."]);
+
+        let todo =
+            std::fs::read_to_string(dir.path().join("todo.md")).unwrap_or_else(|e| panic!("{e}"));
+        assert!(todo.starts_with("- [ ] send the invoice ("));
+        let notes =
+            std::fs::read_to_string(dir.path().join("notes.md")).unwrap_or_else(|e| panic!("{e}"));
+        assert!(notes.ends_with(" Alice: the printer is broken\n"));
+    }
+
+    #[test]
+    fn markers_follow_the_text_before_them() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let mut commands = commands(dir.path());
+        let now = Utc::now();
+
+        let mut outputs = commands.process(
+            &context("1"),
+            vec![
+                turn("Next topic. Hoover bookmark budget review.", now),
+                turn("Hoover tag Q4 planning", now),
+            ],
+        );
+        outputs.extend(commands.finish());
+        assert_eq!(
+            written(&outputs),
+            [
+                "Next topic.".to_string(),
+                format!("[{:?}]", Marker::Bookmark("budget review".to_string())),
+                format!("[{:?}]", Marker::Tag("q4-planning".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn pause_drops_what_follows() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let mut commands = commands(dir.path());
+        let now = Utc::now();
+        let later = |minutes| now + chrono::Duration::minutes(minutes);
+
+        let mut outputs = commands.process(
+            &context("1"),
+            vec![turn("Hoover, pause for five minutes.", now)],
+        );
+        assert!(commands.is_paused(later(4)));
+        outputs.extend(commands.process(&context("2"), vec![turn("private", later(2))]));
+        assert!(!commands.is_paused(later(5)));
+        outputs.extend(commands.process(&context("3"), vec![turn("back", later(6))]));
+        outputs.extend(commands.finish());
+        assert_eq!(written(&outputs), ["back"]);
+
+        assert_eq!(spoken_minutes("for 20 minutes"), Some(20));
+        assert_eq!(spoken_minutes("for an hour"), Some(60));
+        assert_eq!(spoken_minutes("for a bit"), None);
+    }
+
+    #[test]
+    fn disabled_commands_pass_everything() {
+        let config: Config = serde_yaml_ng::from_str("{}").unwrap_or_else(|e| panic!("{e}"));
+        let mut commands = VoiceCommands::new(&config).unwrap_or_else(|e| panic!("{e}"));
+        let outputs = commands.process(&context("1"), vec![turn("Scratch that.", Utc::now())]);
+        assert_eq!(written(&outputs), ["Scratch that."]);
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 use serde::Deserialize;
@@ -44,6 +45,10 @@ const fn default_min_confidence() -> f32 {
     0.7
 }
 
+const fn default_cluster_threshold() -> f32 {
+    0.6
+}
+
 fn default_output_directory() -> String {
     let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
     home.join("hoover").to_string_lossy().to_string()
@@ -100,12 +105,24 @@ pub struct Config {
     #[serde(default)]
     pub speaker: SpeakerConfig,
 
+    #[serde(default)]
+    pub redaction: RedactionConfig,
+
+    #[serde(default)]
+    pub commands: CommandsConfig,
+
     #[serde(default)]
     pub output: OutputConfig,
 
     #[serde(default)]
     pub vcs: VcsConfig,
 
+    #[serde(default)]
+    pub summary: SummaryConfig,
+
+    #[serde(default)]
+    pub retention: RetentionConfig,
+
     #[serde(default)]
     pub udp: UdpConfig,
 
@@ -197,6 +214,18 @@ impl Default for SttConfig {
     }
 }
 
+/// What to do with speech from voices that match no enrolled profile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnknownPolicy {
+    /// Leave the segment out of the transcript entirely.
+    Drop,
+    /// Write a placeholder with timestamp and duration, never the words.
+    Redact,
+    /// Transcribe the segment as usual.
+    Keep,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct SpeakerConfig {
     #[serde(default)]
@@ -208,9 +237,24 @@ pub struct SpeakerConfig {
     #[serde(default = "default_min_confidence")]
     pub min_confidence: f32,
 
+    /// Deprecated shorthand for `unknown_policy: drop`.
     #[serde(default)]
     pub filter_unknown: bool,
 
+    /// Handling of voices that match no enrolled profile.  Defaults to `drop`
+    /// when `filter_unknown` is set, `keep` otherwise.
+    #[serde(default)]
+    pub unknown_policy: Option<UnknownPolicy>,
+
+    /// Group unmatched voices into anonymous `Speaker A`, `Speaker B`, ...
+    /// labels that stay stable for the day.
+    #[serde(default)]
+    pub cluster_unknown: bool,
+
+    /// Cosine similarity required to join an existing anonymous cluster.
+    #[serde(default = "default_cluster_threshold")]
+    pub cluster_threshold: f32,
+
     pub model_path: Option<String>,
 }
 
@@ -221,11 +265,27 @@ impl Default for SpeakerConfig {
             profiles_dir: default_profiles_dir(),
             min_confidence: default_min_confidence(),
             filter_unknown: false,
+            unknown_policy: None,
+            cluster_unknown: false,
+            cluster_threshold: default_cluster_threshold(),
             model_path: None,
         }
     }
 }
 
+impl SpeakerConfig {
+    /// The effective policy for unmatched voices, honouring the older
+    /// `filter_unknown` flag when `unknown_policy` is not set.
+    #[must_use]
+    pub const fn unknown_policy(&self) -> UnknownPolicy {
+        match self.unknown_policy {
+            Some(policy) => policy,
+            None if self.filter_unknown => UnknownPolicy::Drop,
+            None => UnknownPolicy::Keep,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct OutputConfig {
     #[serde(default = "default_output_directory")]
@@ -233,6 +293,38 @@ pub struct OutputConfig {
 
     #[serde(default = "default_true")]
     pub timestamps: bool,
+
+    /// Send segments from particular speakers to other files or sections.
+    #[serde(default)]
+    pub routes: Vec<OutputRoute>,
+
+    /// Shorthand for adding a `jsonl` sink when `sinks` is not set.
+    #[serde(default)]
+    pub jsonl: bool,
+
+    /// Outputs every segment is written to.  Defaults to `markdown`, plus
+    /// `jsonl` when `jsonl` is set.
+    #[serde(default)]
+    pub sinks: Option<Vec<SinkConfig>>,
+
+    /// File naming and formatting of the daily markdown files.
+    #[serde(default)]
+    pub layout: LayoutConfig,
+
+    /// IANA time zone used to date and time segments, e.g.
+    /// `Europe/Berlin`.  Defaults to the system time zone.
+    #[serde(default)]
+    pub timezone: Option<String>,
+
+    /// Time of day (`HH:MM`) a new daily file starts.  Segments before it
+    /// belong to the previous day.
+    #[serde(default = "default_day_starts_at")]
+    pub day_starts_at: String,
+
+    /// Encrypt daily files and logs (and optionally speaker profiles) at
+    /// rest.
+    #[serde(default)]
+    pub encryption: Option<EncryptionConfig>,
 }
 
 impl Default for OutputConfig {
@@ -240,10 +332,477 @@ impl Default for OutputConfig {
         Self {
             directory: default_output_directory(),
             timestamps: true,
+            routes: Vec::new(),
+            jsonl: false,
+            sinks: None,
+            layout: LayoutConfig::default(),
+            timezone: None,
+            day_starts_at: default_day_starts_at(),
+            encryption: None,
+        }
+    }
+}
+
+impl OutputConfig {
+    /// The effective sink list, honouring the `jsonl` shorthand when `sinks`
+    /// is not set.
+    #[must_use]
+    pub fn sinks(&self) -> Vec<SinkConfig> {
+        if let Some(sinks) = &self.sinks {
+            return sinks.clone();
+        }
+        let mut sinks = vec![SinkConfig::Markdown];
+        if self.jsonl {
+            sinks.push(SinkConfig::Jsonl);
+        }
+        sinks
+    }
+
+    /// Replace the sinks for `hoover record --emit` and `--no-files`: stream
+    /// segments to stdout in `emit`, and leave out the sinks that write the
+    /// daily files.
+    pub fn override_sinks(&mut self, emit: Option<StdoutFormat>, no_files: bool) {
+        let mut sinks = self.sinks();
+        if no_files {
+            sinks.retain(|sink| !matches!(sink, SinkConfig::Markdown | SinkConfig::Jsonl));
+        }
+        if let Some(format) = emit {
+            sinks.retain(|sink| !matches!(sink, SinkConfig::Stdout { .. }));
+            sinks.push(SinkConfig::Stdout { format });
+        }
+        self.sinks = Some(sinks);
+    }
+
+    /// Path of the search index, when a `sqlite` sink is configured.
+    #[must_use]
+    pub fn index_path(&self) -> Option<PathBuf> {
+        self.sinks().iter().find_map(|sink| match sink {
+            SinkConfig::Sqlite { path } => Some(
+                path.as_deref()
+                    .map_or_else(default_index_path, Config::expand_path),
+            ),
+            _ => None,
+        })
+    }
+}
+
+fn default_index_path() -> PathBuf {
+    let data_dir = dirs::data_dir().unwrap_or_else(|| PathBuf::from(".local/share"));
+    data_dir.join("hoover/index.sqlite")
+}
+
+/// Templates for the daily markdown files.  The defaults produce hoover's
+/// original layout.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct LayoutConfig {
+    /// strftime pattern of a day's file, relative to the output directory,
+    /// e.g. `%Y/%m/%Y-%m-%d.md`.
+    #[serde(default = "default_layout_file")]
+    pub file: String,
+
+    /// strftime pattern of the header at the top of each file.
+    #[serde(default = "default_layout_day_header")]
+    pub day_header: String,
+
+    /// Template of a time heading; `{time}` is replaced by `HH:MM`, or
+    /// `HH:MM:SS` with `granularity: turn`.
+    #[serde(default = "default_layout_time_heading")]
+    pub time_heading: String,
+
+    /// How often a time heading is written.
+    #[serde(default)]
+    pub granularity: HeadingGranularity,
+
+    /// Template of an attributed entry, with `{speaker}` and `{text}`.
+    #[serde(default = "default_layout_speaker")]
+    pub speaker: String,
+
+    /// Whether consecutive segments are separate paragraphs.
+    #[serde(default)]
+    pub join: ParagraphJoin,
+
+    /// Write into daily notes kept by another tool, such as an Obsidian
+    /// vault, instead of owning whole files.
+    #[serde(default)]
+    pub daily_note: Option<DailyNoteConfig>,
+}
+
+/// Daily-note mode: entries go under a heading hoover manages in a note the
+/// user may also edit.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct DailyNoteConfig {
+    /// The heading entries go under, added to the end of the note when it
+    /// is missing.
+    #[serde(default = "default_daily_note_heading")]
+    pub heading: String,
+
+    /// Write speaker names as `[[wiki-links]]`.
+    #[serde(default = "default_true")]
+    pub wiki_links: bool,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        Self {
+            file: default_layout_file(),
+            day_header: default_layout_day_header(),
+            time_heading: default_layout_time_heading(),
+            granularity: HeadingGranularity::default(),
+            speaker: default_layout_speaker(),
+            join: ParagraphJoin::default(),
+            daily_note: None,
+        }
+    }
+}
+
+fn default_day_starts_at() -> String {
+    "00:00".to_string()
+}
+
+/// AES-256-GCM encryption of the files hoover writes.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EncryptionConfig {
+    /// Where the key comes from.
+    pub key: KeySource,
+
+    /// Also encrypt speaker profiles and clusters.
+    #[serde(default)]
+    pub profiles: bool,
+}
+
+/// Source of the 256-bit encryption key.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum KeySource {
+    /// A file holding exactly 32 random bytes.
+    File { path: String },
+    /// A passphrase read from an environment variable, stretched with
+    /// PBKDF2.
+    Passphrase {
+        #[serde(default = "default_passphrase_env")]
+        env: String,
+    },
+    /// A 32-byte `user` key in the Linux kernel keyring.
+    Keyring {
+        #[serde(default = "default_keyring_description")]
+        description: String,
+    },
+}
+
+fn default_passphrase_env() -> String {
+    "HOOVER_PASSPHRASE".to_string()
+}
+
+fn default_keyring_description() -> String {
+    "hoover".to_string()
+}
+
+fn default_layout_file() -> String {
+    "%Y-%m-%d.md".to_string()
+}
+
+fn default_layout_day_header() -> String {
+    "# %a, %B %-d, %Y".to_string()
+}
+
+fn default_layout_time_heading() -> String {
+    "## {time}".to_string()
+}
+
+fn default_layout_speaker() -> String {
+    "**{speaker}:** {text}".to_string()
+}
+
+fn default_daily_note_heading() -> String {
+    "## Transcript".to_string()
+}
+
+/// When a new time heading is written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HeadingGranularity {
+    /// When the minute changes.
+    #[default]
+    Minute,
+    /// When the five-minute slot changes, e.g. `14:05`.
+    FiveMinutes,
+    /// When the hour changes, e.g. `14:00`.
+    Hour,
+    /// At every change of speaker, with seconds.
+    Turn,
+}
+
+/// How consecutive segments are laid out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParagraphJoin {
+    /// Every segment is its own paragraph.
+    #[default]
+    Separate,
+    /// Segments from the same speaker under the same heading are joined
+    /// into one paragraph.
+    Speaker,
+}
+
+/// An output that transcribed segments are written to.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SinkConfig {
+    /// Daily markdown files, following `output.routes`.
+    Markdown,
+    /// Daily `.jsonl` segment logs next to the markdown files.
+    Jsonl,
+    /// One line per segment on standard output.
+    Stdout {
+        #[serde(default)]
+        format: StdoutFormat,
+    },
+    /// Run a command for every segment, with the segment as JSON on stdin.
+    Command { command: Vec<String> },
+    /// A full-text index of every segment, used by search.
+    Sqlite {
+        /// Database file; defaults to `index.sqlite` in hoover's data
+        /// directory.
+        #[serde(default)]
+        path: Option<String>,
+    },
+    /// POST every segment as JSON to a URL.
+    Webhook {
+        url: String,
+        #[serde(default)]
+        headers: BTreeMap<String, String>,
+    },
+    /// Type every segment into the focused window.
+    Dictation {
+        #[serde(default)]
+        injector: InjectorKind,
+        /// Turn spoken punctuation such as "comma" and "new line" into the
+        /// characters.
+        #[serde(default = "default_true")]
+        punctuation: bool,
+        /// Only type while switched on with `hoover dictate`.
+        #[serde(default)]
+        push_to_talk: bool,
+        /// Only type segments from these speakers; `unknown` matches
+        /// segments without one.  Empty types every segment.
+        #[serde(default)]
+        speakers: Vec<String>,
+    },
+}
+
+/// How the `dictation` sink produces keystrokes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InjectorKind {
+    /// `wtype`, for Wayland compositors with the virtual keyboard protocol.
+    #[default]
+    Wtype,
+    /// `xdotool`, for X11.
+    Xdotool,
+    /// `ydotool`, through its daemon's virtual device.
+    Ydotool,
+    /// A virtual keyboard on `/dev/uinput`, with a US layout.
+    Uinput,
+}
+
+/// Line format of the `stdout` sink.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StdoutFormat {
+    /// `HH:MM:SS Speaker: text`
+    #[default]
+    Text,
+    /// The segment as a JSON object.
+    Jsonl,
+}
+
+/// A rule sending segments from the listed speakers somewhere other than the
+/// main daily file.
+#[derive(Debug, Clone, Deserialize)]
+pub struct OutputRoute {
+    /// Speaker names this route applies to.  `unknown` matches segments
+    /// without a speaker.
+    pub speakers: Vec<String>,
+
+    /// Directory for this route's daily files.  Relative paths are resolved
+    /// against `output.directory`; defaults to `output.directory` itself.
+    #[serde(default)]
+    pub directory: Option<String>,
+
+    /// Collect matching segments under this heading of the daily file
+    /// instead of interleaving them with the rest of the transcript.
+    #[serde(default)]
+    pub section: Option<String>,
+
+    /// Also write matching segments to the main daily file.
+    #[serde(default)]
+    pub copy: bool,
+}
+
+impl OutputRoute {
+    /// Whether a segment attributed to `speaker` follows this route.
+    #[must_use]
+    pub fn matches(&self, speaker: Option<&str>) -> bool {
+        let name = speaker.unwrap_or("unknown");
+        self.speakers.iter().any(|s| s.eq_ignore_ascii_case(name))
+    }
+}
+
+/// Removal of personal data from transcribed text before it reaches any
+/// output sink.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RedactionConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Built-in detectors to run.
+    #[serde(default = "default_pii_detectors")]
+    pub detectors: Vec<PiiDetector>,
+
+    /// Additional patterns, each replaced by its name in upper case.
+    #[serde(default)]
+    pub rules: Vec<RedactionRule>,
+
+    /// Names replaced by `[PERSON]`, matched as whole words ignoring case.
+    #[serde(default)]
+    pub people: Vec<String>,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            detectors: default_pii_detectors(),
+            rules: Vec::new(),
+            people: Vec::new(),
         }
     }
 }
 
+/// A built-in kind of personal data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PiiDetector {
+    Email,
+    Phone,
+    /// Payment card numbers passing the Luhn check.
+    Card,
+    /// IBANs passing the mod-97 check.
+    Iban,
+    /// IPv4 and IPv6 addresses.
+    Ip,
+}
+
+/// A user-defined redaction pattern.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RedactionRule {
+    /// Used for the placeholder (`[NAME]`) and the audit counts.
+    pub name: String,
+    /// Regular expression to replace.
+    pub pattern: String,
+}
+
+fn default_pii_detectors() -> Vec<PiiDetector> {
+    vec![
+        PiiDetector::Email,
+        PiiDetector::Phone,
+        PiiDetector::Card,
+        PiiDetector::Iban,
+        PiiDetector::Ip,
+    ]
+}
+
+/// Spoken commands recognized in the transcript while recording.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CommandsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Phrases and what they do.  A phrase matches whole words, ignoring
+    /// case and punctuation.
+    #[serde(default = "default_command_triggers")]
+    pub triggers: Vec<CommandTrigger>,
+
+    /// File `todo` commands append to, relative to the output directory.
+    #[serde(default = "default_todo_file")]
+    pub todo_file: String,
+
+    /// File `note` commands append to, relative to the output directory.
+    #[serde(default = "default_notes_file")]
+    pub notes_file: String,
+
+    /// How long `pause` stops recording when no duration is spoken.
+    #[serde(default = "default_pause_minutes")]
+    pub pause_minutes: u32,
+}
+
+impl Default for CommandsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            triggers: default_command_triggers(),
+            todo_file: default_todo_file(),
+            notes_file: default_notes_file(),
+            pause_minutes: default_pause_minutes(),
+        }
+    }
+}
+
+/// A spoken phrase and the action it triggers.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct CommandTrigger {
+    pub phrase: String,
+    pub action: CommandAction,
+}
+
+/// What a voice command does.  The words after the phrase are its argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommandAction {
+    /// Append the argument to the todo file as an open task.
+    Todo,
+    /// Append the argument to the notes file.
+    Note,
+    /// Insert a heading into the transcript, labelled with the argument.
+    Bookmark,
+    /// Tag the current section of the transcript with the argument.
+    Tag,
+    /// Drop the segment before the phrase.
+    Scratch,
+    /// Stop recording for the spoken number of minutes, or `pause_minutes`.
+    Pause,
+}
+
+fn default_command_triggers() -> Vec<CommandTrigger> {
+    [
+        ("hoover todo", CommandAction::Todo),
+        ("hoover to do", CommandAction::Todo),
+        ("hoover note", CommandAction::Note),
+        ("hoover bookmark", CommandAction::Bookmark),
+        ("hoover tag", CommandAction::Tag),
+        ("hoover pause", CommandAction::Pause),
+        ("scratch that", CommandAction::Scratch),
+    ]
+    .into_iter()
+    .map(|(phrase, action)| CommandTrigger {
+        phrase: phrase.to_string(),
+        action,
+    })
+    .collect()
+}
+
+fn default_todo_file() -> String {
+    "todo.md".to_string()
+}
+
+fn default_notes_file() -> String {
+    "notes.md".to_string()
+}
+
+const fn default_pause_minutes() -> u32 {
+    10
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct VcsConfig {
     #[serde(default)]
@@ -276,6 +835,141 @@ impl Default for VcsConfig {
     }
 }
 
+/// Daily summaries written by a language model behind an OpenAI-compatible
+/// chat completions endpoint.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SummaryConfig {
+    /// Summarize the previous day whenever recording rolls over to a new day.
+    #[serde(default)]
+    pub auto: bool,
+
+    /// Chat completions URL, e.g. a local llama.cpp or Ollama server.
+    #[serde(default = "default_summary_url")]
+    pub url: String,
+
+    #[serde(default = "default_summary_model")]
+    pub model: String,
+
+    /// Sent as a bearer token when set.
+    pub api_key: Option<String>,
+
+    /// The model's context window in tokens.  Longer days are summarized in
+    /// parts that fit it.
+    #[serde(default = "default_context_tokens")]
+    pub context_tokens: u32,
+
+    /// Where the summary is written.
+    #[serde(default)]
+    pub target: SummaryTarget,
+
+    /// How long to wait for each response.
+    #[serde(default = "default_summary_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl Default for SummaryConfig {
+    fn default() -> Self {
+        Self {
+            auto: false,
+            url: default_summary_url(),
+            model: default_summary_model(),
+            api_key: None,
+            context_tokens: default_context_tokens(),
+            target: SummaryTarget::default(),
+            timeout_secs: default_summary_timeout_secs(),
+        }
+    }
+}
+
+/// Where a day's summary goes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SummaryTarget {
+    /// At the top of the day file, before the first time heading.
+    #[default]
+    Inline,
+    /// In `YYYY-MM-DD.summary.md` next to the day file.
+    Sidecar,
+}
+
+fn default_summary_url() -> String {
+    "http://localhost:8080/v1/chat/completions".to_string()
+}
+
+fn default_summary_model() -> String {
+    "default".to_string()
+}
+
+const fn default_context_tokens() -> u32 {
+    8192
+}
+
+const fn default_summary_timeout_secs() -> u64 {
+    600
+}
+
+/// How long daily files are kept in the output directory.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RetentionConfig {
+    /// Prune automatically whenever recording rolls over to a new day.
+    #[serde(default)]
+    pub auto: bool,
+
+    /// Days older than this many days are removed from the output directory
+    /// according to `action`.
+    #[serde(default)]
+    pub keep_days: Option<u32>,
+
+    /// What happens to days older than `keep_days`.
+    #[serde(default)]
+    pub action: RetentionAction,
+
+    /// Where moved days and monthly archives go.  Relative paths are
+    /// resolved against each directory holding daily files.
+    #[serde(default = "default_archive_dir")]
+    pub archive_dir: String,
+
+    /// Days older than this many days keep only their summary: whatever
+    /// precedes the first time heading.  Days without one are left alone.
+    #[serde(default)]
+    pub summaries_after_days: Option<u32>,
+
+    /// With VCS enabled, also rewrite the current branch so earlier commits
+    /// no longer contain the pruned transcripts.  Requires a force push.
+    #[serde(default)]
+    pub rewrite_history: bool,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            auto: false,
+            keep_days: None,
+            action: RetentionAction::default(),
+            archive_dir: default_archive_dir(),
+            summaries_after_days: None,
+            rewrite_history: false,
+        }
+    }
+}
+
+/// What happens to days past `retention.keep_days`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetentionAction {
+    /// Roll them up into one `YYYY-MM.tar.zst` per month.
+    #[default]
+    Archive,
+    /// Move the files to the archive directory as they are.
+    Move,
+    /// Delete them.
+    Delete,
+}
+
+fn default_archive_dir() -> String {
+    "archive".to_string()
+}
+
 #[derive(Debug, Clone, Deserialize, Default)]
 pub struct GithubConfig {
     pub token: Option<String>,
@@ -395,6 +1089,35 @@ impl Config {
 
     /// Update the `audio.device` field in the config file, preserving all other content.
     pub fn set_audio_device(config_path: &Path, device: &str) -> Result<()> {
+        Self::set_value(
+            config_path,
+            "audio",
+            "device",
+            serde_yaml_ng::Value::String(device.to_string()),
+        )
+    }
+
+    /// Update the `speaker.min_confidence` field in the config file, preserving
+    /// all other content.
+    pub fn set_speaker_min_confidence(config_path: &Path, min_confidence: f32) -> Result<()> {
+        // Round so the file reads `0.58` rather than `0.5799999833106995`.
+        let rounded = (f64::from(min_confidence) * 100.0).round() / 100.0;
+        Self::set_value(
+            config_path,
+            "speaker",
+            "min_confidence",
+            serde_yaml_ng::Value::Number(rounded.into()),
+        )
+    }
+
+    /// Set `section.key` in the config file, creating the file and section if
+    /// needed and preserving all other content.
+    fn set_value(
+        config_path: &Path,
+        section: &str,
+        key: &str,
+        new_value: serde_yaml_ng::Value,
+    ) -> Result<()> {
         let contents = if config_path.exists() {
             std::fs::read_to_string(config_path).map_err(|e| {
                 HooverError::Config(format!(
@@ -418,19 +1141,16 @@ impl Config {
             .as_mapping_mut()
             .ok_or_else(|| HooverError::Config("config root is not a mapping".to_string()))?;
 
-        let audio_key = serde_yaml_ng::Value::String("audio".to_string());
-        let audio = map
-            .entry(audio_key)
+        let section_key = serde_yaml_ng::Value::String(section.to_string());
+        let section_value = map
+            .entry(section_key)
             .or_insert_with(|| serde_yaml_ng::Value::Mapping(serde_yaml_ng::Mapping::new()));
 
-        let audio_map = audio
+        let section_map = section_value
             .as_mapping_mut()
-            .ok_or_else(|| HooverError::Config("audio section is not a mapping".to_string()))?;
+            .ok_or_else(|| HooverError::Config(format!("{section} section is not a mapping")))?;
 
-        audio_map.insert(
-            serde_yaml_ng::Value::String("device".to_string()),
-            serde_yaml_ng::Value::String(device.to_string()),
-        );
+        section_map.insert(serde_yaml_ng::Value::String(key.to_string()), new_value);
 
         let yaml = serde_yaml_ng::to_string(&value).map_err(|e| {
             HooverError::Config(format!("failed to serialize config: {e}"))
@@ -603,6 +1323,269 @@ audio:
         assert_eq!(config.audio.silence_threshold_ms, 800);
     }
 
+    #[test]
+    fn unknown_policy_defaults_follow_filter_unknown() {
+        let config: Config = serde_yaml_ng::from_str("{}").unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(config.speaker.unknown_policy(), UnknownPolicy::Keep);
+
+        let config: Config = serde_yaml_ng::from_str("speaker:\n  filter_unknown: true\n")
+            .unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(config.speaker.unknown_policy(), UnknownPolicy::Drop);
+
+        let config: Config =
+            serde_yaml_ng::from_str("speaker:\n  filter_unknown: true\n  unknown_policy: redact\n")
+                .unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(config.speaker.unknown_policy(), UnknownPolicy::Redact);
+
+        assert!(serde_yaml_ng::from_str::<Config>("speaker:\n  unknown_policy: shred\n").is_err());
+    }
+
+    #[test]
+    fn parse_output_routes() {
+        let yaml = r"
+output:
+  routes:
+    - speakers: [Alice]
+      directory: ~/dictation
+    - speakers: [unknown]
+      section: Others
+      copy: true
+";
+        let config: Config =
+            serde_yaml_ng::from_str(yaml).unwrap_or_else(|e| panic!("parse failed: {e}"));
+        let routes = &config.output.routes;
+        assert_eq!(routes.len(), 2);
+        assert_eq!(routes[0].directory.as_deref(), Some("~/dictation"));
+        assert!(!routes[0].copy);
+        assert!(routes[0].matches(Some("alice")));
+        assert!(!routes[0].matches(None));
+        assert_eq!(routes[1].section.as_deref(), Some("Others"));
+        assert!(routes[1].matches(None));
+        assert!(!routes[1].matches(Some("Alice")));
+    }
+
+    #[test]
+    fn parse_output_sinks() {
+        let yaml = r"
+output:
+  sinks:
+    - type: markdown
+    - type: stdout
+      format: jsonl
+    - type: command
+      command: [notify-send, hoover]
+    - type: webhook
+      url: https://example.com/hook
+      headers:
+        Authorization: Bearer abc
+    - type: dictation
+      injector: uinput
+      speakers: [Alice]
+";
+        let config: Config =
+            serde_yaml_ng::from_str(yaml).unwrap_or_else(|e| panic!("parse failed: {e}"));
+        let sinks = config.output.sinks();
+        assert_eq!(sinks.len(), 5);
+        assert_eq!(sinks[0], SinkConfig::Markdown);
+        assert_eq!(
+            sinks[1],
+            SinkConfig::Stdout {
+                format: StdoutFormat::Jsonl
+            }
+        );
+        let SinkConfig::Webhook { headers, .. } = &sinks[3] else {
+            panic!("expected a webhook sink, got {:?}", sinks[3]);
+        };
+        assert_eq!(headers["Authorization"], "Bearer abc");
+        assert_eq!(
+            sinks[4],
+            SinkConfig::Dictation {
+                injector: InjectorKind::Uinput,
+                punctuation: true,
+                push_to_talk: false,
+                speakers: vec!["Alice".to_string()],
+            }
+        );
+
+        assert!(serde_yaml_ng::from_str::<Config>("output:\n  sinks:\n    - type: fax\n").is_err());
+    }
+
+    #[test]
+    fn record_flags_override_sinks() {
+        let yaml = "output:\n  jsonl: true\n";
+        let config: Config =
+            serde_yaml_ng::from_str(yaml).unwrap_or_else(|e| panic!("parse failed: {e}"));
+
+        let mut output = config.output;
+        output.override_sinks(Some(StdoutFormat::Text), false);
+        assert_eq!(
+            output.sinks(),
+            vec![
+                SinkConfig::Markdown,
+                SinkConfig::Jsonl,
+                SinkConfig::Stdout {
+                    format: StdoutFormat::Text
+                },
+            ]
+        );
+
+        output.override_sinks(Some(StdoutFormat::Jsonl), true);
+        assert_eq!(
+            output.sinks(),
+            vec![SinkConfig::Stdout {
+                format: StdoutFormat::Jsonl
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_output_layout() {
+        let yaml = r"
+output:
+  layout:
+    file: '%Y/%m/%Y-%m-%d.md'
+    granularity: five_minutes
+    join: speaker
+";
+        let config: Config =
+            serde_yaml_ng::from_str(yaml).unwrap_or_else(|e| panic!("parse failed: {e}"));
+        let layout = &config.output.layout;
+        assert_eq!(layout.file, "%Y/%m/%Y-%m-%d.md");
+        assert_eq!(layout.granularity, HeadingGranularity::FiveMinutes);
+        assert_eq!(layout.join, ParagraphJoin::Speaker);
+        assert_eq!(layout.speaker, LayoutConfig::default().speaker);
+    }
+
+    #[test]
+    fn parse_encryption() {
+        let yaml = r"
+output:
+  encryption:
+    key:
+      type: passphrase
+    profiles: true
+";
+        let config: Config =
+            serde_yaml_ng::from_str(yaml).unwrap_or_else(|e| panic!("parse failed: {e}"));
+        let encryption = config
+            .output
+            .encryption
+            .unwrap_or_else(|| panic!("encryption missing"));
+        assert!(encryption.profiles);
+        assert_eq!(
+            encryption.key,
+            KeySource::Passphrase {
+                env: "HOOVER_PASSPHRASE".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn parse_retention() {
+        let config: Config =
+            serde_yaml_ng::from_str("{}").unwrap_or_else(|e| panic!("parse failed: {e}"));
+        assert!(!config.retention.auto);
+        assert_eq!(config.retention.action, RetentionAction::Archive);
+        assert_eq!(config.retention.archive_dir, "archive");
+
+        let yaml = r"
+retention:
+  auto: true
+  keep_days: 90
+  action: delete
+  summaries_after_days: 30
+";
+        let config: Config =
+            serde_yaml_ng::from_str(yaml).unwrap_or_else(|e| panic!("parse failed: {e}"));
+        assert!(config.retention.auto);
+        assert_eq!(config.retention.keep_days, Some(90));
+        assert_eq!(config.retention.action, RetentionAction::Delete);
+        assert_eq!(config.retention.summaries_after_days, Some(30));
+    }
+
+    #[test]
+    fn parse_summary() {
+        let config: Config =
+            serde_yaml_ng::from_str("{}").unwrap_or_else(|e| panic!("parse failed: {e}"));
+        assert!(!config.summary.auto);
+        assert_eq!(config.summary.target, SummaryTarget::Inline);
+        assert_eq!(config.summary.context_tokens, 8192);
+
+        let yaml = r"
+summary:
+  auto: true
+  url: http://127.0.0.1:11434/v1/chat/completions
+  model: llama3.1
+  context_tokens: 4096
+  target: sidecar
+";
+        let config: Config =
+            serde_yaml_ng::from_str(yaml).unwrap_or_else(|e| panic!("parse failed: {e}"));
+        assert!(config.summary.auto);
+        assert_eq!(config.summary.model, "llama3.1");
+        assert_eq!(config.summary.context_tokens, 4096);
+        assert_eq!(config.summary.target, SummaryTarget::Sidecar);
+    }
+
+    #[test]
+    fn parse_commands() {
+        let config: Config =
+            serde_yaml_ng::from_str("{}").unwrap_or_else(|e| panic!("parse failed: {e}"));
+        assert!(!config.commands.enabled);
+        assert_eq!(config.commands.triggers.len(), 7);
+        assert_eq!(config.commands.todo_file, "todo.md");
+
+        let yaml = r"
+commands:
+  enabled: true
+  triggers:
+    - phrase: computer remember
+      action: note
+  notes_file: inbox.md
+  pause_minutes: 5
+";
+        let config: Config =
+            serde_yaml_ng::from_str(yaml).unwrap_or_else(|e| panic!("parse failed: {e}"));
+        assert!(config.commands.enabled);
+        assert_eq!(
+            config.commands.triggers,
+            vec![CommandTrigger {
+                phrase: "computer remember".to_string(),
+                action: CommandAction::Note,
+            }]
+        );
+        assert_eq!(config.commands.notes_file, "inbox.md");
+        assert_eq!(config.commands.pause_minutes, 5);
+    }
+
+    #[test]
+    fn parse_redaction() {
+        let config: Config =
+            serde_yaml_ng::from_str("{}").unwrap_or_else(|e| panic!("parse failed: {e}"));
+        assert!(!config.redaction.enabled);
+        assert_eq!(config.redaction.detectors.len(), 5);
+
+        let yaml = r"
+redaction:
+  enabled: true
+  detectors: [email, card]
+  rules:
+    - name: ticket
+      pattern: 'TICKET-\d+'
+  people: [Alice Smith]
+";
+        let config: Config =
+            serde_yaml_ng::from_str(yaml).unwrap_or_else(|e| panic!("parse failed: {e}"));
+        assert!(config.redaction.enabled);
+        assert_eq!(
+            config.redaction.detectors,
+            vec![PiiDetector::Email, PiiDetector::Card]
+        );
+        assert_eq!(config.redaction.rules[0].name, "ticket");
+        assert_eq!(config.redaction.rules[0].pattern, "TICKET-\\d+");
+        assert_eq!(config.redaction.people, vec!["Alice Smith"]);
+    }
+
     #[test]
     fn set_audio_device_creates_and_updates() {
         let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
@@ -618,4 +1601,20 @@ audio:
         let config = Config::load(&path).unwrap_or_else(|e| panic!("{e}"));
         assert_eq!(config.audio.device.as_deref(), Some("Other Mic"));
     }
+
+    #[test]
+    fn set_speaker_min_confidence_preserves_other_fields() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let path = dir.path().join("config.yaml");
+        std::fs::write(&path, "speaker:\n  filter_unknown: true\n")
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        Config::set_speaker_min_confidence(&path, 0.579_999_9).unwrap_or_else(|e| panic!("{e}"));
+        let config = Config::load(&path).unwrap_or_else(|e| panic!("{e}"));
+        assert!((config.speaker.min_confidence - 0.58).abs() < 1e-6);
+        assert!(config.speaker.filter_unknown);
+
+        let contents = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{e}"));
+        assert!(contents.contains("min_confidence: 0.58"));
+    }
 }
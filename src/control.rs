@@ -0,0 +1,186 @@
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+use tokio::net::{UnixListener, UnixStream};
+
+use crate::error::{HooverError, Result};
+use crate::output::dictation::DictationSwitch;
+
+/// Path of the control socket of a running `hoover record`, in the user's
+/// runtime directory.  There is no fallback to a directory shared with other
+/// users.
+pub fn socket_path() -> Result<PathBuf> {
+    dirs::runtime_dir()
+        .map(|dir| dir.join("hoover.sock"))
+        .ok_or_else(|| {
+            HooverError::Control(
+                "no runtime directory for the control socket (set XDG_RUNTIME_DIR)".to_string(),
+            )
+        })
+}
+
+/// Listens on the control socket for one request per line, such as
+/// `dictation toggle`, and answers each with one line.
+pub struct ControlServer {
+    listener: UnixListener,
+    path: PathBuf,
+    dictation: DictationSwitch,
+}
+
+impl ControlServer {
+    /// Bind `path`, replacing a socket left behind by an instance that
+    /// exited without removing it.  Only the user may connect.
+    pub fn bind(path: &Path, dictation: DictationSwitch) -> Result<Self> {
+        if path.exists() {
+            if std::os::unix::net::UnixStream::connect(path).is_ok() {
+                return Err(HooverError::Control(format!(
+                    "{} is in use by another hoover",
+                    path.display()
+                )));
+            }
+            std::fs::remove_file(path).map_err(|e| {
+                HooverError::Control(format!("failed to remove {}: {e}", path.display()))
+            })?;
+        }
+        let listener = UnixListener::bind(path)
+            .map_err(|e| HooverError::Control(format!("failed to bind {}: {e}", path.display())))?;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600)).map_err(|e| {
+            HooverError::Control(format!("failed to restrict {}: {e}", path.display()))
+        })?;
+        tracing::info!("control socket listening on {}", path.display());
+        Ok(Self {
+            listener,
+            path: path.to_path_buf(),
+            dictation,
+        })
+    }
+
+    /// Serve connections until the task is dropped.
+    pub async fn run(self) {
+        loop {
+            match self.listener.accept().await {
+                Ok((stream, _)) => {
+                    let dictation = self.dictation.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = serve(stream, &dictation).await {
+                            tracing::debug!("control connection: {e}");
+                        }
+                    });
+                }
+                Err(e) => tracing::warn!("control socket accept failed: {e}"),
+            }
+        }
+    }
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+async fn serve(stream: UnixStream, dictation: &DictationSwitch) -> std::io::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = tokio::io::BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        let reply = handle_request(&line, dictation);
+        writer.write_all(format!("{reply}\n").as_bytes()).await?;
+    }
+    Ok(())
+}
+
+/// Answer one control request.
+#[must_use]
+pub fn handle_request(request: &str, dictation: &DictationSwitch) -> String {
+    let words: Vec<&str> = request.split_whitespace().collect();
+    let on = match words.as_slice() {
+        ["dictation", "on"] => {
+            dictation.set(true, Utc::now());
+            true
+        }
+        ["dictation", "off"] => {
+            dictation.set(false, Utc::now());
+            false
+        }
+        ["dictation", "toggle"] => dictation.toggle(Utc::now()),
+        ["dictation", "status"] => dictation.is_on(),
+        _ => return format!("error: unknown request {:?}", request.trim()),
+    };
+    format!("dictation {}", if on { "on" } else { "off" })
+}
+
+/// Send `request` to the running `hoover record` on `path` and return its
+/// reply.
+pub fn send(path: &Path, request: &str) -> Result<String> {
+    let mut stream = std::os::unix::net::UnixStream::connect(path).map_err(|e| {
+        HooverError::Control(format!(
+            "failed to connect to {} (is hoover record running?): {e}",
+            path.display()
+        ))
+    })?;
+    writeln!(stream, "{request}")
+        .map_err(|e| HooverError::Control(format!("failed to send request: {e}")))?;
+    let mut reply = String::new();
+    BufReader::new(stream)
+        .read_line(&mut reply)
+        .map_err(|e| HooverError::Control(format!("failed to read reply: {e}")))?;
+    let reply = reply.trim_end().to_string();
+    if let Some(message) = reply.strip_prefix("error: ") {
+        return Err(HooverError::Control(message.to_string()));
+    }
+    Ok(reply)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_switch_dictation() {
+        let switch = DictationSwitch::new(false);
+        assert_eq!(handle_request("dictation status", &switch), "dictation off");
+        assert_eq!(handle_request("dictation toggle", &switch), "dictation on");
+        assert!(switch.is_on());
+        assert_eq!(
+            handle_request(" dictation  off\n", &switch),
+            "dictation off"
+        );
+        assert_eq!(handle_request("dictation on", &switch), "dictation on");
+        assert!(handle_request("dictation maybe", &switch).starts_with("error: "));
+    }
+
+    #[test]
+    fn requests_are_answered_over_the_socket() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let path = dir.path().join("hoover.sock");
+        std::fs::write(&path, "").unwrap_or_else(|e| panic!("{e}"));
+        let switch = DictationSwitch::new(false);
+
+        let runtime = tokio::runtime::Runtime::new().unwrap_or_else(|e| panic!("{e}"));
+        let server = runtime
+            .block_on(async { ControlServer::bind(&path, switch.clone()) })
+            .unwrap_or_else(|e| panic!("{e}"));
+        assert!(
+            runtime
+                .block_on(async { ControlServer::bind(&path, switch.clone()) })
+                .is_err()
+        );
+        let metadata = std::fs::metadata(&path).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+        let task = runtime.spawn(server.run());
+
+        assert_eq!(
+            send(&path, "dictation toggle").unwrap_or_else(|e| panic!("{e}")),
+            "dictation on"
+        );
+        assert!(switch.is_on());
+        assert!(send(&path, "nonsense").is_err());
+
+        task.abort();
+        drop(runtime);
+        assert!(!path.exists());
+    }
+}
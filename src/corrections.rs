@@ -0,0 +1,625 @@
+use std::borrow::Cow;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use chrono::NaiveDate;
+use regex::{NoExpand, Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::error::{HooverError, Result};
+use crate::index;
+use crate::output::jsonl::{self, SegmentRecord};
+use crate::output::layout::Layout;
+use crate::output::route::Routes;
+use crate::speaker::{AttributedSegment, is_redaction_placeholder};
+use crate::summary;
+use crate::vault::{Vault, replace_file};
+use crate::vcs::git;
+
+/// File in the output directory holding the correction rules applied to
+/// new segments.
+pub const RULES_FILE: &str = "corrections.json";
+
+/// A replacement made in transcribed text, such as a name the STT engine
+/// keeps misspelling.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CorrectionRule {
+    pub pattern: String,
+    pub replacement: String,
+    /// `pattern` is a regular expression and `replacement` may refer to its
+    /// groups as `$1`.  Otherwise `pattern` is literal text, matched as
+    /// whole words.
+    #[serde(default)]
+    pub regex: bool,
+    #[serde(default)]
+    pub ignore_case: bool,
+}
+
+/// A [`CorrectionRule`] ready to apply.
+#[derive(Debug, Clone)]
+pub struct Correction {
+    rule: CorrectionRule,
+    regex: Regex,
+}
+
+impl Correction {
+    pub fn new(rule: CorrectionRule) -> Result<Self> {
+        if rule.pattern.is_empty() {
+            return Err(HooverError::Config(
+                "correction pattern must not be empty".to_string(),
+            ));
+        }
+        let pattern = if rule.regex {
+            rule.pattern.clone()
+        } else {
+            let word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+            let boundary = |c| if word(c) { r"\b" } else { "" };
+            format!(
+                "{}{}{}",
+                boundary(rule.pattern.chars().next()),
+                regex::escape(&rule.pattern),
+                boundary(rule.pattern.chars().last()),
+            )
+        };
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(rule.ignore_case)
+            .build()
+            .map_err(|e| {
+                HooverError::Config(format!("invalid correction pattern {}: {e}", rule.pattern))
+            })?;
+        Ok(Self { rule, regex })
+    }
+
+    #[must_use]
+    pub const fn rule(&self) -> &CorrectionRule {
+        &self.rule
+    }
+
+    /// `text` with every match replaced.
+    #[must_use]
+    pub fn apply<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if self.rule.regex {
+            self.regex.replace_all(text, self.rule.replacement.as_str())
+        } else {
+            self.regex
+                .replace_all(text, NoExpand(self.rule.replacement.as_str()))
+        }
+    }
+}
+
+/// Applies the rules recorded by `hoover fix` to new segments, before
+/// redaction and the output sinks.
+///
+/// The rules file is read again whenever it changes, so a rule added while
+/// recording applies from the next chunk on.
+pub struct Corrector {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    corrections: Vec<Correction>,
+}
+
+impl Corrector {
+    pub fn new(config: &Config) -> Result<Self> {
+        let mut corrector = Self {
+            path: Config::expand_path(&config.output.directory).join(RULES_FILE),
+            modified: None,
+            corrections: Vec::new(),
+        };
+        corrector.reload()?;
+        Ok(corrector)
+    }
+
+    /// Correct the text of every turn in place.  Turns replaced by a
+    /// placeholder are skipped.
+    pub fn correct_turns(&mut self, turns: &mut Vec<AttributedSegment>) {
+        if let Err(e) = self.reload() {
+            tracing::warn!("keeping the previous correction rules: {e}");
+        }
+        if self.corrections.is_empty() {
+            return;
+        }
+        for turn in turns.iter_mut().filter(|turn| !turn.redacted) {
+            turn.segment.text = correct(&self.corrections, &turn.segment.text).into_owned();
+        }
+        turns.retain(|turn| !turn.segment.text.trim().is_empty());
+    }
+
+    fn reload(&mut self) -> Result<()> {
+        let modified = fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+        if modified == self.modified {
+            return Ok(());
+        }
+        self.corrections = read_rules(&self.path)?
+            .into_iter()
+            .map(Correction::new)
+            .collect::<Result<_>>()?;
+        self.modified = modified;
+        Ok(())
+    }
+}
+
+/// `text` with every correction applied in order.
+fn correct<'a>(corrections: &[Correction], text: &'a str) -> Cow<'a, str> {
+    corrections
+        .iter()
+        .fold(Cow::Borrowed(text), |text, correction| {
+            match correction.apply(&text) {
+                Cow::Borrowed(_) => text,
+                Cow::Owned(changed) => Cow::Owned(changed),
+            }
+        })
+}
+
+/// Read the rules kept in `path`; a missing file has none.
+pub fn read_rules(path: &Path) -> Result<Vec<CorrectionRule>> {
+    match fs::read_to_string(path) {
+        Ok(data) => serde_json::from_str(&data).map_err(|e| {
+            HooverError::Config(format!("invalid correction rules {}: {e}", path.display()))
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Add `rule` to the rules kept in `path`, replacing a rule for the same
+/// pattern.
+pub fn add_rule(path: &Path, rule: &CorrectionRule) -> Result<()> {
+    let mut rules = read_rules(path)?;
+    rules.retain(|r| (&r.pattern, r.regex) != (&rule.pattern, rule.regex));
+    rules.push(rule.clone());
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut data = serde_json::to_string_pretty(&rules)
+        .map_err(|e| HooverError::Output(format!("failed to serialize correction rules: {e}")))?;
+    data.push('\n');
+    // Replaced atomically: a running recorder reloads the file when it changes.
+    replace_file(path, data.as_bytes())
+}
+
+/// A line changed by a correction.  For a JSONL log, the segment text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineChange {
+    /// 1-based line number.
+    pub line: usize,
+    pub before: String,
+    pub after: String,
+}
+
+/// What a daily file holds, which decides the parts a correction touches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileKind {
+    Transcript,
+    Sidecar,
+    Log,
+}
+
+/// A daily file a correction changes.
+#[derive(Debug, Clone)]
+pub struct FileChange {
+    pub date: NaiveDate,
+    /// Logical path; the vault stores it encrypted when encryption is on.
+    pub path: PathBuf,
+    pub lines: Vec<LineChange>,
+    kind: FileKind,
+}
+
+/// Every change `correction` makes to the days from `from` to `to`, without
+/// writing anything.
+///
+/// Either end is unbounded when `None`.  In every output directory, the
+/// transcript, its JSONL log and its summary sidecar are corrected.  Only
+/// the text of entries is changed, never headings, speaker names or
+/// redaction placeholders; in a daily note, only the managed section.
+pub fn plan(
+    config: &Config,
+    correction: &Correction,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+) -> Result<Vec<FileChange>> {
+    let layout = Layout::new(&config.output.layout)?;
+    let routes = Routes::new(&config.output)?;
+    let vault = Vault::new(&config.output)?;
+    let in_range =
+        |date: NaiveDate| from.is_none_or(|from| date >= from) && to.is_none_or(|to| date <= to);
+
+    let mut changes = Vec::new();
+    for dir in routes.all_directories() {
+        for (date, markdown) in layout.day_files(dir)? {
+            if !in_range(date) {
+                continue;
+            }
+            let files = [
+                (markdown, FileKind::Transcript),
+                (summary::sidecar_file(&layout, dir, date), FileKind::Sidecar),
+                (jsonl::log_file(&layout, dir, date), FileKind::Log),
+            ];
+            for (path, kind) in files {
+                let Some(content) = vault.read(&path)? else {
+                    continue;
+                };
+                if let Some((_, lines)) = correct_file(&layout, correction, kind, date, &content)? {
+                    changes.push(FileChange {
+                        date,
+                        path,
+                        lines,
+                        kind,
+                    });
+                }
+            }
+        }
+    }
+    Ok(changes)
+}
+
+/// `content` with `correction` applied to the parts of a file of `kind` it
+/// may change, and the lines changed, or `None` when nothing matched.
+fn correct_file(
+    layout: &Layout,
+    correction: &Correction,
+    kind: FileKind,
+    date: NaiveDate,
+    content: &str,
+) -> Result<Option<(String, Vec<LineChange>)>> {
+    let start = layout.body_start(content, date);
+    Ok(match kind {
+        FileKind::Transcript if layout.is_daily_note() => {
+            let end = layout.body_end(content, start);
+            correct_markdown(layout, correction, content, start..end)
+        }
+        FileKind::Transcript | FileKind::Sidecar => {
+            correct_markdown(layout, correction, content, start..content.len())
+        }
+        FileKind::Log => correct_log(correction, content)?,
+    })
+}
+
+/// `content` with `correction` applied to the entry text of the lines
+/// within `range`, and the lines changed, or `None` when nothing matched.
+fn correct_markdown(
+    layout: &Layout,
+    correction: &Correction,
+    content: &str,
+    range: std::ops::Range<usize>,
+) -> Option<(String, Vec<LineChange>)> {
+    let mut corrected = String::with_capacity(content.len());
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    for (i, line) in content.split_inclusive('\n').enumerate() {
+        let inside = offset >= range.start && offset < range.end;
+        offset += line.len();
+        let fixed = if inside {
+            correct_line(layout, correction, line)
+        } else {
+            Cow::Borrowed(line)
+        };
+        if let Cow::Owned(fixed) = &fixed {
+            lines.push(LineChange {
+                line: i + 1,
+                before: line.trim_end().to_string(),
+                after: fixed.trim_end().to_string(),
+            });
+        }
+        corrected.push_str(&fixed);
+    }
+    (!lines.is_empty()).then_some((corrected, lines))
+}
+
+/// `line` of a transcript with `correction` applied to its text, leaving
+/// headings, tags, speaker names and redaction placeholders alone.
+fn correct_line<'a>(layout: &Layout, correction: &Correction, line: &'a str) -> Cow<'a, str> {
+    let entry = line.trim_end();
+    if entry.is_empty()
+        || entry.starts_with('#')
+        || layout.parse_time_heading(entry).is_some()
+        || is_redaction_placeholder(entry)
+    {
+        return Cow::Borrowed(line);
+    }
+    let Some((_, text)) = layout.parse_speaker(entry) else {
+        return correction.apply(line);
+    };
+    if is_redaction_placeholder(text) {
+        return Cow::Borrowed(line);
+    }
+    match correction.apply(text) {
+        Cow::Borrowed(_) => Cow::Borrowed(line),
+        Cow::Owned(fixed) => {
+            // `text` is a slice of `line`.
+            let start = text.as_ptr() as usize - line.as_ptr() as usize;
+            let end = start + text.len();
+            Cow::Owned(format!("{}{fixed}{}", &line[..start], &line[end..]))
+        }
+    }
+}
+
+/// `content`, a JSONL log, with `correction` applied to the text of every
+/// record that isn't a redaction placeholder, or `None` when nothing
+/// matched.  Lines that don't parse are kept as they are.
+fn correct_log(
+    correction: &Correction,
+    content: &str,
+) -> Result<Option<(String, Vec<LineChange>)>> {
+    let mut corrected = String::with_capacity(content.len());
+    let mut lines = Vec::new();
+    for (i, line) in content.split_inclusive('\n').enumerate() {
+        let record = serde_json::from_str::<SegmentRecord>(line.trim_end())
+            .ok()
+            .filter(|record| !record.redacted);
+        let Some(mut record) = record else {
+            corrected.push_str(line);
+            continue;
+        };
+        let Cow::Owned(fixed) = correction.apply(&record.text) else {
+            corrected.push_str(line);
+            continue;
+        };
+        lines.push(LineChange {
+            line: i + 1,
+            before: std::mem::replace(&mut record.text, fixed.clone()),
+            after: fixed,
+        });
+        corrected.push_str(
+            &serde_json::to_string(&record)
+                .map_err(|e| HooverError::Output(format!("failed to serialize segment: {e}")))?,
+        );
+        corrected.push('\n');
+    }
+    Ok((!lines.is_empty()).then_some((corrected, lines)))
+}
+
+/// Apply `correction` to the files of the planned `changes`, record it for
+/// future segments, update the search index for the changed days and, with
+/// VCS enabled, commit.  Returns the days changed.
+///
+/// Each file is read again and corrected as it is now, so segments written
+/// since the plan are kept, and corrected too.
+pub fn apply(
+    config: &Config,
+    correction: &Correction,
+    changes: &[FileChange],
+) -> Result<Vec<NaiveDate>> {
+    let layout = Layout::new(&config.output.layout)?;
+    let vault = Vault::new(&config.output)?;
+    let mut days = Vec::new();
+    for change in changes {
+        let Some(content) = vault.read(&change.path)? else {
+            continue;
+        };
+        if let Some((corrected, _)) =
+            correct_file(&layout, correction, change.kind, change.date, &content)?
+        {
+            vault.write(&change.path, &corrected)?;
+            days.push(change.date);
+        }
+    }
+    let output_dir = Config::expand_path(&config.output.directory);
+    add_rule(&output_dir.join(RULES_FILE), correction.rule())?;
+
+    days.sort();
+    days.dedup();
+    if !days.is_empty() {
+        tracing::info!("corrected {} files over {} days", changes.len(), days.len());
+        if let Some(path) = config.output.index_path()
+            && path.exists()
+        {
+            index::reindex_days(config, &path, &days)?;
+        }
+    }
+
+    if config.vcs.enabled && output_dir.join(".git").exists() {
+        git::add_and_commit(&output_dir, &commit_message(correction.rule(), &days))?;
+    }
+    Ok(days)
+}
+
+fn commit_message(rule: &CorrectionRule, days: &[NaiveDate]) -> String {
+    let fix = format!(
+        "fix transcripts: {:?} -> {:?}",
+        rule.pattern, rule.replacement
+    );
+    if days.is_empty() {
+        return fix;
+    }
+    let days: Vec<String> = days.iter().map(ToString::to_string).collect();
+    format!("{fix} ({})", days.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stt::TranscriptionSegment;
+    use chrono::Utc;
+
+    fn config(dir: &Path, yaml: &str) -> Config {
+        let mut config: Config = serde_yaml_ng::from_str(yaml).unwrap_or_else(|e| panic!("{e}"));
+        config.output.directory = dir.to_string_lossy().to_string();
+        config
+    }
+
+    fn rule(pattern: &str, replacement: &str, regex: bool) -> Correction {
+        Correction::new(CorrectionRule {
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+            regex,
+            ignore_case: false,
+        })
+        .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    #[test]
+    fn literal_rules_match_whole_words() {
+        let jon = rule("Jon", "John", false);
+        assert_eq!(jon.apply("Jon met Jonathan"), "John met Jonathan");
+        assert!(matches!(jon.apply("no match"), Cow::Borrowed(_)));
+        assert_eq!(
+            rule("$5", "five dollars", false).apply("it was $5."),
+            "it was five dollars."
+        );
+        assert_eq!(
+            rule(r"(\w+) ?Kubernetes", "$1 Kubernetes", true).apply("run Kubernetes"),
+            "run Kubernetes"
+        );
+        assert_eq!(
+            rule(r"Cube (\w+)", "Kube$1", true).apply("Cube ctl"),
+            "Kubectl"
+        );
+        assert!(
+            Correction::new(CorrectionRule {
+                pattern: "(".to_string(),
+                replacement: String::new(),
+                regex: true,
+                ignore_case: false,
+            })
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn recorded_rules_apply_to_new_segments() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let config = config(dir.path(), "{}");
+        let mut corrector = Corrector::new(&config).unwrap_or_else(|e| panic!("{e}"));
+
+        let turn = |text: &str| AttributedSegment {
+            segment: TranscriptionSegment {
+                text: text.to_string(),
+                timestamp: Utc::now(),
+                duration_secs: 1.0,
+                confidence: None,
+            },
+            speaker: None,
+            speaker_confidence: None,
+            redacted: false,
+        };
+        let mut turns = vec![turn("ask Jon")];
+        corrector.correct_turns(&mut turns);
+        assert_eq!(turns[0].segment.text, "ask Jon");
+
+        add_rule(
+            &dir.path().join(RULES_FILE),
+            rule("jon", "John", false).rule(),
+        )
+        .unwrap_or_else(|e| panic!("{e}"));
+        let mut ignore_case = rule("jon", "John", false).rule().clone();
+        ignore_case.ignore_case = true;
+        add_rule(&dir.path().join(RULES_FILE), &ignore_case).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(
+            read_rules(&dir.path().join(RULES_FILE))
+                .unwrap_or_else(|e| panic!("{e}"))
+                .len(),
+            1
+        );
+
+        // Rules are picked up without restarting.
+        corrector.modified = None;
+        let mut turns = vec![turn("ask Jon"), turn("ask jon")];
+        corrector.correct_turns(&mut turns);
+        assert_eq!(turns[0].segment.text, "ask John");
+        assert_eq!(turns[1].segment.text, "ask John");
+    }
+
+    #[test]
+    fn fix_rewrites_history_and_commits() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let config = config(dir.path(), "vcs:\n  enabled: true\n");
+        let layout = Layout::new(&config.output.layout).unwrap_or_else(|e| panic!("{e}"));
+        let day = |d| NaiveDate::from_ymd_opt(2026, 10, d).unwrap_or_default();
+        for d in [1, 2, 3] {
+            let path = layout.day_file(dir.path(), day(d));
+            let header = layout.day_header(day(d));
+            fs::create_dir_all(path.parent().unwrap_or_else(|| dir.path()))
+                .unwrap_or_else(|e| panic!("{e}"));
+            fs::write(
+                &path,
+                format!("{header}### 10:00\n\n**Alice:** ask Jon about it\n"),
+            )
+            .unwrap_or_else(|e| panic!("{e}"));
+        }
+        let record = SegmentRecord {
+            text: "ask Jon".to_string(),
+            timestamp: Utc::now(),
+            duration_secs: 1.0,
+            confidence: None,
+            speaker: None,
+            speaker_confidence: None,
+            redacted: false,
+            backend: "whisper".to_string(),
+            source: "microphone".to_string(),
+            chunk_id: "c1".to_string(),
+        };
+        let vault = Vault::new(&config.output).unwrap_or_else(|e| panic!("{e}"));
+        jsonl::append_record(&layout, &vault, dir.path(), day(2), &record)
+            .unwrap_or_else(|e| panic!("{e}"));
+        git::add_and_commit(dir.path(), "initial").unwrap_or_else(|e| panic!("{e}"));
+
+        let jon = rule("Jon", "John", false);
+        let changes = plan(&config, &jon, Some(day(2)), None).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(changes.len(), 3);
+        assert_eq!(
+            changes[0].lines,
+            [LineChange {
+                line: 5,
+                before: "**Alice:** ask Jon about it".to_string(),
+                after: "**Alice:** ask John about it".to_string(),
+            }]
+        );
+
+        let days = apply(&config, &jon, &changes).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(days, [day(2), day(3)]);
+        let read = |d| fs::read_to_string(layout.day_file(dir.path(), day(d))).unwrap_or_default();
+        assert!(read(1).contains("ask Jon about"));
+        assert!(read(2).contains("ask John about"));
+        assert!(read(3).contains("ask John about"));
+        let records =
+            jsonl::read_day(&layout, &vault, dir.path(), day(2)).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(records[0].text, "ask John");
+        assert!(dir.path().join(RULES_FILE).exists());
+
+        let log = git::commit_log(dir.path(), 1).unwrap_or_else(|e| panic!("{e}"));
+        assert!(log[0].contains(r#"fix transcripts: "Jon" -> "John" (2026-10-02, 2026-10-03)"#));
+    }
+
+    #[test]
+    fn fix_corrects_entry_text_only() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let config = config(dir.path(), "");
+        let layout = Layout::new(&config.output.layout).unwrap_or_else(|e| panic!("{e}"));
+        let day = NaiveDate::from_ymd_opt(2026, 10, 2).unwrap_or_default();
+        let path = layout.day_file(dir.path(), day);
+        let header = layout.day_header(day);
+        fs::create_dir_all(path.parent().unwrap_or_else(|| dir.path()))
+            .unwrap_or_else(|e| panic!("{e}"));
+        fs::write(
+            &path,
+            format!(
+                "{header}### 14:05\n\n**Jon:** call Jon at 9\n_[other speaker, 12s]_\n\n### Bookmark: Jon 2\n"
+            ),
+        )
+        .unwrap_or_else(|e| panic!("{e}"));
+
+        let jon = rule("Jon", "John", false);
+        let changes = plan(&config, &jon, None, None).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].lines[0].after, "**Jon:** call John at 9");
+        let digits = rule(r"\d+", "N", true);
+        let changes = plan(&config, &digits, None, None).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(changes[0].lines.len(), 1);
+        assert_eq!(changes[0].lines[0].after, "**Jon:** call Jon at N");
+
+        // A segment the recorder appends after the preview is kept.
+        let mut content = fs::read_to_string(&path).unwrap_or_else(|e| panic!("{e}"));
+        content.push_str("**Alice:** 42 more\n");
+        fs::write(&path, content).unwrap_or_else(|e| panic!("{e}"));
+        apply(&config, &digits, &changes).unwrap_or_else(|e| panic!("{e}"));
+        let content = fs::read_to_string(&path).unwrap_or_else(|e| panic!("{e}"));
+        assert!(content.contains("### 14:05\n"));
+        assert!(content.contains("_[other speaker, 12s]_"));
+        assert!(content.contains("### Bookmark: Jon 2"));
+        assert!(content.contains("**Jon:** call Jon at N\n"));
+        assert!(content.contains("**Alice:** N more\n"));
+    }
+}
@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::output::ChunkContext;
+use crate::speaker::AttributedSegment;
+use crate::stt::TranscriptionSegment;
+
+/// Words at the end of the text kept so far that the next chunk is aligned
+/// against; more than fit in an overlap.
+const TRAILING_WORDS: usize = 40;
+
+/// Leeway for STT timestamps, which are off by a few hundred milliseconds.
+const TIMESTAMP_TOLERANCE_MS: i64 = 300;
+
+/// Removes what a chunk repeats of the chunk before it.
+///
+/// Chunks overlap so that words cut at a chunk boundary are transcribed
+/// whole, which transcribes the start of every chunk twice.  Segments lying
+/// wholly inside audio an earlier chunk covered are dropped.  The words of
+/// the segments that start inside it are aligned against the end of the text
+/// kept so far by edit distance over normalized words, so differences in
+/// punctuation, case or a misrecognized word don't defeat it, and the
+/// repeated ones are removed.
+///
+/// Each audio source is followed on its own.  Speakers don't matter, since
+/// the same audio can be attributed differently in the two chunks.
+#[derive(Debug, Default)]
+pub struct Deduplicator {
+    sources: HashMap<String, SourceState>,
+}
+
+#[derive(Debug, Default)]
+struct SourceState {
+    /// End of the audio transcribed so far.
+    covered_until: Option<DateTime<Utc>>,
+    /// Normalized words at the end of the text kept so far.
+    trailing: Vec<String>,
+}
+
+impl Deduplicator {
+    /// Remove the turns and words of a chunk's `turns` that repeat earlier
+    /// chunks from the same source.
+    pub fn dedup_turns(&mut self, context: &ChunkContext, turns: &mut Vec<AttributedSegment>) {
+        let state = self.sources.entry(context.source.clone()).or_default();
+        let tolerance = Duration::milliseconds(TIMESTAMP_TOLERANCE_MS);
+
+        if let Some(covered) = state.covered_until {
+            turns.retain(|turn| end_of(&turn.segment) > covered + tolerance);
+
+            // Words of the turns starting inside the covered audio, as
+            // (turn, word) positions.
+            let mut positions = Vec::new();
+            let mut words = Vec::new();
+            for (i, turn) in turns.iter().enumerate() {
+                if turn.redacted || turn.segment.timestamp + tolerance >= covered {
+                    continue;
+                }
+                for (j, word) in turn.segment.text.split_whitespace().enumerate() {
+                    let word = normalize(word);
+                    if !word.is_empty() {
+                        positions.push((i, j));
+                        words.push(word);
+                    }
+                }
+            }
+
+            let repeated = overlap_len(&state.trailing, &words);
+            if repeated > 0 {
+                let (last_turn, last_word) = positions[repeated - 1];
+                for (i, turn) in turns.iter_mut().enumerate().take(last_turn + 1) {
+                    if positions[..repeated].iter().all(|&(t, _)| t != i) {
+                        continue;
+                    }
+                    let skip = if i == last_turn {
+                        last_word + 1
+                    } else {
+                        usize::MAX
+                    };
+                    let kept: Vec<&str> = turn.segment.text.split_whitespace().skip(skip).collect();
+                    turn.segment.text = kept.join(" ");
+                }
+                turns.retain(|turn| !turn.segment.text.is_empty());
+            }
+        }
+
+        for turn in turns.iter().filter(|turn| !turn.redacted) {
+            state.trailing.extend(
+                turn.segment
+                    .text
+                    .split_whitespace()
+                    .map(normalize)
+                    .filter(|word| !word.is_empty()),
+            );
+        }
+        let excess = state.trailing.len().saturating_sub(TRAILING_WORDS);
+        state.trailing.drain(..excess);
+
+        let ends = turns.iter().map(|turn| end_of(&turn.segment));
+        state.covered_until = std::iter::once(context.end())
+            .chain(state.covered_until)
+            .chain(ends)
+            .max();
+    }
+}
+
+fn end_of(segment: &TranscriptionSegment) -> DateTime<Utc> {
+    segment.timestamp + Duration::milliseconds((segment.duration_secs * 1000.0) as i64)
+}
+
+/// `word` lowercased, without punctuation.
+fn normalize(word: &str) -> String {
+    word.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// How many of `words` repeat the end of `trailing`.
+///
+/// Every prefix of `words` is aligned against the suffixes of `trailing`
+/// about as long, allowing one edit per four words.  The alignment with the
+/// most matched words, less two for each edit, wins, so extending a match
+/// with words that don't match never pays.
+fn overlap_len(trailing: &[String], words: &[String]) -> usize {
+    let mut best = (0, 0);
+    for len in 1..=words.len().min(TRAILING_WORDS) {
+        let allowed = (len + 1) / 4;
+        let prefix = &words[..len];
+        let shortest = len.saturating_sub(allowed).max(1);
+        let longest = (len + allowed).min(trailing.len());
+        let Some(edits) = (shortest..=longest)
+            .map(|suffix| edit_distance(&trailing[trailing.len() - suffix..], prefix))
+            .min()
+            .filter(|&edits| edits <= allowed)
+        else {
+            continue;
+        };
+        let score = len.saturating_sub(2 * edits);
+        if score > best.1 {
+            best = (len, score);
+        }
+    }
+    best.0
+}
+
+/// Levenshtein distance between two word sequences.
+fn edit_distance(a: &[String], b: &[String]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, x) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, y) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(x != y);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn start() -> DateTime<Utc> {
+        DateTime::from_timestamp(1_800_000_000, 0).unwrap_or_default()
+    }
+
+    fn context(source: &str, from_secs: i64, duration_secs: f32) -> ChunkContext {
+        ChunkContext {
+            source: source.to_string(),
+            start: start() + Duration::seconds(from_secs),
+            duration_secs,
+            ..ChunkContext::default()
+        }
+    }
+
+    fn turn(at_secs: i64, duration_secs: f32, speaker: &str, text: &str) -> AttributedSegment {
+        AttributedSegment {
+            segment: TranscriptionSegment {
+                text: text.to_string(),
+                timestamp: start() + Duration::seconds(at_secs),
+                duration_secs,
+                confidence: None,
+            },
+            speaker: Some(speaker.to_string()),
+            speaker_confidence: None,
+            redacted: false,
+        }
+    }
+
+    fn texts(turns: &[AttributedSegment]) -> Vec<&str> {
+        turns.iter().map(|t| t.segment.text.as_str()).collect()
+    }
+
+    fn words(text: &str) -> Vec<String> {
+        text.split_whitespace().map(normalize).collect()
+    }
+
+    #[test]
+    fn overlap_allows_a_misrecognized_word() {
+        let trailing = words("so the quick brown fox");
+        assert_eq!(overlap_len(&trailing, &words("brown fox jumps over")), 2);
+        assert_eq!(overlap_len(&trailing, &words("Quick, brown fax jumps")), 3);
+        assert_eq!(
+            overlap_len(&trailing, &words("completely different text")),
+            0
+        );
+        assert_eq!(overlap_len(&[], &words("first segment")), 0);
+        // Words after the repeated ones are never counted as edits.
+        assert_eq!(overlap_len(&words("a b c d"), &words("c d x y z")), 2);
+    }
+
+    #[test]
+    fn repeated_audio_is_removed() {
+        let mut dedup = Deduplicator::default();
+
+        let mut first = vec![
+            turn(0, 20.0, "Alice", "Let's start with the budget."),
+            turn(40, 19.0, "Alice", "We agreed on the quick brown fox."),
+        ];
+        dedup.dedup_turns(&context("microphone", 0, 60.0), &mut first);
+        assert_eq!(texts(&first).len(), 2);
+
+        // The next chunk starts 5 seconds before the first one ended.
+        let mut second = vec![
+            turn(55, 3.0, "Alice", "on the quick"),
+            turn(57, 4.0, "Bob", "Quick brown fax. Sounds good."),
+            turn(70, 2.0, "Bob", "Sounds good."),
+        ];
+        dedup.dedup_turns(&context("microphone", 55, 60.0), &mut second);
+        assert_eq!(texts(&second), ["Sounds good.", "Sounds good."]);
+
+        // Another source is deduplicated on its own.
+        let mut other = vec![turn(56, 4.0, "Carol", "the quick brown fox")];
+        dedup.dedup_turns(&context("udp", 56, 30.0), &mut other);
+        assert_eq!(texts(&other), ["the quick brown fox"]);
+    }
+
+    #[test]
+    fn chunks_without_overlap_keep_repeated_words() {
+        let mut dedup = Deduplicator::default();
+        let mut first = vec![turn(0, 9.0, "Alice", "no")];
+        dedup.dedup_turns(&context("microphone", 0, 10.0), &mut first);
+
+        let mut second = vec![turn(10, 2.0, "Alice", "no, no")];
+        dedup.dedup_turns(&context("microphone", 10, 10.0), &mut second);
+        assert_eq!(texts(&second), ["no, no"]);
+    }
+}
@@ -32,6 +32,15 @@ pub enum HooverError {
     #[error("speaker identification error: {0}")]
     Speaker(String),
 
+    #[error("index error: {0}")]
+    Index(String),
+
+    #[error("summary error: {0}")]
+    Summary(String),
+
+    #[error("control socket error: {0}")]
+    Control(String),
+
     #[error("I/O error: {0}")]
     Io(#[from] io::Error),
 
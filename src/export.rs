@@ -0,0 +1,387 @@
+use std::fmt::Write as _;
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, TimeDelta};
+use serde::Serialize;
+
+use crate::config::OutputConfig;
+use crate::error::{HooverError, Result};
+use crate::output::calendar::Calendar;
+use crate::output::jsonl;
+use crate::output::layout::Layout;
+use crate::output::markdown;
+use crate::output::route::Routes;
+use crate::vault::Vault;
+
+/// Speaking rate used to estimate how long a markdown entry lasted.
+const WORDS_PER_SECOND: f32 = 2.5;
+
+/// Shortest time a subtitle stays on screen.
+const MIN_DURATION_SECS: f32 = 1.0;
+
+/// Output format of `hoover export`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Srt,
+    Vtt,
+    Txt,
+    Html,
+    Json,
+}
+
+/// Which segments to export.
+#[derive(Debug, Clone)]
+pub struct ExportFilter {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// Only segments at or after this time of day.
+    pub since: Option<NaiveTime>,
+    /// Only segments before this time of day.  Earlier than `since` selects
+    /// a window spanning midnight.
+    pub until: Option<NaiveTime>,
+    /// Only segments from these speakers (`unknown` for none); all when
+    /// empty.
+    pub speakers: Vec<String>,
+}
+
+impl ExportFilter {
+    fn accepts_time(&self, time: NaiveTime) -> bool {
+        match (self.since, self.until) {
+            (Some(since), Some(until)) if until < since => time >= since || time < until,
+            (since, until) => {
+                since.is_none_or(|since| time >= since) && until.is_none_or(|until| time < until)
+            }
+        }
+    }
+
+    fn accepts_speaker(&self, speaker: Option<&str>) -> bool {
+        let name = speaker.unwrap_or("unknown");
+        self.speakers.is_empty() || self.speakers.iter().any(|s| s.eq_ignore_ascii_case(name))
+    }
+}
+
+/// A transcript segment reconstructed from the daily files.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExportSegment {
+    /// The day whose transcript the segment is in.
+    pub day: NaiveDate,
+    /// Wall-clock start time.  Only as precise as the time headings when read
+    /// from markdown.
+    pub start: NaiveDateTime,
+    /// Duration in seconds, estimated from the word count when read from
+    /// markdown.
+    pub duration_secs: f32,
+    pub speaker: Option<String>,
+    pub text: String,
+}
+
+impl ExportSegment {
+    fn end(&self) -> NaiveDateTime {
+        self.start + secs(self.duration_secs)
+    }
+}
+
+fn secs(secs: f32) -> TimeDelta {
+    TimeDelta::milliseconds((secs * 1000.0).round() as i64)
+}
+
+fn estimated_duration(text: &str) -> f32 {
+    (text.split_whitespace().count() as f32 / WORDS_PER_SECOND).max(MIN_DURATION_SECS)
+}
+
+/// Reconstruct the segments matching `filter`, in time order, from every
+/// directory daily files are written to.
+///
+/// A day's JSONL log is used when present, as it has exact times and
+/// durations; otherwise the day's markdown is parsed.
+pub fn collect_segments(
+    output: &OutputConfig,
+    filter: &ExportFilter,
+) -> Result<Vec<ExportSegment>> {
+    let routes = Routes::new(output)?;
+    let layout = Layout::new(&output.layout)?;
+    let calendar = Calendar::new(output)?;
+    let vault = Vault::new(output)?;
+    let mut segments = Vec::new();
+
+    for date in filter
+        .from
+        .iter_days()
+        .take_while(|date| *date <= filter.to)
+    {
+        for dir in routes.all_directories() {
+            let records = jsonl::read_day(&layout, &vault, dir, date)?;
+            if records.is_empty() {
+                segments.extend(read_markdown_day(&layout, &calendar, &vault, dir, date)?);
+            } else {
+                segments.extend(records.into_iter().map(|record| ExportSegment {
+                    day: date,
+                    start: calendar.local(record.timestamp),
+                    duration_secs: record.duration_secs,
+                    speaker: record.speaker,
+                    text: record.text,
+                }));
+            }
+        }
+    }
+
+    segments.retain(|s| {
+        filter.accepts_time(s.start.time()) && filter.accepts_speaker(s.speaker.as_deref())
+    });
+    segments.sort_by_key(|s| s.start);
+    // Routes with `copy` write the same segment to several directories.
+    segments.dedup_by(|a, b| a.start == b.start && a.speaker == b.speaker && a.text == b.text);
+    Ok(segments)
+}
+
+/// Entries under the same time heading are laid out one after the other,
+/// each lasting its estimated duration.
+fn read_markdown_day(
+    layout: &Layout,
+    calendar: &Calendar,
+    vault: &Vault,
+    dir: &std::path::Path,
+    date: NaiveDate,
+) -> Result<Vec<ExportSegment>> {
+    let Some(content) = vault.read(&layout.day_file(dir, date))? else {
+        return Ok(Vec::new());
+    };
+
+    let mut segments: Vec<ExportSegment> = Vec::new();
+    let mut previous_end = calendar.day_start(date);
+    for entry in markdown::parse_day(&content, date, layout) {
+        let heading = entry
+            .time
+            .as_deref()
+            .and_then(|t| {
+                NaiveTime::parse_from_str(t, "%H:%M:%S")
+                    .or_else(|_| NaiveTime::parse_from_str(t, "%H:%M"))
+                    .ok()
+            })
+            .map(|t| calendar.on_day(date, t));
+        let start = heading.map_or(previous_end, |h| h.max(previous_end));
+        let segment = ExportSegment {
+            day: date,
+            start,
+            duration_secs: estimated_duration(&entry.text),
+            speaker: entry.speaker,
+            text: entry.text,
+        };
+        previous_end = segment.end();
+        segments.push(segment);
+    }
+    Ok(segments)
+}
+
+/// Render segments in `format`.
+///
+/// Subtitle times are offsets from the start of the first segment.  A cue
+/// ends when the next one starts, if that is sooner than its duration.
+pub fn render(segments: &[ExportSegment], format: ExportFormat) -> Result<String> {
+    Ok(match format {
+        ExportFormat::Srt => render_subtitles(segments, false),
+        ExportFormat::Vtt => render_subtitles(segments, true),
+        ExportFormat::Txt => render_text(segments),
+        ExportFormat::Html => render_html(segments),
+        ExportFormat::Json => serde_json::to_string_pretty(segments)
+            .map_err(|e| HooverError::Output(format!("failed to serialize export: {e}")))?,
+    })
+}
+
+fn render_subtitles(segments: &[ExportSegment], vtt: bool) -> String {
+    let mut out = String::new();
+    if vtt {
+        out.push_str("WEBVTT\n\n");
+    }
+    let Some(origin) = segments.first().map(|s| s.start) else {
+        return out;
+    };
+
+    for (i, segment) in segments.iter().enumerate() {
+        let next_start = segments.get(i + 1).map(|next| next.start);
+        let end = match next_start {
+            Some(next) if next > segment.start && next < segment.end() => next,
+            _ => segment.end(),
+        };
+        let start = cue_time(segment.start - origin, vtt);
+        let end = cue_time(end - origin, vtt);
+
+        if vtt {
+            let text = escape_html(&segment.text);
+            let _ = match &segment.speaker {
+                Some(name) => writeln!(out, "{start} --> {end}\n<v {}>{text}\n", escape_html(name)),
+                None => writeln!(out, "{start} --> {end}\n{text}\n"),
+            };
+        } else {
+            let _ = writeln!(out, "{}\n{start} --> {end}", i + 1);
+            let _ = match &segment.speaker {
+                Some(name) => writeln!(out, "{name}: {}\n", segment.text),
+                None => writeln!(out, "{}\n", segment.text),
+            };
+        }
+    }
+    out
+}
+
+/// `HH:MM:SS,mmm` for SRT, `HH:MM:SS.mmm` for `WebVTT`.
+fn cue_time(offset: TimeDelta, vtt: bool) -> String {
+    let millis = offset.num_milliseconds().max(0);
+    let separator = if vtt { '.' } else { ',' };
+    format!(
+        "{:02}:{:02}:{:02}{separator}{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+fn render_text(segments: &[ExportSegment]) -> String {
+    let mut out = String::new();
+    for segment in segments {
+        let start = segment.start.format("%Y-%m-%d %H:%M:%S");
+        let _ = match &segment.speaker {
+            Some(name) => writeln!(out, "[{start}] {name}: {}", segment.text),
+            None => writeln!(out, "[{start}] {}", segment.text),
+        };
+    }
+    out
+}
+
+fn render_html(segments: &[ExportSegment]) -> String {
+    let mut out = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Transcript</title>\n</head>\n<body>\n",
+    );
+    let mut current_date = None;
+    for segment in segments {
+        let date = segment.day;
+        if current_date != Some(date) {
+            current_date = Some(date);
+            let _ = writeln!(out, "<h2>{}</h2>", date.format("%A, %B %-d, %Y"));
+        }
+        let _ = write!(
+            out,
+            "<p><time datetime=\"{}\">{}</time> ",
+            segment.start.format("%Y-%m-%dT%H:%M:%S"),
+            segment.start.format("%H:%M:%S")
+        );
+        if let Some(name) = &segment.speaker {
+            let _ = write!(out, "<strong>{}:</strong> ", escape_html(name));
+        }
+        let _ = writeln!(out, "{}</p>", escape_html(&segment.text));
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn at(time: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(&format!("2026-03-02 {time}"), "%Y-%m-%d %H:%M:%S")
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    fn segment(time: &str, duration_secs: f32, speaker: Option<&str>, text: &str) -> ExportSegment {
+        ExportSegment {
+            day: at(time).date(),
+            start: at(time),
+            duration_secs,
+            speaker: speaker.map(str::to_string),
+            text: text.to_string(),
+        }
+    }
+
+    fn filter(date: NaiveDate) -> ExportFilter {
+        ExportFilter {
+            from: date,
+            to: date,
+            since: None,
+            until: None,
+            speakers: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn collects_markdown_with_filters() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let date = at("00:00:00").date();
+        let layout = Layout::new(&OutputConfig::default().layout).unwrap_or_else(|e| panic!("{e}"));
+        fs::write(
+            layout.day_file(dir.path(), date),
+            "# Mon, March 2, 2026\n\n## 09:00\n\n**Alice:** one two three four five\n\n\
+             **Bob:** reply\n\n## 14:30\n\n**Alice:** afternoon\n\n",
+        )
+        .unwrap_or_else(|e| panic!("{e}"));
+        let config = OutputConfig {
+            directory: dir.path().to_string_lossy().to_string(),
+            ..OutputConfig::default()
+        };
+
+        let all = collect_segments(&config, &filter(date)).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].start, at("09:00:00"));
+        // Laid out after the previous entry's estimated two seconds.
+        assert_eq!(all[1].start, at("09:00:02"));
+
+        let mut morning_alice = filter(date);
+        morning_alice.until = NaiveTime::from_hms_opt(12, 0, 0);
+        morning_alice.speakers = vec!["alice".to_string()];
+        let selected = collect_segments(&config, &morning_alice).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].text, "one two three four five");
+
+        let mut overnight = filter(date);
+        overnight.since = NaiveTime::from_hms_opt(14, 0, 0);
+        overnight.until = NaiveTime::from_hms_opt(9, 0, 0);
+        let late = collect_segments(&config, &overnight).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(late.len(), 1);
+        assert_eq!(late[0].text, "afternoon");
+    }
+
+    #[test]
+    fn renders_subtitles_relative_to_first_segment() {
+        let segments = vec![
+            segment("10:00:00", 4.0, Some("Alice"), "hello <there>"),
+            segment("10:00:02", 1.5, None, "overlap"),
+        ];
+
+        let srt = render(&segments, ExportFormat::Srt).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(
+            srt,
+            "1\n00:00:00,000 --> 00:00:02,000\nAlice: hello <there>\n\n\
+             2\n00:00:02,000 --> 00:00:03,500\noverlap\n\n"
+        );
+
+        let vtt = render(&segments, ExportFormat::Vtt).unwrap_or_else(|e| panic!("{e}"));
+        assert!(vtt.starts_with("WEBVTT\n\n00:00:00.000 --> 00:00:02.000\n"));
+        assert!(vtt.contains("<v Alice>hello &lt;there&gt;\n"));
+    }
+
+    #[test]
+    fn renders_text_html_and_json() {
+        let segments = vec![segment("10:00:00", 1.0, Some("Alice"), "a & b")];
+
+        let txt = render(&segments, ExportFormat::Txt).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(txt, "[2026-03-02 10:00:00] Alice: a & b\n");
+
+        let html = render(&segments, ExportFormat::Html).unwrap_or_else(|e| panic!("{e}"));
+        assert!(html.contains("<h2>Monday, March 2, 2026</h2>"));
+        assert!(html.contains("<strong>Alice:</strong> a &amp; b</p>"));
+
+        let json = render(&segments, ExportFormat::Json).unwrap_or_else(|e| panic!("{e}"));
+        let parsed: serde_json::Value =
+            serde_json::from_str(&json).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(parsed[0]["day"], "2026-03-02");
+        assert_eq!(parsed[0]["start"], "2026-03-02T10:00:00");
+        assert_eq!(parsed[0]["speaker"], "Alice");
+    }
+}
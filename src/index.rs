@@ -0,0 +1,546 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+use rusqlite::{Connection, params};
+
+use crate::config::Config;
+use crate::error::{HooverError, Result};
+use crate::output::ChunkContext;
+use crate::output::calendar::Calendar;
+use crate::output::jsonl;
+use crate::output::layout::Layout;
+use crate::output::markdown;
+use crate::output::route::Routes;
+use crate::output::sink::OutputSink;
+use crate::speaker::{AttributedSegment, is_redaction_placeholder};
+use crate::vault::Vault;
+
+/// Full-text table over every segment.  Only `text` and `speaker` are
+/// searchable; the prefix indexes speed up `term*` queries.
+const SCHEMA: &str = "
+CREATE VIRTUAL TABLE IF NOT EXISTS segments USING fts5(
+    text,
+    speaker,
+    date UNINDEXED,
+    time UNINDEXED,
+    source UNINDEXED,
+    prefix = '2 3'
+);
+";
+
+/// Number of results returned when no limit is given.
+pub const DEFAULT_SEARCH_LIMIT: usize = 50;
+
+/// A segment as stored in the index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexEntry {
+    /// Day the segment belongs to, `YYYY-MM-DD`.
+    pub date: String,
+    /// Wall-clock time, `HH:MM:SS` for recorded segments or `HH:MM` when rebuilt
+    /// from markdown.
+    pub time: Option<String>,
+    pub speaker: Option<String>,
+    pub text: String,
+    /// Where the audio came from, when known.
+    pub source: Option<String>,
+}
+
+/// Full-text (`FTS5`) index of transcribed segments.
+pub struct SearchIndex {
+    conn: Connection,
+}
+
+impl SearchIndex {
+    /// Open the index at `path`, creating it if needed.
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(path)
+            .map_err(|e| HooverError::Index(format!("failed to open {}: {e}", path.display())))?;
+        // WAL lets the MCP server search while recording writes.
+        conn.pragma_update(None, "journal_mode", "WAL")
+            .and_then(|()| conn.execute_batch(SCHEMA))
+            .map_err(|e| {
+                HooverError::Index(format!("failed to initialize {}: {e}", path.display()))
+            })?;
+        Ok(Self { conn })
+    }
+
+    pub fn insert(&self, entry: &IndexEntry) -> Result<()> {
+        insert_entry(&self.conn, entry)
+    }
+
+    /// Replace the whole index with `entries`.  Returns the number indexed.
+    pub fn rebuild(&mut self, entries: &[IndexEntry]) -> Result<usize> {
+        let tx = self
+            .conn
+            .transaction()
+            .map_err(|e| HooverError::Index(format!("failed to start rebuild: {e}")))?;
+        tx.execute("DELETE FROM segments", [])
+            .map_err(|e| HooverError::Index(format!("failed to clear index: {e}")))?;
+        for entry in entries {
+            insert_entry(&tx, entry)?;
+        }
+        tx.commit()
+            .map_err(|e| HooverError::Index(format!("failed to commit rebuild: {e}")))?;
+        Ok(entries.len())
+    }
+
+    /// Drop every segment from the given `YYYY-MM-DD` days.  Returns the
+    /// number removed.
+    pub fn remove_days(&mut self, dates: &[String]) -> Result<usize> {
+        let tx = self
+            .conn
+            .transaction()
+            .map_err(|e| HooverError::Index(format!("failed to start removal: {e}")))?;
+        let mut removed = 0;
+        for date in dates {
+            removed += tx
+                .execute("DELETE FROM segments WHERE date = ?1", params![date])
+                .map_err(|e| HooverError::Index(format!("failed to remove {date}: {e}")))?;
+        }
+        tx.commit()
+            .map_err(|e| HooverError::Index(format!("failed to commit removal: {e}")))?;
+        Ok(removed)
+    }
+
+    /// Search with FTS5 query syntax: terms, `"exact phrases"`, `prefix*`,
+    /// `AND`/`OR`/`NOT`.  Best matches come first.  A query FTS5 cannot
+    /// parse is retried with every term quoted.
+    pub fn search(
+        &self,
+        query: &str,
+        from: Option<&str>,
+        to: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<IndexEntry>> {
+        match self.query(query, from, to, limit) {
+            Ok(hits) => Ok(hits),
+            Err(e) => {
+                let quoted = quote_terms(query);
+                if quoted.is_empty() || quoted == query {
+                    return Err(e);
+                }
+                self.query(&quoted, from, to, limit)
+            }
+        }
+    }
+
+    fn query(
+        &self,
+        query: &str,
+        from: Option<&str>,
+        to: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<IndexEntry>> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT date, time, speaker, text, source FROM segments
+                 WHERE segments MATCH ?1
+                   AND (?2 IS NULL OR date >= ?2)
+                   AND (?3 IS NULL OR date <= ?3)
+                 ORDER BY rank, date, time
+                 LIMIT ?4",
+            )
+            .map_err(|e| HooverError::Index(format!("failed to prepare search: {e}")))?;
+
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+        let rows = stmt
+            .query_map(params![query, from, to, limit], |row| {
+                Ok(IndexEntry {
+                    date: row.get(0)?,
+                    time: row.get(1)?,
+                    speaker: row.get(2)?,
+                    text: row.get(3)?,
+                    source: row.get(4)?,
+                })
+            })
+            .map_err(|e| HooverError::Index(format!("search for '{query}' failed: {e}")))?;
+
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| HooverError::Index(format!("search for '{query}' failed: {e}")))
+    }
+}
+
+fn insert_entry(conn: &Connection, entry: &IndexEntry) -> Result<()> {
+    conn.execute(
+        "INSERT INTO segments (text, speaker, date, time, source) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            entry.text,
+            entry.speaker,
+            entry.date,
+            entry.time,
+            entry.source
+        ],
+    )
+    .map_err(|e| HooverError::Index(format!("failed to index segment: {e}")))?;
+    Ok(())
+}
+
+/// Quote every term of `query` so FTS5 reads it literally, keeping a trailing
+/// `*` as a prefix match.
+fn quote_terms(query: &str) -> String {
+    query
+        .split_whitespace()
+        .filter_map(|term| {
+            let (term, prefix) = term
+                .strip_suffix('*')
+                .map_or((term, ""), |stripped| (stripped, "*"));
+            let term = term.replace('"', "");
+            (!term.is_empty()).then(|| format!("\"{term}\"{prefix}"))
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Adds every written segment to the search index.
+pub struct IndexSink {
+    index: SearchIndex,
+    calendar: Calendar,
+}
+
+impl IndexSink {
+    pub fn new(path: &Path, calendar: Calendar) -> Result<Self> {
+        Ok(Self {
+            index: SearchIndex::open(path)?,
+            calendar,
+        })
+    }
+}
+
+impl OutputSink for IndexSink {
+    fn name(&self) -> &'static str {
+        "sqlite"
+    }
+
+    fn write_segment(&mut self, turn: &AttributedSegment, context: &ChunkContext) -> Result<()> {
+        if turn.redacted {
+            return Ok(());
+        }
+        let timestamp = turn.segment.timestamp;
+        let local = self.calendar.local(timestamp);
+        self.index.insert(&IndexEntry {
+            date: self.calendar.day_of(timestamp).to_string(),
+            time: Some(local.format("%H:%M:%S").to_string()),
+            speaker: turn.speaker.clone(),
+            text: turn.segment.text.clone(),
+            source: (!context.source.is_empty()).then(|| context.source.clone()),
+        })
+    }
+}
+
+/// Result of [`rebuild_index`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RebuildSummary {
+    pub days: usize,
+    pub segments: usize,
+}
+
+/// Rebuild the index configured in `output.sinks` from the daily files.
+///
+/// A day's JSONL log is used when present, as it keeps exact times and
+/// sources; otherwise the day's markdown is parsed.
+pub fn rebuild_index(config: &Config) -> Result<(PathBuf, RebuildSummary)> {
+    let Some(path) = config.output.index_path() else {
+        return Err(HooverError::Index(
+            "no `sqlite` sink is configured in output.sinks".to_string(),
+        ));
+    };
+
+    let routes = Routes::new(&config.output)?;
+    let layout = Layout::new(&config.output.layout)?;
+    let calendar = Calendar::new(&config.output)?;
+    let vault = Vault::new(&config.output)?;
+    let mut entries = Vec::new();
+    let mut days = 0;
+    let mut seen = HashSet::new();
+    for dir in routes.all_directories() {
+        for (date, path) in layout.day_files(dir)? {
+            days += 1;
+            let day = read_day_entries(&layout, &calendar, &vault, dir, date, &path)?;
+            entries.extend(skip_copies(&mut seen, day));
+        }
+    }
+
+    let mut index = SearchIndex::open(&path)?;
+    let segments = index.rebuild(&entries)?;
+    Ok((path, RebuildSummary { days, segments }))
+}
+
+/// Replace the entries of `dates` in the index at `path` with what the daily
+/// files hold now, after they were rewritten.  Returns the number of
+/// segments indexed.
+pub fn reindex_days(config: &Config, path: &Path, dates: &[NaiveDate]) -> Result<usize> {
+    let routes = Routes::new(&config.output)?;
+    let layout = Layout::new(&config.output.layout)?;
+    let calendar = Calendar::new(&config.output)?;
+    let vault = Vault::new(&config.output)?;
+
+    let days: Vec<String> = dates
+        .iter()
+        .map(|date| date.format("%Y-%m-%d").to_string())
+        .collect();
+    let mut index = SearchIndex::open(path)?;
+    index.remove_days(&days)?;
+    let mut segments = 0;
+    let mut seen = HashSet::new();
+    for dir in routes.all_directories() {
+        for &date in dates {
+            let day_file = layout.day_file(dir, date);
+            if vault.stored_path(&day_file).is_none() {
+                continue;
+            }
+            let day = read_day_entries(&layout, &calendar, &vault, dir, date, &day_file)?;
+            for entry in skip_copies(&mut seen, day) {
+                index.insert(&entry)?;
+                segments += 1;
+            }
+        }
+    }
+    Ok(segments)
+}
+
+/// The entries of one daily file that no file read before holds, as a `copy`
+/// route writes its segments to both its own directory and the main one.
+/// Repeats within the file are kept; `seen` collects what was read.
+fn skip_copies(
+    seen: &mut HashSet<(String, Option<String>, Option<String>, String)>,
+    entries: Vec<IndexEntry>,
+) -> Vec<IndexEntry> {
+    let key = |entry: &IndexEntry| {
+        (
+            entry.date.clone(),
+            entry.time.clone(),
+            entry.speaker.clone(),
+            entry.text.clone(),
+        )
+    };
+    let entries: Vec<IndexEntry> = entries
+        .into_iter()
+        .filter(|entry| !seen.contains(&key(entry)))
+        .collect();
+    seen.extend(entries.iter().map(key));
+    entries
+}
+
+fn read_day_entries(
+    layout: &Layout,
+    calendar: &Calendar,
+    vault: &Vault,
+    dir: &Path,
+    date: NaiveDate,
+    path: &Path,
+) -> Result<Vec<IndexEntry>> {
+    let day = date.format("%Y-%m-%d").to_string();
+
+    let records = jsonl::read_day(layout, vault, dir, date)?;
+    if !records.is_empty() {
+        return Ok(records
+            .into_iter()
+            .filter(|record| !record.redacted)
+            .map(|record| IndexEntry {
+                date: day.clone(),
+                time: Some(
+                    calendar
+                        .local(record.timestamp)
+                        .format("%H:%M:%S")
+                        .to_string(),
+                ),
+                speaker: record.speaker,
+                text: record.text,
+                source: Some(record.source),
+            })
+            .collect());
+    }
+
+    let content = vault.read(path)?.unwrap_or_default();
+    Ok(markdown::parse_day(&content, date, layout)
+        .into_iter()
+        .filter(|entry| !is_redaction_placeholder(&entry.text))
+        .map(|entry| IndexEntry {
+            date: day.clone(),
+            time: entry.time,
+            speaker: entry.speaker,
+            text: entry.text,
+            source: None,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::{Local, TimeZone};
+
+    fn entry(date: &str, speaker: &str, text: &str) -> IndexEntry {
+        IndexEntry {
+            date: date.to_string(),
+            time: Some("10:00".to_string()),
+            speaker: Some(speaker.to_string()),
+            text: text.to_string(),
+            source: None,
+        }
+    }
+
+    #[test]
+    fn ranked_phrase_and_prefix_search() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let mut index =
+            SearchIndex::open(&dir.path().join("index.sqlite")).unwrap_or_else(|e| panic!("{e}"));
+        index
+            .rebuild(&[
+                entry("2026-01-01", "Alice", "the quarterly budget review"),
+                entry("2026-01-02", "Bob", "budget budget budget"),
+                entry("2026-01-03", "Alice", "review the budget quarterly"),
+            ])
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        let search = |query: &str, from: Option<&str>| {
+            index
+                .search(query, from, None, DEFAULT_SEARCH_LIMIT)
+                .unwrap_or_else(|e| panic!("{e}"))
+        };
+
+        let hits = search("budget", None);
+        assert_eq!(hits.len(), 3);
+        assert_eq!(hits[0].speaker.as_deref(), Some("Bob"));
+
+        let hits = search("\"quarterly budget\"", None);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].date, "2026-01-01");
+
+        assert_eq!(search("quart*", None).len(), 2);
+        assert_eq!(search("budget", Some("2026-01-02")).len(), 2);
+
+        // Unbalanced quotes and punctuation fall back to literal terms.
+        assert_eq!(search("budget\" (review", None).len(), 2);
+
+        let removed = index
+            .remove_days(&["2026-01-01".to_string(), "2026-01-03".to_string()])
+            .unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(removed, 2);
+        let hits = index
+            .search("budget", None, None, DEFAULT_SEARCH_LIMIT)
+            .unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(hits.len(), 1);
+    }
+
+    #[test]
+    fn rebuild_reads_markdown_and_jsonl() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let index_path = dir.path().join("index.sqlite");
+        let output = dir.path().join("out");
+        fs::create_dir_all(&output).unwrap_or_else(|e| panic!("{e}"));
+        fs::write(
+            output.join("2026-01-01.md"),
+            "# Thursday, January 1, 2026\n\n## 09:15\n\n**Alice:** new year plans\n\n\
+             _[other speaker, 3s]_\n\n",
+        )
+        .unwrap_or_else(|e| panic!("{e}"));
+        fs::write(output.join("notes.md"), "not a day file\n").unwrap_or_else(|e| panic!("{e}"));
+
+        // A day with a structured log is read from it rather than the markdown.
+        fs::write(
+            output.join("2026-01-02.md"),
+            "# Friday, January 2, 2026\n\nstale\n",
+        )
+        .unwrap_or_else(|e| panic!("{e}"));
+        let noon = Local
+            .with_ymd_and_hms(2026, 1, 2, 12, 0, 0)
+            .single()
+            .unwrap_or_else(|| panic!("ambiguous time"));
+        let turn = AttributedSegment::unattributed(crate::stt::TranscriptionSegment {
+            text: "logged plans".to_string(),
+            timestamp: noon.with_timezone(&chrono::Utc),
+            duration_secs: 1.0,
+            confidence: None,
+        });
+        let context = ChunkContext {
+            source: "udp".to_string(),
+            ..ChunkContext::default()
+        };
+        let layout =
+            Layout::new(&crate::config::LayoutConfig::default()).unwrap_or_else(|e| panic!("{e}"));
+        jsonl::append_record(
+            &layout,
+            &crate::vault::Vault::default(),
+            &output,
+            noon.date_naive(),
+            &jsonl::SegmentRecord::new(&turn, &context),
+        )
+        .unwrap_or_else(|e| panic!("{e}"));
+
+        let yaml = format!(
+            "output:\n  directory: {}\n  sinks:\n    - type: sqlite\n      path: {}\n",
+            output.display(),
+            index_path.display()
+        );
+        let config: Config = serde_yaml_ng::from_str(&yaml).unwrap_or_else(|e| panic!("{e}"));
+
+        let (path, summary) = rebuild_index(&config).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(path, index_path);
+        assert_eq!(
+            summary,
+            RebuildSummary {
+                days: 2,
+                segments: 2
+            }
+        );
+
+        let index = SearchIndex::open(&index_path).unwrap_or_else(|e| panic!("{e}"));
+        let mut hits = index
+            .search("plans", None, None, 10)
+            .unwrap_or_else(|e| panic!("{e}"));
+        hits.sort_by(|a, b| a.date.cmp(&b.date));
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].time.as_deref(), Some("09:15"));
+        assert_eq!(hits[0].speaker.as_deref(), Some("Alice"));
+        assert_eq!(hits[1].time.as_deref(), Some("12:00:00"));
+        assert_eq!(hits[1].source.as_deref(), Some("udp"));
+        assert!(
+            index
+                .search("stale", None, None, 10)
+                .unwrap_or_else(|e| panic!("{e}"))
+                .is_empty()
+        );
+
+        let mut without_index = config;
+        without_index.output.sinks = None;
+        assert!(rebuild_index(&without_index).is_err());
+    }
+
+    #[test]
+    fn copied_segments_are_indexed_once() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let index_path = dir.path().join("index.sqlite");
+        let output = dir.path().join("out");
+        let day = "# Thursday, January 1, 2026\n\n## 09:15\n\n**Bob:** budget\n\n";
+        fs::create_dir_all(output.join("shared")).unwrap_or_else(|e| panic!("{e}"));
+        fs::write(output.join("shared/2026-01-01.md"), day).unwrap_or_else(|e| panic!("{e}"));
+        fs::write(
+            output.join("2026-01-01.md"),
+            format!("{day}**Alice:** budget\n\n**Alice:** budget\n\n"),
+        )
+        .unwrap_or_else(|e| panic!("{e}"));
+
+        let yaml = format!(
+            "output:\n  directory: {}\n  sinks:\n    - type: sqlite\n      path: {}\n  \
+             routes:\n    - speakers: [Bob]\n      directory: shared\n      copy: true\n",
+            output.display(),
+            index_path.display()
+        );
+        let config: Config = serde_yaml_ng::from_str(&yaml).unwrap_or_else(|e| panic!("{e}"));
+
+        let (_, summary) = rebuild_index(&config).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(summary.segments, 3);
+        let date = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap_or_default();
+        let segments =
+            reindex_days(&config, &index_path, &[date]).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(segments, 3);
+    }
+}
@@ -8,13 +8,23 @@ compile_error!("features `nogpu` and `cuda` conflict — use --no-default-featur
 compile_error!("features `nogpu` and `rocm` conflict");
 
 pub mod audio;
+pub mod commands;
 pub mod config;
+pub mod control;
+pub mod corrections;
+pub mod dedup;
 pub mod error;
+pub mod export;
+pub mod index;
 pub mod mcp;
 pub mod models;
 pub mod net;
 pub mod output;
 pub mod recording;
+pub mod redact;
+pub mod retention;
 pub mod speaker;
 pub mod stt;
+pub mod summary;
+pub mod vault;
 pub mod vcs;
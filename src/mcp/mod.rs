@@ -1,19 +1,27 @@
 pub mod repository;
 
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
 
 use rmcp::handler::server::router::tool::ToolRouter;
-use rmcp::handler::server::wrapper::Parameters;
+use rmcp::handler::server::wrapper::{Json, Parameters};
 use rmcp::model::{ServerCapabilities, ServerInfo};
 use rmcp::{tool_handler, tool_router};
 
 use crate::config::Config;
 use crate::error::HooverError;
+use crate::index::{DEFAULT_SEARCH_LIMIT, IndexEntry, SearchIndex};
+use crate::output::layout::Layout;
+use crate::output::markdown;
+use crate::vault::Vault;
+
+use repository::{CommitLog, FileHistory, RepoDiff, RepoStatus, TranscriptChanges};
 
 /// Run the MCP server on stdio transport.
 pub async fn run_mcp_server(config: Config) -> crate::error::Result<()> {
-    let service = HooverMcpService::new(config);
+    let service = HooverMcpService::new(config)?;
 
     let server = rmcp::ServiceExt::serve(service, rmcp::transport::io::stdio())
         .await
@@ -29,12 +37,16 @@ pub async fn run_mcp_server(config: Config) -> crate::error::Result<()> {
 
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
 struct SearchParams {
-    #[schemars(description = "Text to search for")]
+    #[schemars(
+        description = "Text to search for. With the search index, supports \"exact phrases\", prefix* terms and AND/OR/NOT"
+    )]
     query: String,
     #[schemars(description = "Start date (YYYY-MM-DD)")]
     from_date: Option<String>,
     #[schemars(description = "End date (YYYY-MM-DD)")]
     to_date: Option<String>,
+    #[schemars(description = "Maximum number of results (default 50)")]
+    limit: Option<usize>,
 }
 
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
@@ -51,57 +63,123 @@ struct DateRangeParams {
     to: String,
 }
 
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct CommitLogParams {
+    #[schemars(description = "Maximum number of commits (default 20)")]
+    limit: Option<usize>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct DiffParams {
+    #[schemars(
+        description = "Revision to compare from, e.g. a commit id or HEAD~3 (default HEAD)"
+    )]
+    from_ref: Option<String>,
+    #[schemars(
+        description = "Revision to compare to (default: the working tree, with uncommitted changes)"
+    )]
+    to_ref: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct SinceParams {
+    #[schemars(
+        description = "A revision such as HEAD~1 or a commit id, or a date (YYYY-MM-DD) to compare against the last commit before that day"
+    )]
+    since: String,
+}
+
 #[derive(Clone, Debug)]
 struct HooverMcpService {
     output_dir: PathBuf,
+    layout: Layout,
+    vault: Vault,
     config: Config,
     tool_router: ToolRouter<Self>,
 }
 
 impl HooverMcpService {
-    fn new(config: Config) -> Self {
+    fn new(config: Config) -> crate::error::Result<Self> {
         let output_dir = Config::expand_path(&config.output.directory);
-        Self {
+        let layout = Layout::new(&config.output.layout)?;
+        let vault = Vault::new(&config.output)?;
+        Ok(Self {
             output_dir,
+            layout,
+            vault,
             config,
             tool_router: Self::tool_router(),
-        }
+        })
     }
 
-    fn list_markdown_files(&self) -> Vec<PathBuf> {
-        let mut files = Vec::new();
-        if let Ok(entries) = fs::read_dir(&self.output_dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.extension().and_then(|e| e.to_str()) == Some("md") {
-                    files.push(path);
-                }
-            }
-        }
-        files.sort();
-        files
+    /// Daily files in the output directory, oldest first.
+    fn list_markdown_files(&self) -> Vec<(NaiveDate, PathBuf)> {
+        self.layout.day_files(&self.output_dir).unwrap_or_else(|e| {
+            tracing::warn!("failed to list daily files: {e}");
+            Vec::new()
+        })
+    }
+
+    /// The text of a daily file, decrypted when needed.
+    fn read_day_file(&self, path: &Path) -> Option<String> {
+        self.vault.read(path).unwrap_or_else(|e| {
+            tracing::warn!("{e}");
+            None
+        })
     }
 }
 
+/// `DATE TIME Speaker: text`, leaving out what the entry lacks.
+fn format_hit(hit: &IndexEntry) -> String {
+    let mut line = hit.date.clone();
+    if let Some(time) = &hit.time {
+        line.push(' ');
+        line.push_str(time);
+    }
+    line.push(' ');
+    if let Some(speaker) = &hit.speaker {
+        line.push_str(speaker);
+        line.push_str(": ");
+    }
+    line.push_str(&hit.text);
+    line
+}
+
 #[tool_router]
 impl HooverMcpService {
-    #[rmcp::tool(description = "Search across transcription files for a query string")]
+    #[rmcp::tool(
+        description = "Search across transcriptions; results are ranked by relevance when the search index is enabled"
+    )]
     fn search_transcriptions(
         &self,
         Parameters(SearchParams {
             query,
             from_date,
             to_date,
+            limit,
         }): Parameters<SearchParams>,
     ) -> String {
+        let limit = limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+
+        if let Some(path) = self.config.output.index_path()
+            && path.exists()
+        {
+            let hits = SearchIndex::open(&path).and_then(|index| {
+                index.search(&query, from_date.as_deref(), to_date.as_deref(), limit)
+            });
+            match hits {
+                Ok(hits) if hits.is_empty() => return "No matches found.".to_string(),
+                Ok(hits) => return hits.iter().map(format_hit).collect::<Vec<_>>().join("\n"),
+                Err(e) => tracing::warn!("index search failed, scanning files instead: {e}"),
+            }
+        }
+
         let files = self.list_markdown_files();
         let mut results = Vec::new();
 
-        for file in files {
-            let filename = file
-                .file_stem()
-                .and_then(|s| s.to_str())
-                .unwrap_or_default();
+        for (date, file) in files {
+            let filename = date.to_string();
+            let filename = filename.as_str();
 
             // Filter by date range
             if let Some(ref from) = from_date
@@ -115,7 +193,7 @@ impl HooverMcpService {
                 continue;
             }
 
-            if let Ok(content) = fs::read_to_string(&file) {
+            if let Some(content) = self.read_day_file(&file) {
                 for (i, line) in content.lines().enumerate() {
                     if line.to_lowercase().contains(&query.to_lowercase()) {
                         results.push(format!("{}:{}: {}", filename, i + 1, line));
@@ -127,23 +205,25 @@ impl HooverMcpService {
         if results.is_empty() {
             "No matches found.".to_string()
         } else {
+            results.truncate(limit);
             results.join("\n")
         }
     }
 
     #[rmcp::tool(description = "Get the full transcription for a specific day")]
     fn get_day(&self, Parameters(DateParam { date }): Parameters<DateParam>) -> String {
-        let path = self.output_dir.join(format!("{date}.md"));
-        fs::read_to_string(&path).unwrap_or_else(|_| format!("No transcription found for {date}"))
+        let Ok(day) = NaiveDate::parse_from_str(&date, "%Y-%m-%d") else {
+            return format!("Invalid date: {date} (expected YYYY-MM-DD)");
+        };
+        let path = self.layout.day_file(&self.output_dir, day);
+        self.read_day_file(&path)
+            .unwrap_or_else(|| format!("No transcription found for {date}"))
     }
 
     #[rmcp::tool(description = "List all available transcription dates")]
     fn list_dates(&self) -> String {
         let files = self.list_markdown_files();
-        let dates: Vec<&str> = files
-            .iter()
-            .filter_map(|f| f.file_stem().and_then(|s| s.to_str()))
-            .collect();
+        let dates: Vec<String> = files.iter().map(|(date, _)| date.to_string()).collect();
 
         if dates.is_empty() {
             "No transcriptions found.".to_string()
@@ -160,15 +240,13 @@ impl HooverMcpService {
         let files = self.list_markdown_files();
         let mut content = Vec::new();
 
-        for file in files {
-            let filename = file
-                .file_stem()
-                .and_then(|s| s.to_str())
-                .unwrap_or_default();
+        for (date, file) in files {
+            let filename = date.to_string();
+            let filename = filename.as_str();
 
             if filename >= from.as_str()
                 && filename <= to.as_str()
-                && let Ok(text) = fs::read_to_string(&file)
+                && let Some(text) = self.read_day_file(&file)
             {
                 content.push(text);
             }
@@ -184,22 +262,18 @@ impl HooverMcpService {
     #[rmcp::tool(description = "Get summary statistics about transcriptions")]
     fn get_summary(&self) -> String {
         let files = self.list_markdown_files();
-        let dates: Vec<&str> = files
-            .iter()
-            .filter_map(|f| f.file_stem().and_then(|s| s.to_str()))
-            .collect();
+        let dates: Vec<String> = files.iter().map(|(date, _)| date.to_string()).collect();
 
         let total_entries: usize = files
             .iter()
-            .map(|f| {
-                fs::read_to_string(f)
-                    .map(|c| c.lines().filter(|l| l.starts_with("**[")).count())
-                    .unwrap_or(0)
+            .map(|(date, f)| {
+                self.read_day_file(f)
+                    .map_or(0, |c| markdown::parse_day(&c, *date, &self.layout).len())
             })
             .sum();
 
-        let first = dates.first().copied().unwrap_or("none");
-        let last = dates.last().copied().unwrap_or("none");
+        let first = dates.first().map_or("none", String::as_str);
+        let last = dates.last().map_or("none", String::as_str);
 
         format!(
             "Days: {}\nEntries: {total_entries}\nFirst: {first}\nLast: {last}",
@@ -232,13 +306,66 @@ impl HooverMcpService {
             names.join("\n")
         }
     }
+
+    #[rmcp::tool(description = "List recent commits of the transcript repository, newest first")]
+    fn get_commit_log(
+        &self,
+        Parameters(CommitLogParams { limit }): Parameters<CommitLogParams>,
+    ) -> Result<Json<CommitLog>, String> {
+        repository::get_commit_log(&self.config, limit)
+            .map(Json)
+            .map_err(|e| e.to_string())
+    }
+
+    #[rmcp::tool(
+        description = "Get the branch, last commit and uncommitted files of the transcript repository"
+    )]
+    fn get_repo_status(&self) -> Result<Json<RepoStatus>, String> {
+        repository::get_repo_status(&self.config)
+            .map(Json)
+            .map_err(|e| e.to_string())
+    }
+
+    #[rmcp::tool(
+        description = "Get the lines added and removed per file between two revisions of the transcript repository"
+    )]
+    fn get_diff(
+        &self,
+        Parameters(DiffParams { from_ref, to_ref }): Parameters<DiffParams>,
+    ) -> Result<Json<RepoDiff>, String> {
+        repository::get_diff(&self.config, from_ref.as_deref(), to_ref.as_deref())
+            .map(Json)
+            .map_err(|e| e.to_string())
+    }
+
+    #[rmcp::tool(description = "List the commits that changed the transcript of a specific day")]
+    fn get_file_history(
+        &self,
+        Parameters(DateParam { date }): Parameters<DateParam>,
+    ) -> Result<Json<FileHistory>, String> {
+        repository::get_file_history(&self.config, &date)
+            .map(Json)
+            .map_err(|e| e.to_string())
+    }
+
+    #[rmcp::tool(
+        description = "What changed in the transcripts since a revision or date: the lines added and removed per day, including uncommitted changes"
+    )]
+    fn get_transcript_changes(
+        &self,
+        Parameters(SinceParams { since }): Parameters<SinceParams>,
+    ) -> Result<Json<TranscriptChanges>, String> {
+        repository::transcript_changes(&self.config, &since)
+            .map(Json)
+            .map_err(|e| e.to_string())
+    }
 }
 
 #[tool_handler]
 impl rmcp::ServerHandler for HooverMcpService {
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
-            instructions: Some("Hoover transcription data server. Query daily transcriptions, search across dates, and view speaker profiles.".into()),
+            instructions: Some("Hoover transcription data server. Query daily transcriptions, search across dates, view speaker profiles, and see how transcripts changed in their git history.".into()),
             capabilities: ServerCapabilities::builder()
                 .enable_tools()
                 .build(),
@@ -246,3 +373,25 @@ impl rmcp::ServerHandler for HooverMcpService {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repository_tools_are_registered_with_output_schemas() {
+        let router = HooverMcpService::tool_router();
+        for name in [
+            "get_commit_log",
+            "get_repo_status",
+            "get_diff",
+            "get_file_history",
+            "get_transcript_changes",
+        ] {
+            let tool = router
+                .get(name)
+                .unwrap_or_else(|| panic!("{name} is not registered"));
+            assert!(tool.output_schema.is_some(), "{name} has no output schema");
+        }
+    }
+}
@@ -1,138 +1,603 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, NaiveDate};
+use git2::{Delta, DiffLine, DiffOptions, Repository, Sort, StatusOptions, Tree};
+use schemars::JsonSchema;
+use serde::Serialize;
+
 use crate::config::Config;
-use crate::vcs;
+use crate::error::{HooverError, Result};
+use crate::output::calendar::Calendar;
+use crate::output::layout::Layout;
+use crate::output::route::Routes;
+use crate::vault::{self, Vault};
 
-/// MCP tools for interacting with the hoover git repository.
-///
-/// These are registered as additional tools on the MCP service.
-#[must_use]
-pub fn get_commit_log(config: &Config, limit: Option<usize>) -> String {
+/// Commits returned by [`get_commit_log`] unless asked otherwise.
+const DEFAULT_COMMIT_LIMIT: usize = 20;
+
+/// Most commits returned by [`get_file_history`].
+const FILE_HISTORY_LIMIT: usize = 50;
+
+/// A commit of the output repository.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct CommitInfo {
+    /// Full commit id.
+    pub id: String,
+    /// Commit time, RFC 3339.
+    pub time: String,
+    pub message: String,
+}
+
+/// Recent commits, newest first.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct CommitLog {
+    pub commits: Vec<CommitInfo>,
+}
+
+/// A file that differs from the last commit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct FileStatus {
+    pub path: String,
+    /// `added`, `modified`, `deleted`, `renamed`, `typechange` or
+    /// `conflicted`.
+    pub status: String,
+}
+
+/// State of the working tree of the output repository.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct RepoStatus {
+    pub branch: Option<String>,
+    /// Last commit, absent before the first one.
+    pub head: Option<CommitInfo>,
+    pub changed: Vec<FileStatus>,
+}
+
+/// How one file differs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct FileDiff {
+    pub path: String,
+    /// `added`, `modified`, `deleted`, `renamed`, `copied` or `typechange`.
+    pub status: String,
+    /// The file isn't text, such as an encrypted daily file; its lines are
+    /// left out.
+    pub binary: bool,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+/// Differences between two states of the output repository.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct RepoDiff {
+    pub from: String,
+    pub to: String,
+    pub files: Vec<FileDiff>,
+}
+
+/// Commits that changed one daily file, newest first.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct FileHistory {
+    pub path: String,
+    pub commits: Vec<CommitInfo>,
+}
+
+/// Lines added to and removed from one day's transcript.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct DayChange {
+    /// YYYY-MM-DD.
+    pub date: String,
+    pub path: String,
+    pub status: String,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+/// How the transcripts changed since a commit or day.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct TranscriptChanges {
+    pub since: String,
+    /// Commit compared against, absent when there was nothing before
+    /// `since` and every transcript counts as new.
+    pub base: Option<String>,
+    pub days: Vec<DayChange>,
+}
+
+fn open_repo(config: &Config) -> Result<(Repository, PathBuf)> {
     let output_dir = Config::expand_path(&config.output.directory);
-    let limit = limit.unwrap_or(20);
+    let repo = Repository::open(&output_dir).map_err(|e| {
+        HooverError::Other(format!(
+            "{} is not a git repository (is vcs.enabled set?): {e}",
+            output_dir.display()
+        ))
+    })?;
+    Ok((repo, output_dir))
+}
 
-    match vcs::git::commit_log(&output_dir, limit) {
-        Ok(entries) => {
-            if entries.is_empty() {
-                "No commits found.".to_string()
-            } else {
-                entries.join("\n")
-            }
-        }
-        Err(e) => format!("Error: {e}"),
+fn commit_info(commit: &git2::Commit<'_>) -> CommitInfo {
+    CommitInfo {
+        id: commit.id().to_string(),
+        time: DateTime::from_timestamp(commit.time().seconds(), 0)
+            .map(|time| time.to_rfc3339())
+            .unwrap_or_default(),
+        message: commit
+            .message()
+            .unwrap_or("(no message)")
+            .trim()
+            .to_string(),
     }
 }
 
-#[must_use]
-pub fn get_repo_status(config: &Config) -> String {
-    let output_dir = Config::expand_path(&config.output.directory);
-    match vcs::git::repo_status(&output_dir) {
-        Ok(status) => status,
-        Err(e) => format!("Error: {e}"),
+/// Commits reachable from `HEAD`, newest first; none before the first
+/// commit.
+fn commits(repo: &Repository) -> Result<Vec<git2::Commit<'_>>> {
+    let mut revwalk = repo.revwalk()?;
+    if repo.head().is_err() {
+        return Ok(Vec::new());
     }
+    revwalk.push_head()?;
+    revwalk.set_sorting(Sort::TIME)?;
+    revwalk.map(|oid| Ok(repo.find_commit(oid?)?)).collect()
 }
 
-#[must_use]
-pub fn get_diff(config: &Config, from_ref: Option<&str>, to_ref: Option<&str>) -> String {
-    let output_dir = Config::expand_path(&config.output.directory);
+/// The last `limit` commits (20 by default).
+pub fn get_commit_log(config: &Config, limit: Option<usize>) -> Result<CommitLog> {
+    let (repo, _) = open_repo(config)?;
+    let limit = limit.unwrap_or(DEFAULT_COMMIT_LIMIT);
+    Ok(CommitLog {
+        commits: commits(&repo)?
+            .iter()
+            .take(limit)
+            .map(commit_info)
+            .collect(),
+    })
+}
 
-    let repo = match git2::Repository::open(&output_dir) {
-        Ok(r) => r,
-        Err(e) => return format!("Error opening repo: {e}"),
-    };
+/// The current branch and last commit, and the files not committed yet.
+pub fn get_repo_status(config: &Config) -> Result<RepoStatus> {
+    let (repo, _) = open_repo(config)?;
+    let head = repo.head().ok();
+    let branch = head
+        .as_ref()
+        .and_then(|head| head.shorthand())
+        .map(str::to_string);
+    let head = head
+        .and_then(|head| head.peel_to_commit().ok())
+        .map(|commit| commit_info(&commit));
+
+    let mut options = StatusOptions::new();
+    options.include_untracked(true).recurse_untracked_dirs(true);
+    let changed = repo
+        .statuses(Some(&mut options))?
+        .iter()
+        .filter_map(|entry| {
+            let status = entry.status();
+            let status = if status.is_conflicted() {
+                "conflicted"
+            } else if status.intersects(git2::Status::WT_NEW | git2::Status::INDEX_NEW) {
+                "added"
+            } else if status.intersects(git2::Status::WT_DELETED | git2::Status::INDEX_DELETED) {
+                "deleted"
+            } else if status.intersects(git2::Status::WT_RENAMED | git2::Status::INDEX_RENAMED) {
+                "renamed"
+            } else if status
+                .intersects(git2::Status::WT_TYPECHANGE | git2::Status::INDEX_TYPECHANGE)
+            {
+                "typechange"
+            } else if status.intersects(git2::Status::WT_MODIFIED | git2::Status::INDEX_MODIFIED) {
+                "modified"
+            } else {
+                return None;
+            };
+            Some(FileStatus {
+                path: entry.path()?.to_string(),
+                status: status.to_string(),
+            })
+        })
+        .collect();
+
+    Ok(RepoStatus {
+        branch,
+        head,
+        changed,
+    })
+}
 
-    let from_obj = from_ref.map(|r| repo.revparse_single(r));
-    let to_obj = to_ref.map(|r| repo.revparse_single(r));
+/// The tree of `reference`, or `None` for `HEAD` before the first commit.
+fn resolve_tree<'r>(repo: &'r Repository, reference: &str) -> Result<Option<Tree<'r>>> {
+    if reference == "HEAD" && repo.head().is_err() {
+        return Ok(None);
+    }
+    let tree = repo
+        .revparse_single(reference)
+        .and_then(|object| object.peel_to_tree())
+        .map_err(|e| HooverError::Other(format!("unknown revision {reference}: {e}")))?;
+    Ok(Some(tree))
+}
 
-    let from_tree = from_obj.and_then(|obj| obj.ok().and_then(|o| o.peel_to_tree().ok()));
-    let to_tree = to_obj.and_then(|obj| obj.ok().and_then(|o| o.peel_to_tree().ok()));
+/// Differences from `from_ref` (`HEAD` by default) to `to_ref`, or to the
+/// working tree with uncommitted changes when `to_ref` is `None`.
+pub fn get_diff(config: &Config, from_ref: Option<&str>, to_ref: Option<&str>) -> Result<RepoDiff> {
+    let (repo, _) = open_repo(config)?;
+    let from = from_ref.unwrap_or("HEAD");
+    let from_tree = resolve_tree(&repo, from)?;
+    Ok(RepoDiff {
+        from: from.to_string(),
+        to: to_ref.unwrap_or("working tree").to_string(),
+        files: diff_files(&repo, from_tree.as_ref(), to_ref)?,
+    })
+}
 
-    let diff = match repo.diff_tree_to_tree(from_tree.as_ref(), to_tree.as_ref(), None) {
-        Ok(d) => d,
-        Err(e) => return format!("Error generating diff: {e}"),
+fn diff_files(
+    repo: &Repository,
+    from: Option<&Tree<'_>>,
+    to_ref: Option<&str>,
+) -> Result<Vec<FileDiff>> {
+    let mut options = DiffOptions::new();
+    let diff = if let Some(to) = to_ref {
+        let to_tree = resolve_tree(repo, to)?;
+        repo.diff_tree_to_tree(from, to_tree.as_ref(), Some(&mut options))?
+    } else {
+        options
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .show_untracked_content(true);
+        repo.diff_tree_to_workdir_with_index(from, Some(&mut options))?
     };
 
-    let mut output = String::new();
-    let _ = diff.print(git2::DiffFormat::Patch, |_delta, _hunk, line| {
-        if let Ok(content) = std::str::from_utf8(line.content()) {
-            let prefix = match line.origin() {
-                '+' => "+",
-                '-' => "-",
-                _ => " ",
-            };
-            output.push_str(prefix);
-            output.push_str(content);
+    let mut files: Vec<FileDiff> = diff
+        .deltas()
+        .map(|delta| FileDiff {
+            path: delta_path(&delta),
+            status: delta_status(delta.status()).to_string(),
+            binary: false,
+            added: Vec::new(),
+            removed: Vec::new(),
+        })
+        .collect();
+    let mut current = 0;
+    diff.print(git2::DiffFormat::Patch, |delta, _hunk, line| {
+        let path = delta_path(&delta);
+        if files.get(current).is_none_or(|file| file.path != path) {
+            match files.iter().position(|file| file.path == path) {
+                Some(i) => current = i,
+                None => return true,
+            }
         }
+        push_line(&mut files[current], &line);
         true
-    });
+    })?;
+    Ok(files)
+}
 
-    if output.is_empty() {
-        "No differences found.".to_string()
-    } else {
-        output
-    }
+fn delta_path(delta: &git2::DiffDelta<'_>) -> String {
+    delta
+        .new_file()
+        .path()
+        .or_else(|| delta.old_file().path())
+        .map(|path| path.to_string_lossy().into_owned())
+        .unwrap_or_default()
 }
 
-#[must_use]
-pub fn get_file_history(config: &Config, date: &str) -> String {
-    let output_dir = Config::expand_path(&config.output.directory);
-    let filename = format!("{date}.md");
+const fn delta_status(status: Delta) -> &'static str {
+    match status {
+        Delta::Added | Delta::Untracked => "added",
+        Delta::Deleted => "deleted",
+        Delta::Renamed => "renamed",
+        Delta::Copied => "copied",
+        Delta::Typechange => "typechange",
+        _ => "modified",
+    }
+}
 
-    let repo = match git2::Repository::open(&output_dir) {
-        Ok(r) => r,
-        Err(e) => return format!("Error opening repo: {e}"),
+/// Add a line of a patch to `file`.
+fn push_line(file: &mut FileDiff, line: &DiffLine<'_>) {
+    let text = || {
+        String::from_utf8_lossy(line.content())
+            .trim_end_matches(['\r', '\n'])
+            .to_string()
     };
+    match line.origin() {
+        '+' => file.added.push(text()),
+        '-' => file.removed.push(text()),
+        'B' => file.binary = true,
+        _ => {}
+    }
+}
 
-    let mut revwalk = match repo.revwalk() {
-        Ok(r) => r,
-        Err(e) => return format!("Error: {e}"),
-    };
+/// Commits that changed the daily file for `date` (YYYY-MM-DD), in its
+/// plaintext or encrypted form.
+pub fn get_file_history(config: &Config, date: &str) -> Result<FileHistory> {
+    let day = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|e| HooverError::Other(format!("invalid date {date}: {e}")))?;
+    let path = Layout::new(&config.output.layout)?.relative_file(day);
+    let encrypted = vault::encrypted_path(Path::new(&path));
+    let (repo, _) = open_repo(config)?;
 
-    if revwalk.push_head().is_err() {
-        return "No commits found.".to_string();
+    let mut history = Vec::new();
+    for commit in commits(&repo)? {
+        let parent_tree = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(_) => None,
+        };
+        let mut options = DiffOptions::new();
+        options.pathspec(&path).pathspec(&encrypted);
+        let diff = repo.diff_tree_to_tree(
+            parent_tree.as_ref(),
+            Some(&commit.tree()?),
+            Some(&mut options),
+        )?;
+        if diff.deltas().len() > 0 {
+            history.push(commit_info(&commit));
+            if history.len() == FILE_HISTORY_LIMIT {
+                break;
+            }
+        }
     }
+    Ok(FileHistory {
+        path,
+        commits: history,
+    })
+}
 
-    let _ = revwalk.set_sorting(git2::Sort::TIME);
-
-    let mut entries = Vec::new();
-    for oid in revwalk.flatten() {
-        if let Ok(commit) = repo.find_commit(oid) {
-            // Check if this commit touches the file
-            let dominated = commit.parent(0).ok().and_then(|parent| {
-                let parent_tree = parent.tree().ok()?;
-                let commit_tree = commit.tree().ok()?;
-                let diff = repo
-                    .diff_tree_to_tree(Some(&parent_tree), Some(&commit_tree), None)
-                    .ok()?;
-
-                let touches_file = diff.deltas().any(|d| {
-                    d.new_file()
-                        .path()
-                        .and_then(|p| p.to_str())
-                        .is_some_and(|p| p == filename || p.ends_with(&filename))
-                });
-
-                if touches_file { Some(()) } else { None }
-            });
-
-            if dominated.is_some() || (commit.parent_count() == 0) {
-                let message = commit.message().unwrap_or("(no message)").trim();
-                let time = commit.time();
-                let ts = chrono::DateTime::from_timestamp(time.seconds(), 0).map_or_else(
-                    || "unknown".to_string(),
-                    |dt| dt.format("%Y-%m-%d %H:%M:%S").to_string(),
-                );
-
-                entries.push(format!("{} {} {}", &oid.to_string()[..8], ts, message));
-            }
+/// The lines added to and removed from each day's transcript since `since`,
+/// up to the working tree.
+///
+/// `since` is a revision, or a day (YYYY-MM-DD): the last commit made before
+/// that day is compared against.  Encrypted transcripts are decrypted to
+/// compare them.
+pub fn transcript_changes(config: &Config, since: &str) -> Result<TranscriptChanges> {
+    let (repo, output_dir) = open_repo(config)?;
+    let base = match NaiveDate::parse_from_str(since, "%Y-%m-%d") {
+        Ok(day) => {
+            let calendar = Calendar::new(&config.output)?;
+            commits(&repo)?.into_iter().find(|commit| {
+                DateTime::from_timestamp(commit.time().seconds(), 0)
+                    .is_some_and(|time| calendar.day_of(time) < day)
+            })
         }
+        Err(_) => Some(
+            repo.revparse_single(since)
+                .and_then(|object| object.peel_to_commit())
+                .map_err(|e| HooverError::Other(format!("unknown revision {since}: {e}")))?,
+        ),
+    };
+    let base_id = base.as_ref().map(|commit| commit.id().to_string());
+    let files = match &base_id {
+        Some(id) => get_diff(config, Some(id), None)?.files,
+        None => diff_files(&repo, None, None)?,
+    };
 
-        if entries.len() >= 50 {
-            break;
+    let layout = Layout::new(&config.output.layout)?;
+    let routes = Routes::new(&config.output)?;
+    let vault = Vault::new(&config.output)?;
+    let mut days = Vec::new();
+    for mut file in files {
+        let stored = output_dir.join(&file.path);
+        let logical = vault::logical_path(&stored);
+        let Some(date) = routes
+            .all_directories()
+            .into_iter()
+            .find_map(|dir| layout.date_of(dir, &logical))
+        else {
+            continue;
+        };
+        if logical != stored {
+            let old = base
+                .as_ref()
+                .and_then(|commit| commit.tree().ok()?.get_path(Path::new(&file.path)).ok())
+                .and_then(|entry| repo.find_blob(entry.id()).ok())
+                .map(|blob| blob.content().to_vec());
+            let new = std::fs::read(&stored).ok();
+            decrypted_lines(&vault, &mut file, old, new)?;
         }
+        days.push(DayChange {
+            date: date.to_string(),
+            path: vault::logical_path(Path::new(&file.path))
+                .to_string_lossy()
+                .into_owned(),
+            status: file.status,
+            added: file.added,
+            removed: file.removed,
+        });
     }
+    days.sort_by(|a, b| (&a.date, &a.path).cmp(&(&b.date, &b.path)));
 
-    if entries.is_empty() {
-        format!("No history found for {filename}")
-    } else {
-        entries.join("\n")
+    Ok(TranscriptChanges {
+        since: since.to_string(),
+        base: base_id,
+        days,
+    })
+}
+
+/// Fill the lines of `file`, an encrypted file, from its `old` and `new`
+/// sealed content.
+fn decrypted_lines(
+    vault: &Vault,
+    file: &mut FileDiff,
+    old: Option<Vec<u8>>,
+    new: Option<Vec<u8>>,
+) -> Result<()> {
+    let open = |data: Option<Vec<u8>>| data.map(|data| vault.open(data)).transpose();
+    let (old, new) = (open(old)?, open(new)?);
+    let mut patch = git2::Patch::from_buffers(
+        old.as_deref().unwrap_or_default(),
+        None,
+        new.as_deref().unwrap_or_default(),
+        None,
+        None,
+    )?;
+    file.binary = false;
+    file.added.clear();
+    file.removed.clear();
+    patch.print(&mut |_delta, _hunk, line| {
+        push_line(file, &line);
+        true
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vcs::git;
+    use std::fs;
+
+    fn config(dir: &Path) -> Config {
+        let mut config: Config = serde_yaml_ng::from_str("{}").unwrap_or_else(|e| panic!("{e}"));
+        config.output.directory = dir.to_string_lossy().to_string();
+        config
+    }
+
+    fn write(dir: &Path, name: &str, content: &str) {
+        fs::write(dir.join(name), content).unwrap_or_else(|e| panic!("{e}"));
+    }
+
+    /// Two commits, then an uncommitted edit and an untracked file.
+    fn repo(dir: &Path) {
+        write(dir, "2026-10-01.md", "# Day 1\n\n**Alice:** hello\n");
+        git::add_and_commit(dir, "first").unwrap_or_else(|e| panic!("{e}"));
+        write(dir, "2026-10-01.md", "# Day 1\n\n**Alice:** hello there\n");
+        write(dir, "2026-10-02.md", "# Day 2\n\n**Bob:** morning\n");
+        git::add_and_commit(dir, "second").unwrap_or_else(|e| panic!("{e}"));
+        write(dir, "2026-10-02.md", "# Day 2\n\n**Bob:** good morning\n");
+        write(dir, "notes.txt", "not a transcript\n");
+    }
+
+    #[test]
+    fn log_status_and_history() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let config = config(dir.path());
+        assert!(get_commit_log(&config, None).is_err());
+        repo(dir.path());
+
+        let log = get_commit_log(&config, None).unwrap_or_else(|e| panic!("{e}"));
+        let messages: Vec<&str> = log.commits.iter().map(|c| c.message.as_str()).collect();
+        assert_eq!(messages, ["second", "first"]);
+        let log = get_commit_log(&config, Some(1)).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(log.commits.len(), 1);
+
+        let status = get_repo_status(&config).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(status.head.map(|c| c.message).as_deref(), Some("second"));
+        assert!(status.branch.is_some());
+        assert_eq!(
+            status.changed,
+            [
+                FileStatus {
+                    path: "2026-10-02.md".to_string(),
+                    status: "modified".to_string(),
+                },
+                FileStatus {
+                    path: "notes.txt".to_string(),
+                    status: "added".to_string(),
+                },
+            ]
+        );
+
+        let history = get_file_history(&config, "2026-10-01").unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(history.path, "2026-10-01.md");
+        assert_eq!(history.commits.len(), 2);
+        let history = get_file_history(&config, "2026-10-02").unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(history.commits.len(), 1);
+        assert_eq!(history.commits[0].message, "second");
+        assert!(get_file_history(&config, "yesterday").is_err());
+    }
+
+    #[test]
+    fn diffs_between_commits_and_the_working_tree() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let config = config(dir.path());
+        repo(dir.path());
+
+        let diff =
+            get_diff(&config, Some("HEAD~1"), Some("HEAD")).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(
+            diff.files,
+            [
+                FileDiff {
+                    path: "2026-10-01.md".to_string(),
+                    status: "modified".to_string(),
+                    binary: false,
+                    added: vec!["**Alice:** hello there".to_string()],
+                    removed: vec!["**Alice:** hello".to_string()],
+                },
+                FileDiff {
+                    path: "2026-10-02.md".to_string(),
+                    status: "added".to_string(),
+                    binary: false,
+                    added: vec![
+                        "# Day 2".to_string(),
+                        String::new(),
+                        "**Bob:** morning".to_string()
+                    ],
+                    removed: Vec::new(),
+                },
+            ]
+        );
+
+        let diff = get_diff(&config, None, None).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(diff.to, "working tree");
+        let paths: Vec<&str> = diff.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, ["2026-10-02.md", "notes.txt"]);
+        assert!(get_diff(&config, Some("no-such-ref"), None).is_err());
+    }
+
+    #[test]
+    fn transcript_changes_since_a_revision_or_day() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let config = config(dir.path());
+        repo(dir.path());
+
+        let changes = transcript_changes(&config, "HEAD~1").unwrap_or_else(|e| panic!("{e}"));
+        let days: Vec<&str> = changes.days.iter().map(|d| d.date.as_str()).collect();
+        assert_eq!(days, ["2026-10-01", "2026-10-02"]);
+        assert_eq!(changes.days[1].status, "added");
+        assert_eq!(changes.days[1].added[2], "**Bob:** good morning");
+
+        // Every commit was made today: since tomorrow compares against the
+        // last one, since today against nothing.
+        let today = Calendar::new(&config.output)
+            .unwrap_or_else(|e| panic!("{e}"))
+            .today();
+        let tomorrow = (today + chrono::Duration::days(1)).to_string();
+        let changes = transcript_changes(&config, &tomorrow).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(changes.base.as_deref().map(str::len), Some(40));
+        assert_eq!(changes.days.len(), 1);
+        assert_eq!(changes.days[0].removed, ["**Bob:** morning"]);
+        assert_eq!(changes.days[0].added, ["**Bob:** good morning"]);
+
+        let changes =
+            transcript_changes(&config, &today.to_string()).unwrap_or_else(|e| panic!("{e}"));
+        assert!(changes.base.is_none());
+        assert_eq!(changes.days.len(), 2);
+    }
+
+    #[test]
+    fn encrypted_transcripts_are_compared_decrypted() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let key_file = dir.path().join("key");
+        fs::write(&key_file, [1u8; 32]).unwrap_or_else(|e| panic!("{e}"));
+        let output = dir.path().join("out");
+        fs::create_dir_all(&output).unwrap_or_else(|e| panic!("{e}"));
+        let config: Config = serde_yaml_ng::from_str(&format!(
+            "output:\n  directory: {}\n  encryption:\n    key:\n      type: file\n      path: {}\n",
+            output.display(),
+            key_file.display()
+        ))
+        .unwrap_or_else(|e| panic!("{e}"));
+        let vault = Vault::new(&config.output).unwrap_or_else(|e| panic!("{e}"));
+
+        let path = output.join("2026-10-01.md");
+        vault
+            .write(&path, "# Day 1\n\n**Alice:** hello\n")
+            .unwrap_or_else(|e| panic!("{e}"));
+        git::add_and_commit(&output, "first").unwrap_or_else(|e| panic!("{e}"));
+        vault
+            .write(&path, "# Day 1\n\n**Alice:** hello\n**Bob:** hi\n")
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        let changes = transcript_changes(&config, "HEAD").unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(changes.days.len(), 1);
+        assert_eq!(changes.days[0].path, "2026-10-01.md");
+        assert_eq!(changes.days[0].added, ["**Bob:** hi"]);
+        assert!(changes.days[0].removed.is_empty());
     }
 }
@@ -12,6 +12,9 @@ use crate::net::crypto::CryptoContext;
 use crate::net::firewall::FirewallManager;
 use crate::net::protocol::{DecodedMessage, MessageType, PacketOrderer, decode_packet};
 
+/// Source label of chunks received over UDP.
+pub const SOURCE_UDP: &str = "udp";
+
 /// UDP audio receiver server.
 pub struct UdpServer {
     socket: Arc<UdpSocket>,
@@ -140,6 +143,7 @@ impl UdpServer {
                 samples_i16: chunk_i16,
                 timestamp: chrono::Utc::now(),
                 duration_secs: 1.0,
+                source: SOURCE_UDP,
             };
 
             if self.chunk_tx.blocking_send(audio_chunk).is_err() {
@@ -166,6 +170,7 @@ impl UdpServer {
             samples_i16: chunk_i16,
             timestamp: chrono::Utc::now(),
             duration_secs: duration,
+            source: SOURCE_UDP,
         };
 
         let _ = self.chunk_tx.blocking_send(audio_chunk);
@@ -0,0 +1,142 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+
+use crate::config::OutputConfig;
+use crate::error::{HooverError, Result};
+
+/// What "the day" and "the time" of a segment are, from `output.timezone`
+/// and `output.day_starts_at`.
+///
+/// Daily files, time headings, logs, the index and every date lookup go
+/// through this so they agree on which day a segment belongs to.
+#[derive(Debug, Clone, Copy)]
+pub struct Calendar {
+    /// `None` uses the system time zone.
+    timezone: Option<Tz>,
+    day_start: NaiveTime,
+}
+
+impl Default for Calendar {
+    /// The system time zone, with days starting at midnight.
+    fn default() -> Self {
+        Self {
+            timezone: None,
+            day_start: NaiveTime::MIN,
+        }
+    }
+}
+
+impl Calendar {
+    pub fn new(config: &OutputConfig) -> Result<Self> {
+        let timezone = config
+            .timezone
+            .as_deref()
+            .map(|name| {
+                name.parse::<Tz>()
+                    .map_err(|_| HooverError::Config(format!("unknown output.timezone: {name}")))
+            })
+            .transpose()?;
+        let day_start =
+            NaiveTime::parse_from_str(&config.day_starts_at, "%H:%M").map_err(|_| {
+                HooverError::Config(format!(
+                    "output.day_starts_at must be HH:MM, got {}",
+                    config.day_starts_at
+                ))
+            })?;
+
+        Ok(Self {
+            timezone,
+            day_start,
+        })
+    }
+
+    /// Wall-clock time of `timestamp`.
+    #[must_use]
+    pub fn local(&self, timestamp: DateTime<Utc>) -> NaiveDateTime {
+        self.timezone.map_or_else(
+            || timestamp.with_timezone(&Local).naive_local(),
+            |tz| timestamp.with_timezone(&tz).naive_local(),
+        )
+    }
+
+    /// The day `timestamp` belongs to.
+    #[must_use]
+    pub fn day_of(&self, timestamp: DateTime<Utc>) -> NaiveDate {
+        self.day_of_local(self.local(timestamp))
+    }
+
+    /// The day a wall-clock time belongs to.
+    #[must_use]
+    pub fn day_of_local(&self, local: NaiveDateTime) -> NaiveDate {
+        (local - (self.day_start - NaiveTime::MIN)).date()
+    }
+
+    /// The current day.
+    #[must_use]
+    pub fn today(&self) -> NaiveDate {
+        self.day_of(Utc::now())
+    }
+
+    /// First wall-clock moment of `day`.
+    #[must_use]
+    pub const fn day_start(&self, day: NaiveDate) -> NaiveDateTime {
+        day.and_time(self.day_start)
+    }
+
+    /// Wall-clock time of `time` of day on `day`, which falls on the next
+    /// calendar date when it is before the day's start.
+    #[must_use]
+    pub fn on_day(&self, day: NaiveDate, time: NaiveTime) -> NaiveDateTime {
+        let date = if time < self.day_start {
+            day.succ_opt().unwrap_or(day)
+        } else {
+            day
+        };
+        date.and_time(time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn days_start_at_the_configured_time_and_zone() {
+        let config = OutputConfig {
+            timezone: Some("America/New_York".to_string()),
+            day_starts_at: "04:00".to_string(),
+            ..OutputConfig::default()
+        };
+        let calendar = Calendar::new(&config).unwrap_or_else(|e| panic!("{e}"));
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap_or_default();
+
+        // 05:30 UTC is 01:30 in New York (EDT), before the 04:00 boundary.
+        let late = Utc
+            .with_ymd_and_hms(2026, 10, 17, 5, 30, 0)
+            .single()
+            .unwrap_or_else(|| panic!("ambiguous time"));
+        assert_eq!(calendar.local(late).time().to_string(), "01:30:00");
+        assert_eq!(calendar.day_of(late), date(2026, 10, 16));
+
+        let morning = late + chrono::Duration::hours(3);
+        assert_eq!(calendar.day_of(morning), date(2026, 10, 17));
+
+        let time = NaiveTime::from_hms_opt(1, 30, 0).unwrap_or_default();
+        assert_eq!(
+            calendar.on_day(date(2026, 10, 16), time),
+            calendar.local(late)
+        );
+
+        let bad_zone = OutputConfig {
+            timezone: Some("Mars/Olympus".to_string()),
+            ..OutputConfig::default()
+        };
+        assert!(Calendar::new(&bad_zone).is_err());
+        let bad_start = OutputConfig {
+            day_starts_at: "4am".to_string(),
+            ..OutputConfig::default()
+        };
+        assert!(Calendar::new(&bad_start).is_err());
+    }
+}
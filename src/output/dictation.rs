@@ -0,0 +1,387 @@
+use std::collections::VecDeque;
+use std::process::Command;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use chrono::{DateTime, Utc};
+
+use crate::config::InjectorKind;
+use crate::error::{HooverError, Result};
+use crate::speaker::AttributedSegment;
+
+use super::ChunkContext;
+use super::sink::OutputSink;
+
+/// Toggles kept to tell whether dictation was on when a segment was spoken.
+const SWITCH_HISTORY: usize = 64;
+
+/// Spoken punctuation and what it types, longer phrases first.
+const SPOKEN_PUNCTUATION: &[(&str, &str)] = &[
+    ("new paragraph", "\n\n"),
+    ("new line", "\n"),
+    ("question mark", "?"),
+    ("exclamation mark", "!"),
+    ("exclamation point", "!"),
+    ("full stop", "."),
+    ("period", "."),
+    ("comma", ","),
+    ("semicolon", ";"),
+    ("colon", ":"),
+];
+
+/// Produces keystrokes in the focused window.
+pub trait Injector: Send {
+    /// Type `text`, with `\n` as Enter.
+    fn type_text(&mut self, text: &str) -> Result<()>;
+}
+
+/// Create the injector for `kind`.
+pub fn create_injector(kind: InjectorKind) -> Result<Box<dyn Injector>> {
+    Ok(match kind {
+        InjectorKind::Wtype => Box::new(CommandInjector::new("wtype", &["--"])),
+        InjectorKind::Xdotool => Box::new(CommandInjector::new(
+            "xdotool",
+            &["type", "--clearmodifiers", "--"],
+        )),
+        InjectorKind::Ydotool => Box::new(CommandInjector::new("ydotool", &["type", "--"])),
+        #[cfg(target_os = "linux")]
+        InjectorKind::Uinput => Box::new(super::uinput::UinputInjector::new()?),
+        #[cfg(not(target_os = "linux"))]
+        InjectorKind::Uinput => {
+            return Err(HooverError::Config(
+                "the uinput injector is only available on Linux".to_string(),
+            ));
+        }
+    })
+}
+
+/// Types text by running a program with the text as its last argument.
+pub struct CommandInjector {
+    program: &'static str,
+    args: Vec<&'static str>,
+}
+
+impl CommandInjector {
+    #[must_use]
+    pub fn new(program: &'static str, args: &[&'static str]) -> Self {
+        Self {
+            program,
+            args: args.to_vec(),
+        }
+    }
+}
+
+impl Injector for CommandInjector {
+    fn type_text(&mut self, text: &str) -> Result<()> {
+        let status = Command::new(self.program)
+            .args(&self.args)
+            .arg(text)
+            .status()
+            .map_err(|e| HooverError::Output(format!("failed to run {}: {e}", self.program)))?;
+        if !status.success() {
+            return Err(HooverError::Output(format!(
+                "{} exited with {status}",
+                self.program
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Keeps what would have been typed, for tests.  Clones share the text.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryInjector {
+    typed: Arc<Mutex<String>>,
+}
+
+impl MemoryInjector {
+    /// Everything typed so far.
+    #[must_use]
+    pub fn typed(&self) -> String {
+        self.typed
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+impl Injector for MemoryInjector {
+    fn type_text(&mut self, text: &str) -> Result<()> {
+        self.typed
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push_str(text);
+        Ok(())
+    }
+}
+
+/// Whether dictation is on, switched by `hoover dictate` through the control
+/// socket.  Clones share the state.
+///
+/// Segments arrive a chunk after they are spoken, so the switch keeps its
+/// recent toggles and a segment is typed if dictation was on when it started.
+#[derive(Debug, Clone)]
+pub struct DictationSwitch {
+    state: Arc<Mutex<SwitchState>>,
+}
+
+#[derive(Debug)]
+struct SwitchState {
+    /// State before the oldest toggle kept.
+    initial: bool,
+    toggles: VecDeque<(DateTime<Utc>, bool)>,
+}
+
+impl DictationSwitch {
+    #[must_use]
+    pub fn new(on: bool) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(SwitchState {
+                initial: on,
+                toggles: VecDeque::new(),
+            })),
+        }
+    }
+
+    /// Whether dictation is on now.
+    #[must_use]
+    pub fn is_on(&self) -> bool {
+        let state = self.lock();
+        state.toggles.back().map_or(state.initial, |&(_, on)| on)
+    }
+
+    /// Whether dictation was on at `at`.
+    #[must_use]
+    pub fn was_on_at(&self, at: DateTime<Utc>) -> bool {
+        let state = self.lock();
+        state
+            .toggles
+            .iter()
+            .rev()
+            .find(|(since, _)| *since <= at)
+            .map_or(state.initial, |&(_, on)| on)
+    }
+
+    /// Switch dictation on or off as of `at`.
+    pub fn set(&self, on: bool, at: DateTime<Utc>) {
+        let mut state = self.lock();
+        let current = state.toggles.back().map_or(state.initial, |&(_, on)| on);
+        if current == on {
+            return;
+        }
+        if state.toggles.len() == SWITCH_HISTORY
+            && let Some((_, oldest)) = state.toggles.pop_front()
+        {
+            state.initial = oldest;
+        }
+        state.toggles.push_back((at, on));
+    }
+
+    /// Flip dictation as of `at`, returning whether it is now on.
+    #[must_use]
+    pub fn toggle(&self, at: DateTime<Utc>) -> bool {
+        let on = !self.is_on();
+        self.set(on, at);
+        on
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, SwitchState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Default for DictationSwitch {
+    fn default() -> Self {
+        Self::new(true)
+    }
+}
+
+/// Types every segment into the focused window while dictation is on.
+pub struct DictationSink {
+    injector: Box<dyn Injector>,
+    switch: DictationSwitch,
+    punctuation: bool,
+    speakers: Vec<String>,
+    /// Whether the cursor is where nothing needs a space before it.
+    at_line_start: bool,
+}
+
+impl DictationSink {
+    #[must_use]
+    pub fn new(
+        injector: Box<dyn Injector>,
+        switch: DictationSwitch,
+        punctuation: bool,
+        speakers: Vec<String>,
+    ) -> Self {
+        Self {
+            injector,
+            switch,
+            punctuation,
+            speakers,
+            at_line_start: true,
+        }
+    }
+
+    fn wants(&self, speaker: Option<&str>) -> bool {
+        let name = speaker.unwrap_or("unknown");
+        self.speakers.is_empty() || self.speakers.iter().any(|s| s.eq_ignore_ascii_case(name))
+    }
+}
+
+impl OutputSink for DictationSink {
+    fn name(&self) -> &'static str {
+        "dictation"
+    }
+
+    fn write_segment(&mut self, turn: &AttributedSegment, _context: &ChunkContext) -> Result<()> {
+        if turn.redacted
+            || !self.wants(turn.speaker.as_deref())
+            || !self.switch.was_on_at(turn.segment.timestamp)
+        {
+            return Ok(());
+        }
+
+        let text = if self.punctuation {
+            spoken_punctuation(&turn.segment.text)
+        } else {
+            turn.segment.text.trim().to_string()
+        };
+        if text.is_empty() {
+            return Ok(());
+        }
+
+        let joins = text.starts_with([',', '.', ';', ':', '?', '!', '\n']);
+        let separator = if self.at_line_start || joins { "" } else { " " };
+        self.injector.type_text(&format!("{separator}{text}"))?;
+        self.at_line_start = text.ends_with('\n');
+        Ok(())
+    }
+}
+
+/// `text` with spoken punctuation replaced by the characters it names.
+///
+/// Punctuation the STT engine put around the spoken word, as in "Hello,
+/// comma, world", is dropped, and a word after a sentence end is
+/// capitalized.
+#[must_use]
+pub fn spoken_punctuation(text: &str) -> String {
+    let tokens: Vec<&str> = text.split_whitespace().collect();
+    let core = |token: &str| {
+        token
+            .trim_matches(|c: char| !c.is_alphanumeric())
+            .to_lowercase()
+    };
+
+    let mut out = String::new();
+    // Whether `out` ends with punctuation that was spoken rather than added
+    // by the STT engine.
+    let mut spoken_end = false;
+    let mut capitalize = false;
+    let mut i = 0;
+    while i < tokens.len() {
+        let spoken = SPOKEN_PUNCTUATION.iter().find_map(|(phrase, symbol)| {
+            let words: Vec<&str> = phrase.split(' ').collect();
+            let matched = tokens
+                .get(i..i + words.len())
+                .is_some_and(|found| found.iter().zip(&words).all(|(t, w)| core(t) == *w));
+            matched.then_some((words.len(), *symbol))
+        });
+
+        if let Some((len, symbol)) = spoken {
+            if !spoken_end {
+                let kept = out.trim_end_matches([',', '.', ';', ':', '?', '!']).len();
+                out.truncate(kept);
+            }
+            out.push_str(symbol);
+            spoken_end = true;
+            capitalize = symbol.ends_with(['.', '?', '!', '\n']);
+            i += len;
+            continue;
+        }
+
+        if !out.is_empty() && !out.ends_with('\n') {
+            out.push(' ');
+        }
+        let mut chars = tokens[i].chars();
+        if capitalize && let Some(first) = chars.next() {
+            out.extend(first.to_uppercase());
+            out.push_str(chars.as_str());
+        } else {
+            out.push_str(tokens[i]);
+        }
+        spoken_end = false;
+        capitalize = false;
+        i += 1;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stt::TranscriptionSegment;
+    use chrono::Duration;
+
+    fn turn(text: &str, at: DateTime<Utc>, speaker: Option<&str>) -> AttributedSegment {
+        AttributedSegment {
+            segment: TranscriptionSegment {
+                text: text.to_string(),
+                timestamp: at,
+                duration_secs: 1.0,
+                confidence: None,
+            },
+            speaker: speaker.map(str::to_string),
+            speaker_confidence: None,
+            redacted: false,
+        }
+    }
+
+    #[test]
+    fn spoken_punctuation_is_typed() {
+        assert_eq!(
+            spoken_punctuation("Hello, comma, world period how are you question mark"),
+            "Hello, world. How are you?"
+        );
+        assert_eq!(
+            spoken_punctuation("Dear Bob. New line. Thanks comma period"),
+            "Dear Bob\nThanks,."
+        );
+        assert_eq!(spoken_punctuation("New paragraph. done"), "\n\nDone");
+    }
+
+    #[test]
+    fn segments_are_typed_while_dictation_is_on() {
+        let injector = MemoryInjector::default();
+        let start = Utc::now();
+        let switch = DictationSwitch::new(false);
+        let mut sink = DictationSink::new(
+            Box::new(injector.clone()),
+            switch.clone(),
+            true,
+            vec!["Alice".to_string()],
+        );
+        let context = ChunkContext::default();
+        let at = |secs| start + Duration::seconds(secs);
+
+        switch.set(true, at(10));
+        switch.set(false, at(20));
+        for t in [
+            turn("before", at(5), Some("Alice")),
+            turn("Hello comma", at(11), Some("Alice")),
+            turn("world", at(12), Some("Alice")),
+            turn("not me", at(13), Some("Bob")),
+            turn("new line", at(14), Some("Alice")),
+            turn("Next", at(15), Some("Alice")),
+            turn("after", at(25), Some("Alice")),
+        ] {
+            sink.write_segment(&t, &context)
+                .unwrap_or_else(|e| panic!("{e}"));
+        }
+
+        assert_eq!(injector.typed(), "Hello, world\nNext");
+        assert!(!switch.is_on());
+        assert!(switch.toggle(at(30)));
+        assert!(switch.was_on_at(at(15)));
+        assert!(!switch.was_on_at(at(25)));
+    }
+}
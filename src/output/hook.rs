@@ -0,0 +1,231 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crossbeam_channel::{Sender, TrySendError};
+
+use crate::error::{HooverError, Result};
+use crate::speaker::AttributedSegment;
+
+use super::ChunkContext;
+use super::jsonl::SegmentRecord;
+use super::sink::OutputSink;
+
+/// Segments queued for a hook before new ones are dropped.
+const QUEUE_CAPACITY: usize = 256;
+
+/// Upper bound on a single webhook request.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Runs a hook for every record on a background thread, so a slow hook never
+/// stalls recording.
+struct Worker {
+    name: &'static str,
+    tx: Option<Sender<SegmentRecord>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Worker {
+    fn spawn(
+        name: &'static str,
+        mut hook: impl FnMut(&SegmentRecord) -> Result<()> + Send + 'static,
+    ) -> Result<Self> {
+        let (tx, rx) = crossbeam_channel::bounded::<SegmentRecord>(QUEUE_CAPACITY);
+        let handle = std::thread::Builder::new()
+            .name(format!("hoover-{name}"))
+            .spawn(move || {
+                for record in rx {
+                    if let Err(e) = hook(&record) {
+                        tracing::error!("{name} output failed: {e}");
+                    }
+                }
+            })
+            .map_err(|e| HooverError::Output(format!("failed to start {name} output: {e}")))?;
+
+        Ok(Self {
+            name,
+            tx: Some(tx),
+            handle: Some(handle),
+        })
+    }
+
+    fn send(&self, record: SegmentRecord) -> Result<()> {
+        let Some(tx) = &self.tx else {
+            return Err(HooverError::Output(format!(
+                "{} output is shut down",
+                self.name
+            )));
+        };
+        tx.try_send(record).map_err(|e| match e {
+            TrySendError::Full(_) => {
+                HooverError::Output(format!("{} output is behind, dropping segment", self.name))
+            }
+            TrySendError::Disconnected(_) => {
+                HooverError::Output(format!("{} output thread has stopped", self.name))
+            }
+        })
+    }
+
+    /// Wait for queued records to be handled.
+    fn shutdown(&mut self) {
+        drop(self.tx.take());
+        if let Some(handle) = self.handle.take()
+            && handle.join().is_err()
+        {
+            tracing::error!("{} output thread panicked", self.name);
+        }
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Runs a command for every segment, passing the segment as JSON on stdin.
+pub struct CommandSink {
+    worker: Worker,
+}
+
+impl CommandSink {
+    pub fn new(command: &[String]) -> Result<Self> {
+        let Some((program, args)) = command.split_first() else {
+            return Err(HooverError::Config(
+                "command output needs a non-empty `command`".to_string(),
+            ));
+        };
+        let program = program.clone();
+        let args = args.to_vec();
+
+        let worker = Worker::spawn("command", move |record| {
+            run_command(&program, &args, record)
+        })?;
+        Ok(Self { worker })
+    }
+}
+
+fn run_command(program: &str, args: &[String], record: &SegmentRecord) -> Result<()> {
+    let json = serde_json::to_vec(record)
+        .map_err(|e| HooverError::Output(format!("failed to serialize segment: {e}")))?;
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .map_err(|e| HooverError::Output(format!("failed to run {program}: {e}")))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A command that ignores its input may exit before reading it.
+        let _ = stdin.write_all(&json);
+    }
+
+    let status = child
+        .wait()
+        .map_err(|e| HooverError::Output(format!("failed to wait for {program}: {e}")))?;
+    if !status.success() {
+        return Err(HooverError::Output(format!(
+            "{program} exited with {status}"
+        )));
+    }
+    Ok(())
+}
+
+impl OutputSink for CommandSink {
+    fn name(&self) -> &'static str {
+        "command"
+    }
+
+    fn write_segment(&mut self, turn: &AttributedSegment, context: &ChunkContext) -> Result<()> {
+        self.worker.send(SegmentRecord::new(turn, context))
+    }
+
+    fn shutdown(&mut self) -> Result<()> {
+        self.worker.shutdown();
+        Ok(())
+    }
+}
+
+/// POSTs every segment as JSON to a URL.
+pub struct WebhookSink {
+    worker: Worker,
+}
+
+impl WebhookSink {
+    pub fn new(url: &str, headers: &BTreeMap<String, String>) -> Result<Self> {
+        let agent: ureq::Agent = ureq::Agent::config_builder()
+            .timeout_global(Some(WEBHOOK_TIMEOUT))
+            .build()
+            .into();
+        let url = url.to_string();
+        let headers = headers.clone();
+
+        let worker = Worker::spawn("webhook", move |record| {
+            let body = serde_json::to_vec(record)
+                .map_err(|e| HooverError::Output(format!("failed to serialize segment: {e}")))?;
+            let mut request = agent.post(&url).header("Content-Type", "application/json");
+            for (name, value) in &headers {
+                request = request.header(name, value);
+            }
+            request
+                .send(&body[..])
+                .map_err(|e| HooverError::Network(format!("webhook {url} failed: {e}")))?;
+            Ok(())
+        })?;
+        Ok(Self { worker })
+    }
+}
+
+impl OutputSink for WebhookSink {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    fn write_segment(&mut self, turn: &AttributedSegment, context: &ChunkContext) -> Result<()> {
+        self.worker.send(SegmentRecord::new(turn, context))
+    }
+
+    fn shutdown(&mut self) -> Result<()> {
+        self.worker.shutdown();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::Utc;
+
+    use crate::stt::TranscriptionSegment;
+
+    #[test]
+    fn command_receives_segment_json() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let out = dir.path().join("out.json");
+        let command = vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            format!("cat >> '{}'", out.display()),
+        ];
+        let mut sink = CommandSink::new(&command).unwrap_or_else(|e| panic!("{e}"));
+
+        let turn = AttributedSegment::unattributed(TranscriptionSegment {
+            text: "ship it".to_string(),
+            timestamp: Utc::now(),
+            duration_secs: 1.0,
+            confidence: None,
+        });
+        sink.write_segment(&turn, &ChunkContext::default())
+            .unwrap_or_else(|e| panic!("{e}"));
+        sink.shutdown().unwrap_or_else(|e| panic!("{e}"));
+
+        let written = std::fs::read_to_string(&out).unwrap_or_else(|e| panic!("{e}"));
+        let record: SegmentRecord =
+            serde_json::from_str(&written).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(record.text, "ship it");
+        assert!(CommandSink::new(&[]).is_err());
+    }
+}
@@ -0,0 +1,209 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config::OutputConfig;
+use crate::error::{HooverError, Result};
+use crate::speaker::AttributedSegment;
+use crate::vault::Vault;
+
+use super::ChunkContext;
+use super::calendar::Calendar;
+use super::layout::Layout;
+use super::route::Routes;
+use super::sink::OutputSink;
+
+/// One line of the daily structured segment log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SegmentRecord {
+    pub text: String,
+    pub timestamp: DateTime<Utc>,
+    pub duration_secs: f32,
+    /// STT confidence, when the backend reports one.
+    pub confidence: Option<f32>,
+    pub speaker: Option<String>,
+    pub speaker_confidence: Option<f32>,
+    /// `text` is a placeholder for a redacted segment.
+    #[serde(default)]
+    pub redacted: bool,
+    pub backend: String,
+    pub source: String,
+    pub chunk_id: String,
+}
+
+impl SegmentRecord {
+    #[must_use]
+    pub fn new(turn: &AttributedSegment, context: &ChunkContext) -> Self {
+        Self {
+            text: turn.segment.text.clone(),
+            timestamp: turn.segment.timestamp,
+            duration_secs: turn.segment.duration_secs,
+            confidence: turn.segment.confidence,
+            speaker: turn.speaker.clone(),
+            speaker_confidence: turn.speaker_confidence,
+            redacted: turn.redacted,
+            backend: context.backend.clone(),
+            source: context.source.clone(),
+            chunk_id: context.chunk_id.clone(),
+        }
+    }
+}
+
+/// Logs every segment next to each daily markdown file it is routed to.
+pub struct JsonlSink {
+    routes: Routes,
+    layout: Layout,
+    calendar: Calendar,
+    vault: Vault,
+}
+
+impl JsonlSink {
+    pub fn new(config: &OutputConfig) -> Result<Self> {
+        Ok(Self {
+            routes: Routes::new(config)?,
+            layout: Layout::new(&config.layout)?,
+            calendar: Calendar::new(config)?,
+            vault: Vault::new(config)?,
+        })
+    }
+}
+
+impl OutputSink for JsonlSink {
+    fn name(&self) -> &'static str {
+        "jsonl"
+    }
+
+    fn write_segment(&mut self, turn: &AttributedSegment, context: &ChunkContext) -> Result<()> {
+        let record = SegmentRecord::new(turn, context);
+        let date = self.calendar.day_of(record.timestamp);
+        for dir in self.routes.directories(turn.speaker.as_deref()) {
+            append_record(&self.layout, &self.vault, dir, date, &record)?;
+        }
+        Ok(())
+    }
+}
+
+/// Path of the segment log for `date` inside `output_dir`: the daily
+/// markdown file's path with a `.jsonl` extension.
+#[must_use]
+pub fn log_file(layout: &Layout, output_dir: &Path, date: NaiveDate) -> PathBuf {
+    layout.day_file(output_dir, date).with_extension("jsonl")
+}
+
+/// Append a record to the log for `date`.
+///
+/// Unencrypted, the line is written with a single append, so concurrent
+/// readers never see a partial record unless the process dies mid-write;
+/// the record after a torn one still starts a line of its own.  Encrypted
+/// logs are replaced atomically.
+pub fn append_record(
+    layout: &Layout,
+    vault: &Vault,
+    output_dir: &Path,
+    date: NaiveDate,
+    record: &SegmentRecord,
+) -> Result<()> {
+    let path = log_file(layout, output_dir, date);
+    let mut line = serde_json::to_string(record)
+        .map_err(|e| HooverError::Output(format!("failed to serialize segment: {e}")))?;
+    line.push('\n');
+    vault.append_line(&path, &line)
+}
+
+/// Read every record of the log for `date`.  A missing log yields no records;
+/// lines that fail to parse (such as one torn by a crash) are skipped.
+pub fn read_day(
+    layout: &Layout,
+    vault: &Vault,
+    output_dir: &Path,
+    date: NaiveDate,
+) -> Result<Vec<SegmentRecord>> {
+    let path = log_file(layout, output_dir, date);
+    let Some(content) = vault.read(&path)? else {
+        return Ok(Vec::new());
+    };
+
+    Ok(content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(line) {
+            Ok(record) => Some(record),
+            Err(e) => {
+                tracing::warn!("skipping malformed line in {}: {e}", path.display());
+                None
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stt::TranscriptionSegment;
+    use std::fs::OpenOptions;
+    use std::io::Write;
+
+    #[test]
+    fn append_and_read_back() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let turn = AttributedSegment {
+            segment: TranscriptionSegment {
+                text: "hello there".to_string(),
+                timestamp: Utc::now(),
+                duration_secs: 1.5,
+                confidence: Some(0.9),
+            },
+            speaker: Some("Alice".to_string()),
+            speaker_confidence: Some(0.8),
+            redacted: false,
+        };
+        let context = ChunkContext {
+            backend: "whisper".to_string(),
+            source: "microphone".to_string(),
+            chunk_id: "2026-10-18T09:00:00.000Z".to_string(),
+            ..ChunkContext::default()
+        };
+        let record = SegmentRecord::new(&turn, &context);
+        let layout =
+            Layout::new(&crate::config::LayoutConfig::default()).unwrap_or_else(|e| panic!("{e}"));
+        let date = Calendar::default().day_of(record.timestamp);
+        let vault = Vault::default();
+        append_record(&layout, &vault, dir.path(), date, &record).unwrap_or_else(|e| panic!("{e}"));
+        append_record(&layout, &vault, dir.path(), date, &record).unwrap_or_else(|e| panic!("{e}"));
+
+        let path = log_file(&layout, dir.path(), date);
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap_or_else(|e| panic!("{e}"));
+        file.write_all(b"{\"text\":\"torn")
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        let records = read_day(&layout, &vault, dir.path(), date).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(records, vec![record.clone(), record.clone()]);
+
+        // The record after a torn one is kept.
+        append_record(&layout, &vault, dir.path(), date, &record).unwrap_or_else(|e| panic!("{e}"));
+        let records = read_day(&layout, &vault, dir.path(), date).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(records.len(), 3);
+
+        let sealed = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let vault = Vault::with_key(&[9; 32]);
+        append_record(&layout, &vault, sealed.path(), date, &record)
+            .unwrap_or_else(|e| panic!("{e}"));
+        append_record(&layout, &vault, sealed.path(), date, &record)
+            .unwrap_or_else(|e| panic!("{e}"));
+        assert!(!log_file(&layout, sealed.path(), date).exists());
+        let records =
+            read_day(&layout, &vault, sealed.path(), date).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(records, vec![record.clone(), record]);
+
+        let empty = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        assert!(
+            read_day(&layout, &vault, empty.path(), date)
+                .unwrap_or_else(|e| panic!("{e}"))
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,560 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use chrono::{NaiveDate, NaiveTime, Timelike};
+
+use crate::config::{HeadingGranularity, LayoutConfig, ParagraphJoin};
+use crate::error::{HooverError, Result};
+
+/// The layout of the daily markdown files, from `output.layout`.
+///
+/// Both the writer and everything reading the files back go through this, so
+/// they agree on file names, headings and speaker labels.
+#[derive(Debug, Clone)]
+pub struct Layout {
+    file: String,
+    day_header: String,
+    /// Literal text before and after `{time}`.
+    time_heading: (String, String),
+    speaker: SpeakerTemplate,
+    granularity: HeadingGranularity,
+    join: ParagraphJoin,
+    note: Option<DailyNote>,
+}
+
+/// The managed heading of `output.layout.daily_note`.
+#[derive(Debug, Clone)]
+struct DailyNote {
+    heading: String,
+    level: usize,
+    wiki_links: bool,
+}
+
+/// An entry template split around its `{speaker}` and `{text}` placeholders.
+#[derive(Debug, Clone)]
+struct SpeakerTemplate {
+    prefix: String,
+    separator: String,
+    suffix: String,
+    speaker_first: bool,
+}
+
+impl Layout {
+    pub fn new(config: &LayoutConfig) -> Result<Self> {
+        check_file_pattern(&config.file)?;
+        check_strftime(&config.day_header, "day_header")?;
+
+        let Some(time_heading) = split_placeholder(&config.time_heading, "{time}") else {
+            return Err(HooverError::Config(
+                "output.layout.time_heading must contain {time} once".to_string(),
+            ));
+        };
+        // Level-1 headings mark the sections added by `output.routes`.
+        if config.time_heading.starts_with("# ") {
+            return Err(HooverError::Config(
+                "output.layout.time_heading cannot be a level-1 heading".to_string(),
+            ));
+        }
+
+        let note = config
+            .daily_note
+            .as_ref()
+            .map(|note| {
+                let heading = note.heading.trim_end().to_string();
+                let Some(level) = heading_level(&heading) else {
+                    return Err(HooverError::Config(format!(
+                        "output.layout.daily_note.heading must be a markdown heading: {heading}"
+                    )));
+                };
+                // Time headings must stay inside the managed section.
+                if heading_level(&config.time_heading).is_some_and(|time| time <= level) {
+                    return Err(HooverError::Config(format!(
+                        "output.layout.time_heading must be a deeper heading than {heading}, \
+                         e.g. \"{} {{time}}\"",
+                        "#".repeat(level + 1)
+                    )));
+                }
+                Ok(DailyNote {
+                    heading,
+                    level,
+                    wiki_links: note.wiki_links,
+                })
+            })
+            .transpose()?;
+
+        Ok(Self {
+            file: config.file.clone(),
+            day_header: config.day_header.clone(),
+            time_heading,
+            speaker: SpeakerTemplate::new(&config.speaker)?,
+            granularity: config.granularity,
+            join: config.join,
+            note,
+        })
+    }
+
+    #[must_use]
+    pub const fn join(&self) -> ParagraphJoin {
+        self.join
+    }
+
+    /// Path of the daily file for `date` inside `output_dir`.
+    #[must_use]
+    pub fn day_file(&self, output_dir: &Path, date: NaiveDate) -> PathBuf {
+        output_dir.join(self.relative_file(date))
+    }
+
+    /// Path of the daily file for `date`, relative to its output directory.
+    #[must_use]
+    pub fn relative_file(&self, date: NaiveDate) -> String {
+        date.format(&self.file).to_string()
+    }
+
+    /// The day a file inside `output_dir` holds, if it is a daily file.
+    #[must_use]
+    pub fn date_of(&self, output_dir: &Path, path: &Path) -> Option<NaiveDate> {
+        let relative = path.strip_prefix(output_dir).ok()?;
+        let relative = relative
+            .components()
+            .map(|c| c.as_os_str().to_str())
+            .collect::<Option<Vec<_>>>()?
+            .join("/");
+        let date = NaiveDate::parse_from_str(&relative, &self.file).ok()?;
+        (self.relative_file(date) == relative).then_some(date)
+    }
+
+    /// Every daily file inside `output_dir`, oldest first.  Hidden
+    /// directories such as `.git` are skipped.  Encrypted files are listed
+    /// under the path they stand for; read them through a
+    /// [`Vault`](crate::vault::Vault).
+    pub fn day_files(&self, output_dir: &Path) -> Result<Vec<(NaiveDate, PathBuf)>> {
+        let mut files = Vec::new();
+        let mut pending = vec![output_dir.to_path_buf()];
+        while let Some(dir) = pending.pop() {
+            let entries = match fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => {
+                    return Err(HooverError::Output(format!(
+                        "failed to read {}: {e}",
+                        dir.display()
+                    )));
+                }
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if entry.file_name().to_string_lossy().starts_with('.') {
+                    continue;
+                }
+                if path.is_dir() {
+                    pending.push(path);
+                } else {
+                    let path = crate::vault::logical_path(&path);
+                    if let Some(date) = self.date_of(output_dir, &path) {
+                        files.push((date, path));
+                    }
+                }
+            }
+        }
+        files.sort();
+        files.dedup();
+        Ok(files)
+    }
+
+    /// Whether entries go under a managed heading of a daily note rather
+    /// than filling the whole file.
+    #[must_use]
+    pub const fn is_daily_note(&self) -> bool {
+        self.note.is_some()
+    }
+
+    /// The header a new daily file starts with, followed by a blank line.
+    /// For a daily note, the managed heading.
+    #[must_use]
+    pub fn day_header(&self, date: NaiveDate) -> String {
+        self.note.as_ref().map_or_else(
+            || self.title_header(date),
+            |note| format!("{}\n\n", note.heading),
+        )
+    }
+
+    /// The header of a file of hoover's own about `date`, such as a summary
+    /// sidecar, even for daily notes.
+    #[must_use]
+    pub fn title_header(&self, date: NaiveDate) -> String {
+        format!("{}\n\n", date.format(&self.day_header))
+    }
+
+    /// Offset of the first line after the day header in `content`.  Falls
+    /// back to the end of the first line when the header was edited.  For a
+    /// daily note, the offset after the managed heading, or the end of
+    /// `content` when it has none.
+    #[must_use]
+    pub fn body_start(&self, content: &str, date: NaiveDate) -> usize {
+        if let Some(note) = &self.note {
+            return note_heading(content, &note.heading)
+                .map_or(content.len(), |(start, line)| start + line.len());
+        }
+        let header = date.format(&self.day_header).to_string();
+        let from = if content.starts_with(&header) {
+            header.len()
+        } else {
+            0
+        };
+        content[from..]
+            .find('\n')
+            .map_or(content.len(), |i| from + i + 1)
+    }
+
+    /// Whether `content` starts with the day header for `date`, or for a
+    /// daily note, has the managed heading.
+    #[must_use]
+    pub fn has_day_header(&self, content: &str, date: NaiveDate) -> bool {
+        self.note.as_ref().map_or_else(
+            || content.starts_with(&date.format(&self.day_header).to_string()),
+            |note| note_heading(content, &note.heading).is_some(),
+        )
+    }
+
+    /// Offset of the end of the main transcript, which starts at `from`:
+    /// the first level-1 heading, where routed sections begin, or for a
+    /// daily note the first heading not deeper than the managed one.
+    #[must_use]
+    pub fn body_end(&self, content: &str, from: usize) -> usize {
+        let max_level = self.note.as_ref().map_or(1, |note| note.level);
+        let mut offset = from;
+        for line in content[from..].split_inclusive('\n') {
+            if heading_level(line).is_some_and(|level| level <= max_level) {
+                return offset;
+            }
+            offset += line.len();
+        }
+        content.len()
+    }
+
+    /// Level of the headings hoover adds inside the main transcript, such as
+    /// `## Summary`: one deeper than the day header or managed heading.
+    #[must_use]
+    pub fn subheading_level(&self) -> usize {
+        self.note.as_ref().map_or(2, |note| note.level + 1)
+    }
+
+    /// Offset of the first time heading in `content`, where the transcript
+    /// starts.  Whatever comes before it, after the day header, is the day's
+    /// summary.
+    #[must_use]
+    pub fn transcript_start(&self, content: &str, date: NaiveDate) -> Option<usize> {
+        let mut offset = self.body_start(content, date);
+        let end = self.body_end(content, offset);
+        for line in content[offset..end].split_inclusive('\n') {
+            if self.parse_time_heading(line.trim_end()).is_some() {
+                return Some(offset);
+            }
+            offset += line.len();
+        }
+        None
+    }
+
+    /// The time a heading shows for a segment at `time`.
+    #[must_use]
+    pub fn heading_time(&self, time: NaiveTime) -> String {
+        let (hour, minute) = (time.hour(), time.minute());
+        match self.granularity {
+            HeadingGranularity::Minute => format!("{hour:02}:{minute:02}"),
+            HeadingGranularity::FiveMinutes => format!("{hour:02}:{:02}", minute - minute % 5),
+            HeadingGranularity::Hour => format!("{hour:02}:00"),
+            HeadingGranularity::Turn => time.format("%H:%M:%S").to_string(),
+        }
+    }
+
+    /// Whether an entry needs a new time heading, given the last heading
+    /// written and whether the speaker changed.
+    #[must_use]
+    pub fn needs_heading(&self, last: Option<&str>, time: &str, speaker_changed: bool) -> bool {
+        match self.granularity {
+            HeadingGranularity::Turn => last.is_none() || speaker_changed,
+            _ => last != Some(time),
+        }
+    }
+
+    #[must_use]
+    pub fn time_heading(&self, time: &str) -> String {
+        let (prefix, suffix) = &self.time_heading;
+        format!("{prefix}{time}{suffix}")
+    }
+
+    /// The time of a heading line, if `line` is one.
+    #[must_use]
+    pub fn parse_time_heading<'a>(&self, line: &'a str) -> Option<&'a str> {
+        let (prefix, suffix) = &self.time_heading;
+        let time = line
+            .strip_prefix(prefix.as_str())?
+            .strip_suffix(suffix.as_str())?;
+        let time = time.trim();
+        (NaiveTime::parse_from_str(time, "%H:%M").is_ok()
+            || NaiveTime::parse_from_str(time, "%H:%M:%S").is_ok())
+        .then_some(time)
+    }
+
+    /// An entry attributed to `speaker`.
+    #[must_use]
+    pub fn speaker_line(&self, speaker: &str, text: &str) -> String {
+        let t = &self.speaker;
+        let link;
+        let speaker = if self.note.as_ref().is_some_and(|note| note.wiki_links) {
+            link = format!("[[{speaker}]]");
+            link.as_str()
+        } else {
+            speaker
+        };
+        let (first, second) = if t.speaker_first {
+            (speaker, text)
+        } else {
+            (text, speaker)
+        };
+        let mut line = String::new();
+        let _ = write!(
+            line,
+            "{}{first}{}{second}{}",
+            t.prefix, t.separator, t.suffix
+        );
+        line
+    }
+
+    /// The speaker and text of an attributed entry line.
+    #[must_use]
+    pub fn parse_speaker<'a>(&self, line: &'a str) -> Option<(&'a str, &'a str)> {
+        let t = &self.speaker;
+        let middle = line
+            .strip_prefix(t.prefix.as_str())?
+            .strip_suffix(t.suffix.as_str())?;
+        let (speaker, text) = if t.speaker_first {
+            middle.split_once(t.separator.as_str())?
+        } else {
+            let (text, speaker) = middle.rsplit_once(t.separator.as_str())?;
+            (speaker, text)
+        };
+        let speaker = if self.note.as_ref().is_some_and(|note| note.wiki_links) {
+            speaker
+                .strip_prefix("[[")
+                .and_then(|name| name.strip_suffix("]]"))
+                .unwrap_or(speaker)
+        } else {
+            speaker
+        };
+        (!speaker.is_empty()).then_some((speaker, text))
+    }
+}
+
+impl SpeakerTemplate {
+    fn new(template: &str) -> Result<Self> {
+        let invalid = || {
+            HooverError::Config(
+                "output.layout.speaker must contain {speaker} and {text} once each, \
+                 with text between them"
+                    .to_string(),
+            )
+        };
+        let (Some(speaker_at), Some(text_at)) =
+            (template.find("{speaker}"), template.find("{text}"))
+        else {
+            return Err(invalid());
+        };
+        let speaker_first = speaker_at < text_at;
+        let (first, first_len, second, second_len) = if speaker_first {
+            (speaker_at, "{speaker}".len(), text_at, "{text}".len())
+        } else {
+            (text_at, "{text}".len(), speaker_at, "{speaker}".len())
+        };
+
+        let separator = &template[first + first_len..second];
+        let suffix = &template[second + second_len..];
+        if separator.is_empty()
+            || template.matches("{speaker}").count() != 1
+            || template.matches("{text}").count() != 1
+        {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            prefix: template[..first].to_string(),
+            separator: separator.to_string(),
+            suffix: suffix.to_string(),
+            speaker_first,
+        })
+    }
+}
+
+/// The number of `#` of a markdown heading line.
+fn heading_level(line: &str) -> Option<usize> {
+    let level = line.len() - line.trim_start_matches('#').len();
+    (level > 0 && line[level..].starts_with(' ')).then_some(level)
+}
+
+/// Offset and text, with its line break, of the managed heading's line.
+fn note_heading<'a>(content: &'a str, heading: &str) -> Option<(usize, &'a str)> {
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        if line.trim_end() == heading {
+            return Some((offset, line));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// Split `template` around its single `placeholder`.
+fn split_placeholder(template: &str, placeholder: &str) -> Option<(String, String)> {
+    let (prefix, suffix) = template.split_once(placeholder)?;
+    (!suffix.contains(placeholder)).then(|| (prefix.to_string(), suffix.to_string()))
+}
+
+/// A day used to check patterns.
+fn sample_date() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2026, 10, 16).unwrap_or_default()
+}
+
+fn check_strftime(pattern: &str, field: &str) -> Result<()> {
+    let mut rendered = String::new();
+    write!(rendered, "{}", sample_date().format(pattern)).map_err(|_| {
+        HooverError::Config(format!(
+            "output.layout.{field} is not a valid strftime pattern: {pattern}"
+        ))
+    })
+}
+
+/// The file pattern must name a relative path from which the day can be
+/// read back.
+fn check_file_pattern(pattern: &str) -> Result<()> {
+    check_strftime(pattern, "file")?;
+    let date = sample_date();
+    let rendered = date.format(pattern).to_string();
+    let relative = Path::new(&rendered)
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)));
+    if !relative || NaiveDate::parse_from_str(&rendered, pattern).ok() != Some(date) {
+        return Err(HooverError::Config(format!(
+            "output.layout.file must be a relative path naming the year, month and day: {pattern}"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DailyNoteConfig;
+
+    fn layout(config: &LayoutConfig) -> Layout {
+        Layout::new(config).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    #[test]
+    fn nested_files_round_trip_dates() {
+        let layout = layout(&LayoutConfig {
+            file: "%Y/%m/%Y-%m-%d.md".to_string(),
+            ..LayoutConfig::default()
+        });
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let date = sample_date();
+
+        let path = layout.day_file(dir.path(), date);
+        assert!(path.ends_with("2026/10/2026-10-16.md"));
+        fs::create_dir_all(path.parent().unwrap_or_else(|| panic!("no parent")))
+            .unwrap_or_else(|e| panic!("{e}"));
+        fs::write(&path, "").unwrap_or_else(|e| panic!("{e}"));
+        fs::write(dir.path().join("notes.md"), "").unwrap_or_else(|e| panic!("{e}"));
+        let next = date.succ_opt().unwrap_or_default();
+        let encrypted = layout.day_file(dir.path(), next);
+        fs::write(crate::vault::encrypted_path(&encrypted), "").unwrap_or_else(|e| panic!("{e}"));
+
+        let files = layout
+            .day_files(dir.path())
+            .unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(files, vec![(date, path), (next, encrypted)]);
+    }
+
+    #[test]
+    fn rejects_unusable_templates() {
+        for config in [
+            LayoutConfig {
+                file: "%Y-%m.md".to_string(),
+                ..LayoutConfig::default()
+            },
+            LayoutConfig {
+                file: "../%Y-%m-%d.md".to_string(),
+                ..LayoutConfig::default()
+            },
+            LayoutConfig {
+                time_heading: "# {time}".to_string(),
+                ..LayoutConfig::default()
+            },
+            LayoutConfig {
+                speaker: "{speaker}{text}".to_string(),
+                ..LayoutConfig::default()
+            },
+        ] {
+            assert!(Layout::new(&config).is_err(), "{config:?}");
+        }
+    }
+
+    #[test]
+    fn templates_render_and_parse() {
+        let layout = layout(&LayoutConfig {
+            time_heading: "### {time} ###".to_string(),
+            granularity: HeadingGranularity::FiveMinutes,
+            speaker: "{text} — *{speaker}*".to_string(),
+            ..LayoutConfig::default()
+        });
+
+        let instant = NaiveTime::from_hms_opt(14, 38, 12).unwrap_or_default();
+        assert_eq!(layout.heading_time(instant), "14:35");
+        let heading = layout.time_heading("14:35");
+        assert_eq!(layout.parse_time_heading(&heading), Some("14:35"));
+        assert_eq!(layout.parse_time_heading("### notes ###"), None);
+
+        let line = layout.speaker_line("Alice", "yes — or no");
+        assert_eq!(line, "yes — or no — *Alice*");
+        assert_eq!(layout.parse_speaker(&line), Some(("Alice", "yes — or no")));
+        assert_eq!(layout.parse_speaker("plain text"), None);
+    }
+
+    #[test]
+    fn daily_notes_bound_the_transcript() {
+        let note = Some(DailyNoteConfig {
+            heading: "## Transcript".to_string(),
+            wiki_links: true,
+        });
+        assert!(
+            Layout::new(&LayoutConfig {
+                daily_note: note.clone(),
+                ..LayoutConfig::default()
+            })
+            .is_err()
+        );
+        let layout = layout(&LayoutConfig {
+            time_heading: "### {time}".to_string(),
+            daily_note: note,
+            ..LayoutConfig::default()
+        });
+        let date = sample_date();
+
+        let content = "# Journal\n\n## Transcript\n\n### 09:00\n\ntext\n\n## Later\n";
+        assert!(layout.has_day_header(content, date));
+        let start = layout.body_start(content, date);
+        assert_eq!(
+            &content[start..layout.body_end(content, start)],
+            "\n### 09:00\n\ntext\n\n"
+        );
+        assert_eq!(layout.transcript_start(content, date), Some(start + 1));
+        assert_eq!(layout.subheading_level(), 3);
+        assert!(!layout.has_day_header("# Journal\n", date));
+        assert_eq!(layout.day_header(date), "## Transcript\n\n");
+
+        let line = layout.speaker_line("Alice", "hi");
+        assert_eq!(line, "**[[Alice]]:** hi");
+        assert_eq!(layout.parse_speaker(&line), Some(("Alice", "hi")));
+        assert_eq!(layout.parse_speaker("**Bob:** hi"), Some(("Bob", "hi")));
+    }
+}
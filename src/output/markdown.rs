@@ -1,36 +1,102 @@
 use std::fmt::Write as _;
 use std::fs::{self, OpenOptions};
-use std::io::Write;
-use std::path::PathBuf;
+use std::io::{Read, Seek, SeekFrom, Write as _};
+use std::path::{Path, PathBuf};
 
-use chrono::{Datelike, Local, NaiveDate};
+use chrono::{DateTime, NaiveDate, Utc};
 
-use crate::config::OutputConfig;
+use crate::config::{OutputConfig, ParagraphJoin};
 use crate::error::{HooverError, Result};
 use crate::stt::TranscriptionSegment;
+use crate::vault::Vault;
+
+use super::Marker;
+use super::calendar::Calendar;
+use super::layout::Layout;
 
 /// Writes transcription segments to daily markdown files.
 pub struct MarkdownWriter {
     output_dir: PathBuf,
     timestamps: bool,
+    layout: Layout,
+    calendar: Calendar,
+    vault: Vault,
     current_date: Option<NaiveDate>,
-    /// The last emitted HH:MM timestamp, to avoid duplicate time headings.
+    /// The time shown by the last time heading, to avoid duplicate headings.
     last_time: Option<String>,
-    /// Trailing words from the last written segment, for overlap deduplication.
-    last_trailing_words: Vec<String>,
+    /// Speaker of the last written turn.
+    last_speaker: Option<String>,
+    /// Whether the last entry can be continued by a segment from the same
+    /// speaker when paragraphs are joined.
+    paragraph_open: bool,
+    /// Heading of the section this writer's entries go under, if any.
+    section: Option<String>,
+    /// Whether other writers add sections to the same daily files, so
+    /// entries must be inserted before them rather than appended.
+    sectioned: bool,
+    /// Length of the plaintext daily file after this writer last appended
+    /// to it, so a joined paragraph can be extended in place while the file
+    /// hasn't changed since.
+    appended_len: Option<u64>,
+}
+
+/// How often a rewrite is retried when the file keeps changing under it.
+const REWRITE_ATTEMPTS: usize = 5;
+
+/// An entry about to be written.
+struct Entry<'a> {
+    /// Time shown by the heading the entry belongs under.
+    time: &'a str,
+    speaker: Option<&'a str>,
+    text: &'a str,
+    /// Whether the speaker differs from the previous entry's.
+    new_turn: bool,
 }
 
 impl MarkdownWriter {
     pub fn new(config: &OutputConfig) -> Result<Self> {
         let output_dir = crate::config::Config::expand_path(&config.directory);
+        let layout = Layout::new(&config.layout)?;
+        let calendar = Calendar::new(config)?;
+        let vault = Vault::new(config)?;
+        Self::with_target(
+            output_dir,
+            config.timestamps,
+            layout,
+            calendar,
+            vault,
+            None,
+            false,
+        )
+    }
+
+    /// Create a writer for daily files in `output_dir`, optionally writing
+    /// into a named section of them.  `sectioned` must be set when other
+    /// writers add sections to the same files.
+    pub fn with_target(
+        output_dir: PathBuf,
+        timestamps: bool,
+        layout: Layout,
+        calendar: Calendar,
+        vault: Vault,
+        section: Option<String>,
+        sectioned: bool,
+    ) -> Result<Self> {
         fs::create_dir_all(&output_dir)?;
 
         Ok(Self {
             output_dir,
-            timestamps: config.timestamps,
+            timestamps,
+            layout,
+            calendar,
+            vault,
             current_date: None,
             last_time: None,
-            last_trailing_words: Vec::new(),
+            last_speaker: None,
+            paragraph_open: false,
+            section,
+            sectioned,
+            appended_len: None,
         })
     }
 
@@ -40,140 +106,492 @@ impl MarkdownWriter {
         segment: &TranscriptionSegment,
         speaker: Option<&str>,
     ) -> Result<()> {
-        let local_time = segment.timestamp.with_timezone(&Local);
-        let date = local_time.date_naive();
+        let local_time = self.calendar.local(segment.timestamp);
+        let date = self.calendar.day_of_local(local_time);
         let path = self.file_path(date);
 
-        // Reset cached state on a new day
-        if self.current_date != Some(date) {
-            self.current_date = Some(date);
-            self.last_time = None;
-            self.last_trailing_words.clear();
+        self.start_day(&path, date);
+
+        let new_turn = self.last_speaker.as_deref() != speaker;
+        if new_turn {
+            self.last_speaker = speaker.map(str::to_string);
         }
 
-        // Overlap deduplication
-        let text = self.deduplicate_overlap(&segment.text);
+        let placeholder = crate::speaker::is_redaction_placeholder(&segment.text);
+        let text = segment.text.trim();
         if text.is_empty() {
             return Ok(());
         }
 
-        // Open once per entry, create if missing, always append
-        let mut file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&path)
-            .map_err(|e| HooverError::Output(format!("failed to open {}: {e}", path.display())))?;
+        let time = self.layout.heading_time(local_time.time());
+        let entry = Entry {
+            time: &time,
+            speaker,
+            text,
+            new_turn,
+        };
+        let joined = !placeholder && self.can_join(&entry) && self.join_entry(&path, date, text)?;
+        if !joined {
+            if self.inserts() {
+                self.insert_entry(&path, date, &entry)?;
+            } else {
+                self.append_entry(&path, date, &entry)?;
+            }
+        }
+        self.paragraph_open = !placeholder;
+
+        tracing::debug!("wrote segment to {}", path.display());
+        Ok(())
+    }
 
-        // Write the day header if the file is empty (new or was deleted)
-        let needs_header = file
-            .metadata()
-            .map(|m| m.len() == 0)
-            .unwrap_or(true);
-        if needs_header {
+    /// Write a voice command's marker: a bookmark heading or a tag line
+    /// under the current time heading.
+    pub fn write_marker(&mut self, marker: &Marker, timestamp: DateTime<Utc>) -> Result<()> {
+        let local_time = self.calendar.local(timestamp);
+        let date = self.calendar.day_of_local(local_time);
+        let path = self.file_path(date);
+        self.start_day(&path, date);
+
+        let text = match marker {
+            Marker::Bookmark(label) if label.is_empty() => "### Bookmark".to_string(),
+            Marker::Bookmark(label) => format!("### Bookmark: {label}"),
+            Marker::Tag(tag) => format!("#{tag}"),
+        };
+        let time = self.layout.heading_time(local_time.time());
+        let entry = Entry {
+            time: &time,
+            speaker: None,
+            text: &text,
+            new_turn: false,
+        };
+        if self.inserts() {
+            self.insert_entry(&path, date, &entry)?;
+        } else {
+            self.append_entry(&path, date, &entry)?;
+        }
+        // The next segment starts a paragraph of its own.
+        self.paragraph_open = false;
+        Ok(())
+    }
+
+    /// Reset cached state on a new day, picking up where an earlier run left
+    /// off in an existing file.
+    fn start_day(&mut self, path: &Path, date: NaiveDate) {
+        if self.current_date != Some(date) {
+            self.current_date = Some(date);
             self.last_time = None;
-            let header = Self::day_header(date);
-            file.write_all(header.as_bytes()).map_err(|e| {
-                HooverError::Output(format!(
-                    "failed to write header to {}: {e}",
-                    path.display()
-                ))
-            })?;
+            self.paragraph_open = false;
+            self.resume(path, date);
         }
+    }
 
-        // Build the entry: emit a time heading only when the HH:MM changes
-        let mut entry = String::new();
-        if self.timestamps {
-            let time_str = local_time.format("%H:%M").to_string();
-            if self.last_time.as_deref() != Some(&time_str) {
-                self.last_time = Some(time_str.clone());
-                let _ = writeln!(entry, "## {time_str}\n");
+    /// Restore the heading and speaker from the last entry in this writer's
+    /// block of an existing daily file.
+    fn resume(&mut self, path: &Path, date: NaiveDate) {
+        let content = match self.vault.read(path) {
+            Ok(Some(content)) => content,
+            Ok(None) => return,
+            Err(e) => {
+                tracing::warn!("failed to resume {}: {e}", path.display());
+                return;
             }
+        };
+        let entries = parse_day(&content, date, &self.layout);
+        let Some(last) = entries.iter().rev().find(|e| e.section == self.section) else {
+            return;
+        };
+        self.last_time.clone_from(&last.time);
+        self.last_speaker.clone_from(&last.speaker);
+    }
+
+    /// Whether `entry` continues the previous paragraph.
+    fn can_join(&self, entry: &Entry<'_>) -> bool {
+        self.layout.join() == ParagraphJoin::Speaker
+            && self.paragraph_open
+            && !entry.new_turn
+            && !(self.timestamps
+                && self
+                    .layout
+                    .needs_heading(self.last_time.as_deref(), entry.time, false))
+    }
+
+    /// Append `text` to the last paragraph of this writer's block.  Returns
+    /// false when the file no longer ends that block with a paragraph.
+    fn join_entry(&mut self, path: &Path, date: NaiveDate, text: &str) -> Result<bool> {
+        if self.extend_appended(path, text)? {
+            return Ok(true);
         }
-        if let Some(name) = speaker {
-            let _ = writeln!(entry, "**{name}:** {text}\n");
-        } else {
-            let _ = writeln!(entry, "{text}\n");
+        self.appended_len = None;
+
+        let vault = self.vault.clone();
+        rewrite(&vault, path, |mut content| {
+            let body_start = self.layout.body_start(&content, date);
+            let block_end = self.block_end(&content, body_start)?;
+            if !content[..block_end].ends_with("\n\n") {
+                return None;
+            }
+            let paragraph_end = content[..block_end].trim_end().len();
+            if paragraph_end <= body_start {
+                return None;
+            }
+            content.insert_str(paragraph_end, &format!(" {text}"));
+            Some(content)
+        })
+    }
+
+    /// Extend the paragraph this writer appended last, when it still ends
+    /// the plaintext file, by overwriting its closing blank line: an append
+    /// rather than a rewrite of the whole day.  Returns false when the file
+    /// changed since, or is encrypted.
+    fn extend_appended(&mut self, path: &Path, text: &str) -> Result<bool> {
+        let Some(len) = self.appended_len.filter(|&len| len >= 2) else {
+            return Ok(false);
+        };
+        if self.inserts() || self.vault.stored_path(path).as_deref() != Some(path) {
+            return Ok(false);
         }
+        let extended = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path)
+            .and_then(|mut file| {
+                if file.metadata()?.len() != len {
+                    return Ok(None);
+                }
+                let mut tail = [0; 2];
+                file.seek(SeekFrom::Start(len - 2))?;
+                file.read_exact(&mut tail)?;
+                if &tail != b"\n\n" {
+                    return Ok(None);
+                }
+                let appended = format!(" {text}\n\n");
+                file.seek(SeekFrom::Start(len - 2))?;
+                file.write_all(appended.as_bytes())?;
+                Ok(Some(len - 2 + appended.len() as u64))
+            })
+            .map_err(|e| {
+                HooverError::Output(format!("failed to write to {}: {e}", path.display()))
+            })?;
+        self.appended_len = extended;
+        Ok(extended.is_some())
+    }
 
-        file.write_all(entry.as_bytes()).map_err(|e| {
-            HooverError::Output(format!("failed to write to {}: {e}", path.display()))
-        })?;
+    /// Whether entries must be inserted into the daily file rather than
+    /// appended: when it has sections after the main transcript, or is a
+    /// daily note with content of its own.
+    const fn inserts(&self) -> bool {
+        self.section.is_some() || self.sectioned || self.layout.is_daily_note()
+    }
 
-        // Store trailing words for next overlap check
-        self.last_trailing_words = text
-            .split_whitespace()
-            .rev()
-            .take(20)
-            .map(str::to_lowercase)
-            .collect();
-        self.last_trailing_words.reverse();
+    /// Append an entry to the end of the daily file.
+    fn append_entry(&mut self, path: &Path, date: NaiveDate, entry: &Entry<'_>) -> Result<()> {
+        // Write the day header if the file is empty (new or was deleted)
+        let mut text = if self.vault.has_content(path) {
+            String::new()
+        } else {
+            self.last_time = None;
+            self.layout.day_header(date)
+        };
 
-        tracing::debug!("wrote segment to {}", path.display());
+        text.push_str(&self.format_entry(entry));
+        self.vault.append(path, &text)?;
+        self.appended_len = if self.vault.stored_path(path).as_deref() == Some(path) {
+            fs::metadata(path).ok().map(|metadata| metadata.len())
+        } else {
+            None
+        };
         Ok(())
     }
 
-    fn file_path(&self, date: NaiveDate) -> PathBuf {
-        self.output_dir
-            .join(format!("{}.md", date.format("%Y-%m-%d")))
+    /// Insert an entry at the end of this writer's block of the daily file.
+    ///
+    /// The main transcript comes first; each section is a level-1 heading
+    /// after it, created the first time it receives an entry.  A daily note
+    /// gets the managed heading at its end when it has none.
+    fn insert_entry(&mut self, path: &Path, date: NaiveDate, entry: &Entry<'_>) -> Result<()> {
+        // Each attempt starts from the heading shown before the first.
+        let last_time = self.last_time.clone();
+        let vault = self.vault.clone();
+        rewrite(&vault, path, |mut content| {
+            self.last_time.clone_from(&last_time);
+            if content.is_empty()
+                || (self.layout.is_daily_note() && !self.layout.has_day_header(&content, date))
+            {
+                self.last_time = None;
+                if !content.is_empty() {
+                    content.truncate(content.trim_end().len());
+                    content.push_str("\n\n");
+                }
+                content.push_str(&self.layout.day_header(date));
+            }
+
+            let body_start = self.layout.body_start(&content, date);
+            let block_end = self.block_end(&content, body_start).unwrap_or_else(|| {
+                self.last_time = None;
+                if !content.ends_with("\n\n") {
+                    content.push('\n');
+                }
+                let _ = writeln!(
+                    content,
+                    "# {}\n",
+                    self.section.as_deref().unwrap_or_default()
+                );
+                content.len()
+            });
+
+            // Exactly one blank line before the entry, even when the file was
+            // edited.
+            let kept = content[..block_end].trim_end().len();
+            content.replace_range(kept..block_end, "\n\n");
+            let block_end = kept + 2;
+
+            let entry = self.format_entry(entry);
+            content.insert_str(block_end, &entry);
+            Some(content)
+        })?;
+        Ok(())
+    }
+
+    /// End of this writer's block in `content`, or `None` if its section
+    /// heading does not exist yet.
+    fn block_end(&self, content: &str, body_start: usize) -> Option<usize> {
+        self.section.as_ref().map_or_else(
+            || Some(self.layout.body_end(content, body_start)),
+            |name| {
+                let heading = format!("# {name}\n");
+                find_line(content, &heading, body_start)
+                    .map(|start| next_section(content, start + heading.len()))
+            },
+        )
+    }
+
+    /// Build an entry, emitting a time heading only when the layout calls
+    /// for one.
+    fn format_entry(&mut self, entry: &Entry<'_>) -> String {
+        let mut out = String::new();
+        if self.timestamps
+            && self
+                .layout
+                .needs_heading(self.last_time.as_deref(), entry.time, entry.new_turn)
+        {
+            self.last_time = Some(entry.time.to_string());
+            let _ = writeln!(out, "{}\n", self.layout.time_heading(entry.time));
+        }
+        if let Some(name) = entry.speaker {
+            let _ = writeln!(out, "{}\n", self.layout.speaker_line(name, entry.text));
+        } else {
+            let _ = writeln!(out, "{}\n", entry.text);
+        }
+        out
     }
 
-    fn day_header(date: NaiveDate) -> String {
-        let weekday = date.weekday();
-        let month = date.format("%B");
-        let day = date.day();
-        let year = date.year();
-        format!("# {weekday}, {month} {day}, {year}\n\n")
+    fn file_path(&self, date: NaiveDate) -> PathBuf {
+        self.layout.day_file(&self.output_dir, date)
     }
+}
 
-    /// Remove overlapping prefix words from the new text.
-    fn deduplicate_overlap(&self, text: &str) -> String {
-        if self.last_trailing_words.is_empty() {
-            return text.to_string();
+/// Rewrite `path` with what `edit` makes of its current content, or leave
+/// it when `edit` returns `None`.  Returns whether it was written.
+///
+/// Daily notes are edited by other apps too: when the file's modification
+/// time or length changed between the read and the write, it is read and
+/// edited again rather than overwritten.
+fn rewrite(
+    vault: &Vault,
+    path: &Path,
+    mut edit: impl FnMut(String) -> Option<String>,
+) -> Result<bool> {
+    let stamp = || {
+        vault
+            .stored_path(path)
+            .and_then(|stored| fs::metadata(stored).ok())
+            .map(|metadata| (metadata.modified().ok(), metadata.len()))
+    };
+    for _ in 0..REWRITE_ATTEMPTS {
+        let before = stamp();
+        let content = vault.read(path)?.unwrap_or_default();
+        let Some(content) = edit(content) else {
+            return Ok(false);
+        };
+        if stamp() == before {
+            vault.write(path, &content)?;
+            return Ok(true);
         }
+        tracing::debug!("{} changed while writing; retrying", path.display());
+    }
+    Err(HooverError::Output(format!(
+        "{} keeps changing; segment not written",
+        path.display()
+    )))
+}
 
-        let new_words: Vec<&str> = text.split_whitespace().collect();
-        if new_words.is_empty() {
-            return String::new();
+/// Offset of the first level-1 heading at or after `from`, or the end of
+/// `content` if there is none.
+fn next_section(content: &str, from: usize) -> usize {
+    let mut offset = from;
+    for line in content[from..].split_inclusive('\n') {
+        if line.starts_with("# ") {
+            return offset;
         }
+        offset += line.len();
+    }
+    content.len()
+}
 
-        let trailing = &self.last_trailing_words;
+/// Offset of the first line at or after `from` equal to `line`.
+fn find_line(content: &str, line: &str, from: usize) -> Option<usize> {
+    let mut offset = from;
+    for candidate in content[from..].split_inclusive('\n') {
+        if candidate == line {
+            return Some(offset);
+        }
+        offset += candidate.len();
+    }
+    None
+}
 
-        // Find the longest prefix of new_words that matches a suffix of last_trailing_words
-        let max_overlap = trailing.len().min(new_words.len());
-        let mut best_overlap = 0;
+/// One entry read back from a daily markdown file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DayEntry {
+    /// The time shown by the heading the entry is under, if any: `HH:MM`, or
+    /// `HH:MM:SS` with per-turn headings.
+    pub time: Option<String>,
+    pub speaker: Option<String>,
+    pub text: String,
+    /// The section heading the entry is under, if any.
+    pub section: Option<String>,
+}
 
-        for overlap_len in 1..=max_overlap {
-            let trailing_suffix = &trailing[trailing.len() - overlap_len..];
-            let new_prefix: Vec<String> = new_words[..overlap_len]
-                .iter()
-                .map(|w| w.to_lowercase())
-                .collect();
+/// Parse the entries of the daily file for `date` written by
+/// [`MarkdownWriter`] with `layout`.
+///
+/// Every non-empty line after the day header that is not a heading or a
+/// line of tags is an entry; a speaker label in the layout's format gives its
+/// speaker.  Other headings before the first time heading of a section, such
+/// as `## Summary`, start notes that run until the next time or level-1
+/// heading and hold no entries; later ones, such as bookmarks, are skipped.
+#[must_use]
+pub fn parse_day(content: &str, date: NaiveDate, layout: &Layout) -> Vec<DayEntry> {
+    let mut entries = Vec::new();
+    let mut time = None;
+    let mut section = None;
+    let mut in_notes = false;
+    // When the header was edited, the first level-1 heading is taken as it.
+    let mut seen_day_header = layout.has_day_header(content, date);
+    let body = if layout.is_daily_note() {
+        // Only the managed section of a daily note is hoover's.
+        if !seen_day_header {
+            return entries;
+        }
+        let start = layout.body_start(content, date);
+        &content[start..layout.body_end(content, start)]
+    } else if seen_day_header {
+        &content[layout.body_start(content, date)..]
+    } else {
+        content
+    };
 
-            if trailing_suffix == new_prefix.as_slice() {
-                best_overlap = overlap_len;
+    for line in body.lines().map(str::trim_end) {
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(heading) = layout.parse_time_heading(line) {
+            time = Some(heading.to_string());
+            in_notes = false;
+            continue;
+        }
+        if let Some(heading) = line.strip_prefix("# ") {
+            // Later level-1 headings start routed sections.
+            if seen_day_header {
+                section = Some(heading.trim().to_string());
+                time = None;
             }
+            seen_day_header = true;
+            in_notes = false;
+            continue;
+        }
+        if line.starts_with("##") && line.trim_start_matches('#').starts_with(' ') {
+            in_notes = time.is_none();
+            continue;
+        }
+        if in_notes || line.starts_with('#') {
+            continue;
         }
 
-        if best_overlap > 0 {
-            new_words[best_overlap..].join(" ")
-        } else {
-            text.to_string()
+        let (speaker, text) = match layout.parse_speaker(line) {
+            Some((name, text)) => (Some(name.to_string()), text),
+            None => (None, line),
+        };
+        entries.push(DayEntry {
+            time: time.clone(),
+            speaker,
+            text: text.to_string(),
+            section: section.clone(),
+        });
+    }
+
+    entries
+}
+
+/// Relabel entries attributed to `from` as `to` throughout a daily file.
+///
+/// Returns the number of entries relabelled; none when the file doesn't
+/// exist.
+pub fn rename_speaker(
+    path: &Path,
+    layout: &Layout,
+    vault: &Vault,
+    from: &str,
+    to: &str,
+) -> Result<usize> {
+    let Some(content) = vault.read(path)? else {
+        return Ok(0);
+    };
+
+    let mut count = 0;
+    let mut updated = String::with_capacity(content.len());
+    for line in content.split_inclusive('\n') {
+        let body = line.trim_end_matches(['\n', '\r']);
+        match layout.parse_speaker(body) {
+            Some((name, text)) if name == from => {
+                count += 1;
+                updated.push_str(&layout.speaker_line(to, text));
+                updated.push_str(&line[body.len()..]);
+            }
+            _ => updated.push_str(line),
         }
     }
+    if count == 0 {
+        return Ok(0);
+    }
+
+    vault.write(path, &updated)?;
+    Ok(count)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::Utc;
+    use chrono::{Local, TimeZone, Utc};
+
+    use crate::config::{DailyNoteConfig, HeadingGranularity, LayoutConfig};
 
     fn test_config(dir: &std::path::Path) -> OutputConfig {
         OutputConfig {
             directory: dir.to_string_lossy().to_string(),
             timestamps: true,
+            routes: Vec::new(),
+            jsonl: false,
+            sinks: None,
+            layout: LayoutConfig::default(),
+            ..OutputConfig::default()
         }
     }
 
+    fn default_layout() -> Layout {
+        Layout::new(&LayoutConfig::default()).unwrap_or_else(|e| panic!("{e}"))
+    }
+
     #[test]
     fn creates_daily_file_with_header() {
         let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
@@ -277,36 +695,81 @@ mod tests {
     }
 
     #[test]
-    fn deduplicates_overlap() {
-        let writer = MarkdownWriter {
-            output_dir: PathBuf::from("/tmp"),
-            timestamps: true,
-            current_date: None,
-            last_time: None,
-            last_trailing_words: vec![
-                "the".to_string(),
-                "quick".to_string(),
-                "brown".to_string(),
-                "fox".to_string(),
-            ],
-        };
+    fn speaker_change_skips_overlap_dedup() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let mut writer =
+            MarkdownWriter::new(&test_config(dir.path())).unwrap_or_else(|e| panic!("{e}"));
 
-        let result = writer.deduplicate_overlap("brown fox jumps over");
-        assert_eq!(result, "jumps over");
+        let now = Utc::now();
+        let turns = [("Alice", "are you ready"), ("Bob", "ready now")];
+        for (speaker, text) in turns {
+            let segment = TranscriptionSegment {
+                text: text.to_string(),
+                timestamp: now,
+                duration_secs: 1.0,
+                confidence: None,
+            };
+            writer
+                .write_segment(&segment, Some(speaker))
+                .unwrap_or_else(|e| panic!("{e}"));
+        }
+
+        let date = now.with_timezone(&Local).date_naive();
+        let file = dir.path().join(format!("{}.md", date.format("%Y-%m-%d")));
+        let content = fs::read_to_string(&file).unwrap_or_else(|e| panic!("{e}"));
+        assert!(content.contains("**Alice:** are you ready"));
+        assert!(content.contains("**Bob:** ready now"));
     }
 
     #[test]
-    fn no_overlap_passes_through() {
-        let writer = MarkdownWriter {
-            output_dir: PathBuf::from("/tmp"),
-            timestamps: true,
-            current_date: None,
-            last_time: None,
-            last_trailing_words: vec!["hello".to_string(), "world".to_string()],
+    fn identical_redaction_placeholders_are_kept() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let mut writer =
+            MarkdownWriter::new(&test_config(dir.path())).unwrap_or_else(|e| panic!("{e}"));
+
+        let now = Utc::now();
+        let segment = TranscriptionSegment {
+            text: crate::speaker::redaction_placeholder(3.0),
+            timestamp: now,
+            duration_secs: 3.0,
+            confidence: None,
         };
+        for _ in 0..2 {
+            writer
+                .write_segment(&segment, None)
+                .unwrap_or_else(|e| panic!("{e}"));
+        }
 
-        let result = writer.deduplicate_overlap("completely different text");
-        assert_eq!(result, "completely different text");
+        let date = now.with_timezone(&Local).date_naive();
+        let content = fs::read_to_string(default_layout().day_file(dir.path(), date))
+            .unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(content.matches("_[other speaker, 3s]_").count(), 2);
+    }
+
+    #[test]
+    fn rename_speaker_rewrites_labels() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let path = dir.path().join("day.md");
+        fs::write(
+            &path,
+            "**Speaker A:** one\n\n**Bob:** two\n\n**Speaker A:** three\n",
+        )
+        .unwrap_or_else(|e| panic!("{e}"));
+
+        let count = rename_speaker(
+            &path,
+            &default_layout(),
+            &Vault::default(),
+            "Speaker A",
+            "Alice",
+        )
+        .unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(count, 2);
+
+        let content = fs::read_to_string(&path).unwrap_or_else(|e| panic!("{e}"));
+        assert!(!content.contains("Speaker A"));
+        assert!(content.contains("**Alice:** three"));
+        assert!(content.contains("**Bob:** two"));
     }
 
     #[test]
@@ -347,7 +810,10 @@ mod tests {
             .unwrap_or_else(|e| panic!("{e}"));
 
         let content = fs::read_to_string(&file).unwrap_or_else(|e| panic!("{e}"));
-        assert!(content.starts_with("# "), "file should start with day header");
+        assert!(
+            content.starts_with("# "),
+            "file should start with day header"
+        );
         assert!(content.contains("after delete"));
     }
 
@@ -386,21 +852,281 @@ mod tests {
             .unwrap_or_else(|e| panic!("{e}"));
 
         let content = fs::read_to_string(&file).unwrap_or_else(|e| panic!("{e}"));
-        assert!(content.starts_with("# "), "file should start with day header");
+        assert!(
+            content.starts_with("# "),
+            "file should start with day header"
+        );
         assert!(content.contains("after truncate"));
     }
 
     #[test]
-    fn empty_trailing_passes_through() {
-        let writer = MarkdownWriter {
-            output_dir: PathBuf::from("/tmp"),
-            timestamps: true,
-            current_date: None,
-            last_time: None,
-            last_trailing_words: Vec::new(),
+    fn parse_day_reads_back_entries() {
+        let content = "# Friday, February 28, 2026\n\n## Summary\n\nA greeting.\n\n\
+                       ## Action items\n\n- Reply\n\n## 14:30\n\n**Alice:** Hello there.\n\n\
+                       Untagged text.\n\n# Others\n\n## 14:31\n\n_[other speaker, 4s]_\n\n";
+        let date = NaiveDate::from_ymd_opt(2026, 2, 28).unwrap_or_default();
+        let entries = parse_day(content, date, &default_layout());
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].time.as_deref(), Some("14:30"));
+        assert_eq!(entries[0].speaker.as_deref(), Some("Alice"));
+        assert_eq!(entries[0].text, "Hello there.");
+        assert_eq!(entries[1].speaker, None);
+        assert_eq!(entries[1].section, None);
+        assert_eq!(entries[2].section.as_deref(), Some("Others"));
+        assert_eq!(entries[2].time.as_deref(), Some("14:31"));
+    }
+
+    #[test]
+    fn rewrite_retries_when_the_file_changes() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let path = dir.path().join("note.md");
+        fs::write(&path, "mine\n").unwrap_or_else(|e| panic!("{e}"));
+
+        let mut edits = 0;
+        let written = rewrite(&Vault::default(), &path, |content| {
+            edits += 1;
+            if edits == 1 {
+                // Another app saves the note meanwhile.
+                fs::write(&path, "mine\ntheirs\n").unwrap_or_else(|e| panic!("{e}"));
+            }
+            Some(format!("{content}hoover\n"))
+        })
+        .unwrap_or_else(|e| panic!("{e}"));
+
+        assert!(written);
+        assert_eq!(edits, 2);
+        let content = fs::read_to_string(&path).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(content, "mine\ntheirs\nhoover\n");
+    }
+
+    #[test]
+    fn templated_layout_writes_and_reads_back() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let config = OutputConfig {
+            layout: LayoutConfig {
+                file: "%Y/%m/%Y-%m-%d.md".to_string(),
+                granularity: HeadingGranularity::Hour,
+                speaker: "{speaker}: {text}".to_string(),
+                join: ParagraphJoin::Speaker,
+                ..LayoutConfig::default()
+            },
+            ..test_config(dir.path())
         };
+        let at = |hour, minute| {
+            Local
+                .with_ymd_and_hms(2026, 10, 16, hour, minute, 0)
+                .single()
+                .unwrap_or_else(|| panic!("ambiguous time"))
+                .with_timezone(&Utc)
+        };
+        let write = |writer: &mut MarkdownWriter, time, speaker, text: &str| {
+            let segment = TranscriptionSegment {
+                text: text.to_string(),
+                timestamp: time,
+                duration_secs: 1.0,
+                confidence: None,
+            };
+            writer
+                .write_segment(&segment, Some(speaker))
+                .unwrap_or_else(|e| panic!("{e}"));
+        };
+
+        let mut writer = MarkdownWriter::new(&config).unwrap_or_else(|e| panic!("{e}"));
+        write(&mut writer, at(14, 5), "Alice", "first part");
+        write(&mut writer, at(14, 20), "Alice", "second part");
+        write(&mut writer, at(14, 21), "Bob", "reply");
+        let path = dir.path().join("2026/10/2026-10-16.md");
+        // An edit behind the writer's back is kept when joining.
+        let content = fs::read_to_string(&path).unwrap_or_else(|e| panic!("{e}"));
+        fs::write(&path, content.replace("reply", "a reply")).unwrap_or_else(|e| panic!("{e}"));
+        write(&mut writer, at(14, 22), "Bob", "indeed");
+        write(&mut writer, at(15, 2), "Bob", "later");
+
+        let content = fs::read_to_string(&path).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(
+            content,
+            "# Fri, October 16, 2026\n\n## 14:00\n\nAlice: first part second part\n\n\
+             Bob: a reply indeed\n\n## 15:00\n\nBob: later\n\n"
+        );
+
+        let layout = Layout::new(&config.layout).unwrap_or_else(|e| panic!("{e}"));
+        let date = at(14, 5).with_timezone(&Local).date_naive();
+        let entries = parse_day(&content, date, &layout);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].time.as_deref(), Some("14:00"));
+        assert_eq!(entries[0].speaker.as_deref(), Some("Alice"));
+        assert_eq!(entries[0].text, "first part second part");
+
+        // A new writer continues the file: the hour heading is not written
+        // again.
+        let mut resumed = MarkdownWriter::new(&config).unwrap_or_else(|e| panic!("{e}"));
+        write(&mut resumed, at(15, 30), "Bob", "on and on");
+        let content = fs::read_to_string(&path).unwrap_or_else(|e| panic!("{e}"));
+        assert!(content.ends_with("## 15:00\n\nBob: later\n\nBob: on and on\n\n"));
+    }
+
+    #[test]
+    fn markers_are_written_between_paragraphs() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let config = OutputConfig {
+            layout: LayoutConfig {
+                join: ParagraphJoin::Speaker,
+                ..LayoutConfig::default()
+            },
+            ..test_config(dir.path())
+        };
+        let at = Local
+            .with_ymd_and_hms(2026, 10, 16, 9, 15, 0)
+            .single()
+            .unwrap_or_else(|| panic!("ambiguous time"))
+            .with_timezone(&Utc);
+        let segment = |text: &str| TranscriptionSegment {
+            text: text.to_string(),
+            timestamp: at,
+            duration_secs: 1.0,
+            confidence: None,
+        };
+
+        let mut writer = MarkdownWriter::new(&config).unwrap_or_else(|e| panic!("{e}"));
+        writer
+            .write_segment(&segment("Budget first."), Some("Alice"))
+            .unwrap_or_else(|e| panic!("{e}"));
+        writer
+            .write_marker(&Marker::Bookmark("budget".to_string()), at)
+            .unwrap_or_else(|e| panic!("{e}"));
+        writer
+            .write_marker(&Marker::Tag("planning".to_string()), at)
+            .unwrap_or_else(|e| panic!("{e}"));
+        writer
+            .write_segment(&segment("Then hiring."), Some("Alice"))
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        let content =
+            fs::read_to_string(dir.path().join("2026-10-16.md")).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(
+            content,
+            "# Fri, October 16, 2026\n\n## 09:15\n\n**Alice:** Budget first.\n\n\
+             ### Bookmark: budget\n\n#planning\n\n**Alice:** Then hiring.\n\n"
+        );
+        let date = at.with_timezone(&Local).date_naive();
+        let entries = parse_day(&content, date, &default_layout());
+        let texts: Vec<&str> = entries.iter().map(|e| e.text.as_str()).collect();
+        assert_eq!(texts, ["Budget first.", "Then hiring."]);
+    }
+
+    #[test]
+    fn encrypted_files_are_written_and_resumed() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let key_file = dir.path().join("transcripts.key");
+        fs::write(&key_file, [3u8; 32]).unwrap_or_else(|e| panic!("{e}"));
+        let config = OutputConfig {
+            encryption: Some(crate::config::EncryptionConfig {
+                key: crate::config::KeySource::File {
+                    path: key_file.to_string_lossy().to_string(),
+                },
+                profiles: false,
+            }),
+            ..test_config(dir.path())
+        };
+        let segment = |text: &str| TranscriptionSegment {
+            text: text.to_string(),
+            timestamp: Utc::now(),
+            duration_secs: 1.0,
+            confidence: None,
+        };
+
+        let mut writer = MarkdownWriter::new(&config).unwrap_or_else(|e| panic!("{e}"));
+        writer
+            .write_segment(&segment("the quick brown fox"), Some("Alice"))
+            .unwrap_or_else(|e| panic!("{e}"));
+        let mut resumed = MarkdownWriter::new(&config).unwrap_or_else(|e| panic!("{e}"));
+        resumed
+            .write_segment(&segment("jumps"), Some("Alice"))
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        let path = default_layout().day_file(dir.path(), Calendar::default().today());
+        assert!(!path.exists());
+        let stored =
+            fs::read(crate::vault::encrypted_path(&path)).unwrap_or_else(|e| panic!("{e}"));
+        assert!(!String::from_utf8_lossy(&stored).contains("quick"));
+
+        let vault = Vault::new(&config).unwrap_or_else(|e| panic!("{e}"));
+        let content = vault
+            .read(&path)
+            .unwrap_or_else(|e| panic!("{e}"))
+            .unwrap_or_else(|| panic!("no day file"));
+        assert!(content.starts_with("# "));
+        assert!(content.contains("**Alice:** the quick brown fox"));
+        assert!(content.contains("**Alice:** jumps"));
+    }
+
+    #[test]
+    fn daily_note_keeps_entries_under_managed_heading() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let config = OutputConfig {
+            layout: LayoutConfig {
+                file: "Journal/%Y-%m-%d.md".to_string(),
+                time_heading: "### {time}".to_string(),
+                daily_note: Some(DailyNoteConfig {
+                    heading: "## Transcript".to_string(),
+                    wiki_links: true,
+                }),
+                ..LayoutConfig::default()
+            },
+            ..test_config(dir.path())
+        };
+        let at = |minute| {
+            Local
+                .with_ymd_and_hms(2026, 10, 16, 9, minute, 0)
+                .single()
+                .unwrap_or_else(|| panic!("ambiguous time"))
+                .with_timezone(&Utc)
+        };
+        let write = |writer: &mut MarkdownWriter, minute, speaker, text: &str| {
+            let segment = TranscriptionSegment {
+                text: text.to_string(),
+                timestamp: at(minute),
+                duration_secs: 1.0,
+                confidence: None,
+            };
+            writer
+                .write_segment(&segment, Some(speaker))
+                .unwrap_or_else(|e| panic!("{e}"));
+        };
+
+        let path = dir.path().join("Journal/2026-10-16.md");
+        fs::create_dir_all(dir.path().join("Journal")).unwrap_or_else(|e| panic!("{e}"));
+        fs::write(&path, "# Friday\n\n## Tasks\n- [ ] call [[Bob]]\n")
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        let mut writer = MarkdownWriter::new(&config).unwrap_or_else(|e| panic!("{e}"));
+        write(&mut writer, 15, "Alice", "hello");
+
+        // The note is edited after the transcript, and before it.
+        let content = fs::read_to_string(&path).unwrap_or_else(|e| panic!("{e}"));
+        let edited = format!(
+            "{}\n## Evening\nlate thoughts\n",
+            content.replace("## Tasks", "Woke up early.\n\n## Tasks")
+        );
+        fs::write(&path, edited).unwrap_or_else(|e| panic!("{e}"));
+        write(&mut writer, 16, "Bob", "hi there");
+
+        let content = fs::read_to_string(&path).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(
+            content,
+            "# Friday\n\nWoke up early.\n\n## Tasks\n- [ ] call [[Bob]]\n\n## Transcript\n\n\
+             ### 09:15\n\n**[[Alice]]:** hello\n\n### 09:16\n\n**[[Bob]]:** hi there\n\n\
+             ## Evening\nlate thoughts\n"
+        );
 
-        let result = writer.deduplicate_overlap("first segment");
-        assert_eq!(result, "first segment");
+        let layout = Layout::new(&config.layout).unwrap_or_else(|e| panic!("{e}"));
+        let date = at(15).with_timezone(&Local).date_naive();
+        let entries = parse_day(&content, date, &layout);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].speaker.as_deref(), Some("Alice"));
+        assert_eq!(entries[1].time.as_deref(), Some("09:16"));
+        assert_eq!(entries[1].speaker.as_deref(), Some("Bob"));
+        assert_eq!(entries[1].text, "hi there");
     }
 }
@@ -1 +1,60 @@
+pub mod calendar;
+pub mod dictation;
+pub mod hook;
+pub mod jsonl;
+pub mod layout;
 pub mod markdown;
+pub mod route;
+pub mod sink;
+#[cfg(target_os = "linux")]
+pub mod uinput;
+
+use chrono::{DateTime, Utc};
+
+use crate::audio::buffer::AudioChunk;
+
+/// Where a batch of segments came from, recorded alongside them by the
+/// structured outputs.
+#[derive(Debug, Clone, Default)]
+pub struct ChunkContext {
+    /// STT backend that transcribed the chunk.
+    pub backend: String,
+    /// Where the audio came from (`microphone` or `udp`).
+    pub source: String,
+    /// Identifies the chunk: its start time in RFC 3339 with milliseconds.
+    pub chunk_id: String,
+    /// Start of the chunk's audio.
+    pub start: DateTime<Utc>,
+    /// Length of the chunk's audio in seconds.
+    pub duration_secs: f32,
+}
+
+impl ChunkContext {
+    #[must_use]
+    pub fn new(backend: &str, chunk: &AudioChunk) -> Self {
+        Self {
+            backend: backend.to_string(),
+            source: chunk.source.to_string(),
+            chunk_id: chunk
+                .timestamp
+                .to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            start: chunk.timestamp,
+            duration_secs: chunk.duration_secs,
+        }
+    }
+
+    /// End of the chunk's audio.
+    #[must_use]
+    pub fn end(&self) -> DateTime<Utc> {
+        self.start + chrono::Duration::milliseconds((self.duration_secs * 1000.0) as i64)
+    }
+}
+
+/// Something a voice command adds to the transcript besides text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Marker {
+    /// A heading, labelled unless the label is empty.
+    Bookmark(String),
+    /// A tag for the current section, without the `#`.
+    Tag(String),
+}
@@ -0,0 +1,355 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+
+use crate::config::{Config, OutputConfig, OutputRoute};
+use crate::error::{HooverError, Result};
+use crate::speaker::AttributedSegment;
+use crate::stt::TranscriptionSegment;
+use crate::vault::Vault;
+
+use super::calendar::Calendar;
+use super::layout::Layout;
+use super::markdown::MarkdownWriter;
+use super::sink::OutputSink;
+use super::{ChunkContext, Marker};
+
+/// `output.routes` resolved against the output directory.
+pub struct Routes {
+    output_dir: PathBuf,
+    rules: Vec<(OutputRoute, PathBuf)>,
+}
+
+impl Routes {
+    pub fn new(config: &OutputConfig) -> Result<Self> {
+        let output_dir = Config::expand_path(&config.directory);
+
+        let mut rules = Vec::with_capacity(config.routes.len());
+        for route in &config.routes {
+            if route.directory.is_none() && route.section.is_none() {
+                return Err(HooverError::Config(format!(
+                    "output route for {} needs a directory or a section",
+                    route.speakers.join(", ")
+                )));
+            }
+            if route.section.is_some() && config.layout.daily_note.is_some() {
+                return Err(HooverError::Config(format!(
+                    "output route for {} can't add a section to daily notes; use a directory",
+                    route.speakers.join(", ")
+                )));
+            }
+            let dir = route.directory.as_deref().map_or_else(
+                || output_dir.clone(),
+                |d| output_dir.join(Config::expand_path(d)),
+            );
+            rules.push((route.clone(), dir));
+        }
+
+        Ok(Self { output_dir, rules })
+    }
+
+    /// Indices of the routes a segment from `speaker` follows, and whether it
+    /// also goes to the main daily file.
+    fn select(&self, speaker: Option<&str>) -> (Vec<usize>, bool) {
+        let mut matched = Vec::new();
+        let mut main = true;
+        for (i, (rule, _)) in self.rules.iter().enumerate() {
+            if rule.matches(speaker) {
+                matched.push(i);
+                main &= rule.copy;
+            }
+        }
+        (matched, main)
+    }
+
+    /// Directories whose daily files receive a segment from `speaker`.
+    #[must_use]
+    pub fn directories(&self, speaker: Option<&str>) -> Vec<&Path> {
+        let (matched, main) = self.select(speaker);
+        let mut dirs: Vec<&Path> = matched
+            .into_iter()
+            .map(|i| self.rules[i].1.as_path())
+            .collect();
+        if main {
+            dirs.push(&self.output_dir);
+        }
+        dirs.sort();
+        dirs.dedup();
+        dirs
+    }
+
+    /// Every directory daily files are written to.
+    #[must_use]
+    pub fn all_directories(&self) -> Vec<&Path> {
+        let mut dirs: Vec<&Path> = self.rules.iter().map(|(_, dir)| dir.as_path()).collect();
+        dirs.push(&self.output_dir);
+        dirs.sort();
+        dirs.dedup();
+        dirs
+    }
+
+    /// Whether some route adds a section to the daily files in `dir`.
+    fn has_sections(&self, dir: &Path) -> bool {
+        self.rules
+            .iter()
+            .any(|(rule, d)| rule.section.is_some() && d == dir)
+    }
+}
+
+/// Writes segments to the main daily files, or to other files or sections
+/// according to `output.routes`.
+pub struct RoutedWriter {
+    routes: Routes,
+    main: MarkdownWriter,
+    /// One writer per route, in the order of `routes.rules`.
+    writers: Vec<MarkdownWriter>,
+}
+
+impl RoutedWriter {
+    pub fn new(config: &OutputConfig) -> Result<Self> {
+        let routes = Routes::new(config)?;
+        let layout = Layout::new(&config.layout)?;
+        let calendar = Calendar::new(config)?;
+        let vault = Vault::new(config)?;
+
+        // Writers sharing daily files with a section must keep their entries
+        // ahead of it.
+        let main = MarkdownWriter::with_target(
+            routes.output_dir.clone(),
+            config.timestamps,
+            layout.clone(),
+            calendar,
+            vault.clone(),
+            None,
+            routes.has_sections(&routes.output_dir),
+        )?;
+        let writers = routes
+            .rules
+            .iter()
+            .map(|(rule, dir)| {
+                MarkdownWriter::with_target(
+                    dir.clone(),
+                    config.timestamps,
+                    layout.clone(),
+                    calendar,
+                    vault.clone(),
+                    rule.section.clone(),
+                    routes.has_sections(dir),
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            routes,
+            main,
+            writers,
+        })
+    }
+
+    /// Write a segment to every matching route, and to the main daily file
+    /// unless a matching route takes it exclusively.
+    pub fn write_segment(
+        &mut self,
+        segment: &TranscriptionSegment,
+        speaker: Option<&str>,
+    ) -> Result<()> {
+        self.each_target(speaker, |writer| writer.write_segment(segment, speaker))
+    }
+
+    /// Write a marker wherever a segment from `speaker` would go.
+    pub fn write_marker(
+        &mut self,
+        marker: &Marker,
+        timestamp: DateTime<Utc>,
+        speaker: Option<&str>,
+    ) -> Result<()> {
+        self.each_target(speaker, |writer| writer.write_marker(marker, timestamp))
+    }
+
+    /// Run `write` on every writer `speaker` is routed to.  A failing target
+    /// is logged and doesn't keep the others from being written; the first
+    /// error is returned afterwards.
+    fn each_target(
+        &mut self,
+        speaker: Option<&str>,
+        mut write: impl FnMut(&mut MarkdownWriter) -> Result<()>,
+    ) -> Result<()> {
+        let (matched, main) = self.routes.select(speaker);
+        let routed = self
+            .writers
+            .iter_mut()
+            .zip(&self.routes.rules)
+            .enumerate()
+            .filter(|(i, _)| matched.contains(i))
+            .map(|(_, (writer, (_, dir)))| (writer, dir.as_path()));
+        let main = main.then_some((&mut self.main, self.routes.output_dir.as_path()));
+
+        let mut first_error = None;
+        for (writer, dir) in routed.chain(main) {
+            if let Err(e) = write(writer) {
+                tracing::error!("writing to {} failed: {e}", dir.display());
+                first_error.get_or_insert(e);
+            }
+        }
+        first_error.map_or(Ok(()), Err)
+    }
+}
+
+impl OutputSink for RoutedWriter {
+    fn name(&self) -> &'static str {
+        "markdown"
+    }
+
+    fn write_segment(&mut self, turn: &AttributedSegment, _context: &ChunkContext) -> Result<()> {
+        Self::write_segment(self, &turn.segment, turn.speaker.as_deref())
+    }
+
+    fn write_marker(&mut self, marker: &Marker, turn: &AttributedSegment) -> Result<()> {
+        Self::write_marker(
+            self,
+            marker,
+            turn.segment.timestamp,
+            turn.speaker.as_deref(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    use chrono::{Local, Utc};
+
+    use crate::config::LayoutConfig;
+
+    fn segment(text: &str) -> TranscriptionSegment {
+        TranscriptionSegment {
+            text: text.to_string(),
+            timestamp: Utc::now(),
+            duration_secs: 1.0,
+            confidence: None,
+        }
+    }
+
+    fn route(speakers: &[&str], directory: Option<&str>, section: Option<&str>) -> OutputRoute {
+        OutputRoute {
+            speakers: speakers.iter().map(|s| (*s).to_string()).collect(),
+            directory: directory.map(str::to_string),
+            section: section.map(str::to_string),
+            copy: false,
+        }
+    }
+
+    fn config(dir: &std::path::Path, routes: Vec<OutputRoute>) -> OutputConfig {
+        OutputConfig {
+            directory: dir.to_string_lossy().to_string(),
+            timestamps: false,
+            routes,
+            jsonl: false,
+            sinks: None,
+            layout: LayoutConfig::default(),
+            ..OutputConfig::default()
+        }
+    }
+
+    fn read_day(dir: &std::path::Path) -> String {
+        let layout = Layout::new(&LayoutConfig::default()).unwrap_or_else(|e| panic!("{e}"));
+        fs::read_to_string(layout.day_file(dir, Local::now().date_naive()))
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    #[test]
+    fn routes_speaker_to_separate_directory() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let mut copied = route(&["Bob"], Some("shared"), None);
+        copied.copy = true;
+        let routes = vec![route(&["Alice"], Some("dictation"), None), copied];
+        let mut writer =
+            RoutedWriter::new(&config(dir.path(), routes)).unwrap_or_else(|e| panic!("{e}"));
+
+        for (text, speaker) in [("my note", Some("Alice")), ("hi", Some("Bob")), ("?", None)] {
+            writer
+                .write_segment(&segment(text), speaker)
+                .unwrap_or_else(|e| panic!("{e}"));
+        }
+
+        let main = read_day(dir.path());
+        assert!(!main.contains("my note"));
+        assert!(main.contains("**Bob:** hi"));
+        assert!(main.contains("\n?\n"));
+
+        let dictation = read_day(&dir.path().join("dictation"));
+        assert!(dictation.contains("**Alice:** my note"));
+        assert!(read_day(&dir.path().join("shared")).contains("**Bob:** hi"));
+    }
+
+    #[test]
+    fn failing_route_still_writes_main_file() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let mut copied = route(&["Bob"], Some("shared"), None);
+        copied.copy = true;
+        let mut writer =
+            RoutedWriter::new(&config(dir.path(), vec![copied])).unwrap_or_else(|e| panic!("{e}"));
+        // A file where the route's directory should be.
+        let shared = dir.path().join("shared");
+        let _ = fs::remove_dir_all(&shared);
+        fs::write(&shared, "").unwrap_or_else(|e| panic!("{e}"));
+
+        assert!(writer.write_segment(&segment("hi"), Some("Bob")).is_err());
+        assert!(read_day(dir.path()).contains("**Bob:** hi"));
+    }
+
+    #[test]
+    fn section_collects_entries_after_main_transcript() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let routes = vec![route(&["unknown"], None, Some("Others"))];
+        let mut writer =
+            RoutedWriter::new(&config(dir.path(), routes)).unwrap_or_else(|e| panic!("{e}"));
+
+        let turns = [
+            ("first", Some("Alice")),
+            ("aside", None),
+            ("second", Some("Alice")),
+            ("another aside", None),
+        ];
+        for (text, speaker) in turns {
+            writer
+                .write_segment(&segment(text), speaker)
+                .unwrap_or_else(|e| panic!("{e}"));
+        }
+
+        let content = read_day(dir.path());
+        let position = |needle: &str| {
+            content
+                .find(needle)
+                .unwrap_or_else(|| panic!("{needle} missing from {content}"))
+        };
+        assert!(position("first") < position("second"));
+        assert!(position("second") < position("# Others"));
+        assert!(position("# Others") < position("aside"));
+        assert!(position("aside") < position("another aside"));
+        assert_eq!(content.matches("# Others").count(), 1);
+    }
+
+    #[test]
+    fn route_without_target_is_rejected() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let routes = vec![route(&["Alice"], None, None)];
+        assert!(RoutedWriter::new(&config(dir.path(), routes)).is_err());
+    }
+
+    #[test]
+    fn directories_follow_routes_once_each() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let mut section = route(&["unknown"], None, Some("Others"));
+        section.copy = true;
+        let routes = vec![route(&["Alice"], Some("dictation"), None), section];
+        let routes = Routes::new(&config(dir.path(), routes)).unwrap_or_else(|e| panic!("{e}"));
+
+        let dictation = dir.path().join("dictation");
+        assert_eq!(routes.directories(Some("Alice")), vec![dictation.as_path()]);
+        assert_eq!(routes.directories(None), vec![dir.path()]);
+        assert_eq!(routes.directories(Some("Bob")), vec![dir.path()]);
+    }
+}
@@ -0,0 +1,314 @@
+use std::io::Write;
+
+use chrono::NaiveDate;
+
+use crate::config::{OutputConfig, SinkConfig, StdoutFormat};
+use crate::error::{HooverError, Result};
+use crate::index::IndexSink;
+use crate::speaker::AttributedSegment;
+
+use super::calendar::Calendar;
+use super::dictation::{DictationSink, DictationSwitch, create_injector};
+use super::hook::{CommandSink, WebhookSink};
+use super::jsonl::{JsonlSink, SegmentRecord};
+use super::route::RoutedWriter;
+use super::{ChunkContext, Marker};
+
+/// A destination for transcribed segments.
+pub trait OutputSink: Send {
+    /// Short name used in log messages.
+    fn name(&self) -> &'static str;
+
+    /// Write one speaker turn.
+    fn write_segment(&mut self, turn: &AttributedSegment, context: &ChunkContext) -> Result<()>;
+
+    /// Write a marker at the time of `turn`, the command that placed it.
+    fn write_marker(&mut self, _marker: &Marker, _turn: &AttributedSegment) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called before the first segment of a new local day.
+    fn day_rollover(&mut self, _date: NaiveDate) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called after each batch of segments.
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called once when recording stops; finishes any pending work.
+    fn shutdown(&mut self) -> Result<()> {
+        self.flush()
+    }
+}
+
+/// All configured sinks.  A failing sink is logged and skipped so it never
+/// keeps segments from the others.
+pub struct Sinks {
+    outputs: Vec<Box<dyn OutputSink>>,
+    calendar: Calendar,
+    current_date: Option<NaiveDate>,
+    /// The day the last rollover finished, until taken.
+    finished_date: Option<NaiveDate>,
+    /// Shared with the dictation sinks.
+    dictation: DictationSwitch,
+}
+
+impl Sinks {
+    /// Create the sinks listed in `output.sinks`.
+    pub fn new(config: &OutputConfig) -> Result<Self> {
+        let push_to_talk = config.sinks().iter().any(|sink| {
+            matches!(
+                sink,
+                SinkConfig::Dictation {
+                    push_to_talk: true,
+                    ..
+                }
+            )
+        });
+        let dictation = DictationSwitch::new(!push_to_talk);
+        let sinks = config
+            .sinks()
+            .iter()
+            .map(|sink| create_sink(config, sink, &dictation))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            calendar: Calendar::new(config)?,
+            dictation,
+            ..Self::from_sinks(sinks)
+        })
+    }
+
+    /// Wrap already created sinks, with days starting at local midnight.
+    #[must_use]
+    pub fn from_sinks(sinks: Vec<Box<dyn OutputSink>>) -> Self {
+        Self {
+            outputs: sinks,
+            calendar: Calendar::default(),
+            current_date: None,
+            finished_date: None,
+            dictation: DictationSwitch::default(),
+        }
+    }
+
+    /// The switch turning dictation on and off.
+    #[must_use]
+    pub const fn dictation(&self) -> &DictationSwitch {
+        &self.dictation
+    }
+
+    /// Write a batch of turns from one chunk to every sink, then flush them.
+    pub fn write_turns(&mut self, turns: &[AttributedSegment], context: &ChunkContext) {
+        for turn in turns {
+            let date = self.calendar.day_of(turn.segment.timestamp);
+            if self.current_date.is_some_and(|current| current != date) {
+                self.each("day rollover", |sink| sink.day_rollover(date));
+                self.finished_date = self.current_date;
+            }
+            self.current_date = Some(date);
+
+            self.each("write", |sink| sink.write_segment(turn, context));
+        }
+        self.each("flush", |sink| sink.flush());
+    }
+
+    /// The day finished by the last rollover, once: every segment of it has
+    /// been written by then.
+    pub const fn take_finished_day(&mut self) -> Option<NaiveDate> {
+        self.finished_date.take()
+    }
+
+    /// Write a marker to every sink that shows them, then flush them.
+    pub fn write_marker(&mut self, marker: &Marker, turn: &AttributedSegment) {
+        self.each("marker", |sink| sink.write_marker(marker, turn));
+        self.each("flush", |sink| sink.flush());
+    }
+
+    /// Shut every sink down, waiting for pending work.
+    pub fn shutdown(&mut self) {
+        self.each("shutdown", |sink| sink.shutdown());
+    }
+
+    fn each(&mut self, action: &str, mut f: impl FnMut(&mut dyn OutputSink) -> Result<()>) {
+        for sink in &mut self.outputs {
+            if let Err(e) = f(sink.as_mut()) {
+                tracing::error!("{} output {action} failed: {e}", sink.name());
+            }
+        }
+    }
+}
+
+fn create_sink(
+    config: &OutputConfig,
+    sink: &SinkConfig,
+    dictation: &DictationSwitch,
+) -> Result<Box<dyn OutputSink>> {
+    Ok(match sink {
+        SinkConfig::Markdown => Box::new(RoutedWriter::new(config)?),
+        SinkConfig::Jsonl => Box::new(JsonlSink::new(config)?),
+        SinkConfig::Stdout { format } => Box::new(StdoutSink {
+            format: *format,
+            calendar: Calendar::new(config)?,
+        }),
+        SinkConfig::Command { command } => Box::new(CommandSink::new(command)?),
+        SinkConfig::Sqlite { .. } => {
+            let path = config
+                .index_path()
+                .ok_or_else(|| HooverError::Config("no index path configured".to_string()))?;
+            Box::new(IndexSink::new(&path, Calendar::new(config)?)?)
+        }
+        SinkConfig::Webhook { url, headers } => Box::new(WebhookSink::new(url, headers)?),
+        SinkConfig::Dictation {
+            injector,
+            punctuation,
+            push_to_talk: _,
+            speakers,
+        } => Box::new(DictationSink::new(
+            create_injector(*injector)?,
+            dictation.clone(),
+            *punctuation,
+            speakers.clone(),
+        )),
+    })
+}
+
+/// Prints one line per segment to standard output.
+pub struct StdoutSink {
+    format: StdoutFormat,
+    calendar: Calendar,
+}
+
+impl OutputSink for StdoutSink {
+    fn name(&self) -> &'static str {
+        "stdout"
+    }
+
+    fn write_segment(&mut self, turn: &AttributedSegment, context: &ChunkContext) -> Result<()> {
+        let line = match self.format {
+            StdoutFormat::Text => format_text_line(turn, &self.calendar),
+            StdoutFormat::Jsonl => serde_json::to_string(&SegmentRecord::new(turn, context))
+                .map_err(|e| HooverError::Output(format!("failed to serialize segment: {e}")))?,
+        };
+        writeln!(std::io::stdout().lock(), "{line}")
+            .map_err(|e| HooverError::Output(format!("failed to write to stdout: {e}")))
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        std::io::stdout()
+            .flush()
+            .map_err(|e| HooverError::Output(format!("failed to flush stdout: {e}")))
+    }
+}
+
+/// `HH:MM:SS Speaker: text`, or `HH:MM:SS text` without a speaker.
+fn format_text_line(turn: &AttributedSegment, calendar: &Calendar) -> String {
+    let time = calendar.local(turn.segment.timestamp).format("%H:%M:%S");
+    let text = &turn.segment.text;
+    turn.speaker.as_ref().map_or_else(
+        || format!("{time} {text}"),
+        |name| format!("{time} {name}: {text}"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    use chrono::{Duration, Local, Utc};
+
+    use crate::stt::TranscriptionSegment;
+
+    /// Records calls, failing every write when `fail` is set.
+    struct Probe {
+        log: Arc<Mutex<Vec<String>>>,
+        fail: bool,
+    }
+
+    impl OutputSink for Probe {
+        fn name(&self) -> &'static str {
+            "probe"
+        }
+
+        fn write_segment(&mut self, turn: &AttributedSegment, _: &ChunkContext) -> Result<()> {
+            if self.fail {
+                return Err(HooverError::Output("broken".to_string()));
+            }
+            self.log
+                .lock()
+                .unwrap_or_else(|e| panic!("{e}"))
+                .push(turn.segment.text.clone());
+            Ok(())
+        }
+
+        fn day_rollover(&mut self, date: NaiveDate) -> Result<()> {
+            self.log
+                .lock()
+                .unwrap_or_else(|e| panic!("{e}"))
+                .push(format!("rollover {date}"));
+            Ok(())
+        }
+    }
+
+    fn turn(text: &str, timestamp: chrono::DateTime<Utc>) -> AttributedSegment {
+        AttributedSegment::unattributed(TranscriptionSegment {
+            text: text.to_string(),
+            timestamp,
+            duration_secs: 1.0,
+            confidence: None,
+        })
+    }
+
+    #[test]
+    fn failing_sink_does_not_block_others() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut sinks = Sinks::from_sinks(vec![
+            Box::new(Probe {
+                log: Arc::clone(&log),
+                fail: true,
+            }),
+            Box::new(Probe {
+                log: Arc::clone(&log),
+                fail: false,
+            }),
+        ]);
+
+        let now = Utc::now();
+        let tomorrow = now + Duration::days(1);
+        sinks.write_turns(&[turn("one", now)], &ChunkContext::default());
+        assert_eq!(sinks.take_finished_day(), None);
+        sinks.write_turns(&[turn("two", tomorrow)], &ChunkContext::default());
+        let today = now.with_timezone(&Local).date_naive();
+        assert_eq!(sinks.take_finished_day(), Some(today));
+        assert_eq!(sinks.take_finished_day(), None);
+        sinks.shutdown();
+
+        let date = tomorrow.with_timezone(&Local).date_naive();
+        let log = log.lock().unwrap_or_else(|e| panic!("{e}")).clone();
+        assert_eq!(
+            log,
+            vec![
+                "one".to_string(),
+                format!("rollover {date}"),
+                format!("rollover {date}"),
+                "two".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn default_sinks_follow_jsonl_shorthand() {
+        let mut config = OutputConfig::default();
+        assert_eq!(config.sinks(), vec![SinkConfig::Markdown]);
+        config.jsonl = true;
+        assert_eq!(
+            config.sinks(),
+            vec![SinkConfig::Markdown, SinkConfig::Jsonl]
+        );
+        config.sinks = Some(vec![SinkConfig::Stdout {
+            format: StdoutFormat::Text,
+        }]);
+        assert_eq!(config.sinks().len(), 1);
+    }
+}
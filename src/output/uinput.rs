@@ -0,0 +1,179 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::time::Duration;
+
+use crate::error::{HooverError, Result};
+
+use super::dictation::Injector;
+
+// From linux/uinput.h and linux/input-event-codes.h.
+const UI_SET_EVBIT: libc::Ioctl = 0x4004_5564;
+const UI_SET_KEYBIT: libc::Ioctl = 0x4004_5565;
+const UI_DEV_SETUP: libc::Ioctl = 0x405c_5503;
+const UI_DEV_CREATE: libc::Ioctl = 0x5501;
+const UI_DEV_DESTROY: libc::Ioctl = 0x5502;
+const EV_SYN: u16 = 0x00;
+const EV_KEY: u16 = 0x01;
+const SYN_REPORT: u16 = 0;
+const BUS_VIRTUAL: u16 = 0x06;
+const KEY_LEFTSHIFT: u16 = 42;
+/// Highest key code registered with the device, the space bar.
+const KEY_MAX: u16 = 57;
+
+/// Time for the compositor to pick up a new device before it is typed on.
+const SETTLE_TIME: Duration = Duration::from_millis(300);
+
+/// Types through a virtual keyboard on `/dev/uinput`, which works under X11,
+/// Wayland and the console alike but needs write access to the device.
+///
+/// Keys are sent for a US layout; characters it has no key for are skipped.
+pub struct UinputInjector {
+    device: File,
+}
+
+impl UinputInjector {
+    pub fn new() -> Result<Self> {
+        let device = OpenOptions::new()
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open("/dev/uinput")
+            .map_err(|e| HooverError::Output(format!("failed to open /dev/uinput: {e}")))?;
+        let fd = device.as_raw_fd();
+
+        let mut setup = libc::uinput_setup {
+            id: libc::input_id {
+                bustype: BUS_VIRTUAL,
+                vendor: 0,
+                product: 0,
+                version: 1,
+            },
+            name: [0; libc::UINPUT_MAX_NAME_SIZE],
+            ff_effects_max: 0,
+        };
+        for (dst, src) in setup.name.iter_mut().zip(b"hoover dictation") {
+            *dst = libc::c_char::try_from(*src).unwrap_or_default();
+        }
+
+        // Safety: each ioctl gets the argument type linux/uinput.h declares
+        // for it, on a file descriptor that stays open for the call.
+        let failed = unsafe {
+            libc::ioctl(fd, UI_SET_EVBIT, libc::c_int::from(EV_KEY)) < 0
+                || (1..=KEY_MAX)
+                    .any(|key| libc::ioctl(fd, UI_SET_KEYBIT, libc::c_int::from(key)) < 0)
+                || libc::ioctl(fd, UI_DEV_SETUP, &raw const setup) < 0
+                || libc::ioctl(fd, UI_DEV_CREATE) < 0
+        };
+        if failed {
+            return Err(HooverError::Output(format!(
+                "failed to create a uinput keyboard: {}",
+                std::io::Error::last_os_error()
+            )));
+        }
+        std::thread::sleep(SETTLE_TIME);
+        Ok(Self { device })
+    }
+
+    fn emit(&mut self, kind: u16, code: u16, value: i32) -> Result<()> {
+        let event = libc::input_event {
+            time: libc::timeval {
+                tv_sec: 0,
+                tv_usec: 0,
+            },
+            type_: kind,
+            code,
+            value,
+        };
+        // Safety: input_event is plain old data, viewed here as its bytes.
+        let bytes = unsafe {
+            std::slice::from_raw_parts(
+                (&raw const event).cast::<u8>(),
+                std::mem::size_of::<libc::input_event>(),
+            )
+        };
+        self.device
+            .write_all(bytes)
+            .map_err(|e| HooverError::Output(format!("failed to write to /dev/uinput: {e}")))
+    }
+
+    fn key(&mut self, code: u16, pressed: bool) -> Result<()> {
+        self.emit(EV_KEY, code, i32::from(pressed))?;
+        self.emit(EV_SYN, SYN_REPORT, 0)
+    }
+}
+
+impl Injector for UinputInjector {
+    fn type_text(&mut self, text: &str) -> Result<()> {
+        for c in text.chars() {
+            let Some((code, shift)) = key_for(c) else {
+                tracing::debug!("no key for {c:?}, skipping");
+                continue;
+            };
+            if shift {
+                self.key(KEY_LEFTSHIFT, true)?;
+            }
+            self.key(code, true)?;
+            self.key(code, false)?;
+            if shift {
+                self.key(KEY_LEFTSHIFT, false)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for UinputInjector {
+    fn drop(&mut self) {
+        // Safety: UI_DEV_DESTROY takes no argument.
+        unsafe {
+            libc::ioctl(self.device.as_raw_fd(), UI_DEV_DESTROY);
+        }
+    }
+}
+
+/// Key code on a US layout for `c`, and whether it needs shift.
+fn key_for(c: char) -> Option<(u16, bool)> {
+    const ROWS: [(&str, &str, u16); 4] = [
+        ("1234567890-=", "!@#$%^&*()_+", 2),
+        ("qwertyuiop[]", "QWERTYUIOP{}", 16),
+        ("asdfghjkl;'`", "ASDFGHJKL:\"~", 30),
+        ("\\zxcvbnm,./", "|ZXCVBNM<>?", 43),
+    ];
+    let c = match c {
+        '‘' | '’' => '\'',
+        '“' | '”' => '"',
+        '–' | '—' => '-',
+        c => c,
+    };
+    match c {
+        '\n' => return Some((28, false)),
+        '\t' => return Some((15, false)),
+        ' ' => return Some((57, false)),
+        _ => {}
+    }
+    ROWS.iter().find_map(|(plain, shifted, first)| {
+        let at = |keys: &str| keys.chars().position(|k| k == c);
+        let (index, shift) = at(plain)
+            .map(|i| (i, false))
+            .or_else(|| at(shifted).map(|i| (i, true)))?;
+        Some((first + u16::try_from(index).ok()?, shift))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_follow_the_us_layout() {
+        assert_eq!(key_for('a'), Some((30, false)));
+        assert_eq!(key_for('A'), Some((30, true)));
+        assert_eq!(key_for('0'), Some((11, false)));
+        assert_eq!(key_for('?'), Some((53, true)));
+        assert_eq!(key_for('\\'), Some((43, false)));
+        assert_eq!(key_for('’'), Some((40, false)));
+        assert_eq!(key_for('\n'), Some((28, false)));
+        assert_eq!(key_for('é'), None);
+    }
+}
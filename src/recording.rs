@@ -1,18 +1,41 @@
+use std::time::Duration;
+
+use chrono::NaiveDate;
 use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
 
 use crate::audio::buffer::AudioChunk;
-use crate::config::Config;
+use crate::commands::{Output, VoiceCommands};
+use crate::config::{Config, SinkConfig, UnknownPolicy};
+use crate::control::{ControlServer, socket_path};
+use crate::corrections::Corrector;
+use crate::dedup::Deduplicator;
 use crate::error::Result;
-use crate::output::markdown::MarkdownWriter;
-use crate::stt;
+use crate::output::ChunkContext;
+use crate::output::calendar::Calendar;
+use crate::output::sink::Sinks;
+use crate::redact::Redactor;
+use crate::speaker::identify::{SpeakerIdentifier, SpeakerMatch};
+use crate::speaker::{AttributedSegment, merge_turns};
+use crate::stt::{self, TranscriptionSegment};
+use crate::vault::Vault;
 
-/// Main recording loop: capture audio -> STT -> markdown output.
+/// Main recording loop: capture audio -> STT -> output sinks.
 #[allow(clippy::too_many_lines)]
 pub async fn run_recording(config: Config) -> Result<()> {
     tracing::info!("starting recording with {} backend", config.stt.backend);
 
     let (chunk_tx, mut chunk_rx) = mpsc::channel::<AudioChunk>(32);
 
+    // Only dictation has anything to control; without a private runtime
+    // directory for its socket, refuse to start
+    let has_dictation = config
+        .output
+        .sinks()
+        .iter()
+        .any(|sink| matches!(sink, SinkConfig::Dictation { .. }));
+    let control_path = has_dictation.then(socket_path).transpose()?;
+
     // Start audio capture pipeline
     let capture = crate::audio::start_audio_pipeline(&config.audio, chunk_tx.clone())?;
     capture.start()?;
@@ -47,11 +70,13 @@ pub async fn run_recording(config: Config) -> Result<()> {
 
     // Create STT engine (runs in a dedicated thread for blocking operations)
     let (stt_tx, mut stt_rx) = mpsc::channel::<AudioChunk>(16);
-    let (result_tx, mut result_rx) =
-        mpsc::channel::<(Vec<crate::stt::TranscriptionSegment>, Option<String>)>(16);
+    let (result_tx, mut result_rx) = mpsc::channel::<(ChunkContext, Vec<AttributedSegment>)>(16);
 
     let stt_config = config.stt.clone();
     let speaker_config = config.speaker.clone();
+    let unknown_policy = config.speaker.unknown_policy();
+    let calendar = Calendar::new(&config.output)?;
+    let profiles_vault = Vault::for_profiles(&config.output)?;
     std::thread::spawn(move || {
         let mut engine = match stt::create_engine(&stt_config) {
             Ok(e) => e,
@@ -65,7 +90,8 @@ pub async fn run_recording(config: Config) -> Result<()> {
 
         // Initialize speaker identifier alongside STT
         let mut speaker_id = if speaker_config.enabled {
-            match crate::speaker::identify::SpeakerIdentifier::new(&speaker_config, stt_config.gpu) {
+            match SpeakerIdentifier::new(&speaker_config, stt_config.gpu, calendar, profiles_vault)
+            {
                 Ok(id) => Some(id),
                 Err(e) => {
                     tracing::warn!("speaker identification disabled: {e}");
@@ -77,20 +103,16 @@ pub async fn run_recording(config: Config) -> Result<()> {
         };
 
         while let Some(chunk) = stt_rx.blocking_recv() {
-            let speaker_name = speaker_id.as_mut().and_then(|id| {
-                match id.identify(&chunk.samples_f32) {
-                    Ok(Some(m)) => m.name,
-                    Ok(None) => None, // filter_unknown suppressed this chunk
-                    Err(e) => {
-                        tracing::warn!("speaker identification error: {e}");
-                        None
-                    }
-                }
-            });
-
             match engine.transcribe(&chunk) {
                 Ok(segments) => {
-                    if result_tx.blocking_send((segments, speaker_name)).is_err() {
+                    let context = ChunkContext::new(engine.name(), &chunk);
+                    let identify = speaker_id.as_mut().map(|id| {
+                        move |samples: &[f32], offset_secs, duration_secs| {
+                            id.identify_segment(samples, offset_secs, duration_secs)
+                        }
+                    });
+                    let turns = attribute_segments(identify, unknown_policy, &chunk, segments);
+                    if result_tx.blocking_send((context, turns)).is_err() {
                         break;
                     }
                 }
@@ -101,15 +123,32 @@ pub async fn run_recording(config: Config) -> Result<()> {
         }
 
         // Flush any pending speaker profile updates before exiting
-        if let Some(ref id) = speaker_id {
+        if let Some(id) = &mut speaker_id {
             id.flush();
         }
 
         tracing::debug!("STT thread exiting");
     });
 
-    // Initialize output writer
-    let mut writer = MarkdownWriter::new(&config.output)?;
+    // Initialize output sinks, and the deduplication, corrections, redaction
+    // and voice commands applied before them
+    let mut sinks = Sinks::new(&config.output)?;
+    let mut dedup = Deduplicator::default();
+    let mut corrector = Corrector::new(&config)?;
+    let redactor = Redactor::new(&config)?;
+    let mut commands = VoiceCommands::new(&config)?;
+    let mut idle = tokio::time::interval(Duration::from_secs(5));
+
+    // Serve `hoover dictate` on the control socket
+    let control =
+        match control_path.map(|path| ControlServer::bind(&path, sinks.dictation().clone())) {
+            Some(Ok(server)) => Some(tokio::spawn(server.run())),
+            Some(Err(e)) => {
+                tracing::warn!("control socket unavailable: {e}");
+                None
+            }
+            None => None,
+        };
 
     // Set up Ctrl+C handler
     let (shutdown_tx, mut shutdown_rx) = tokio::sync::oneshot::channel::<()>();
@@ -119,26 +158,39 @@ pub async fn run_recording(config: Config) -> Result<()> {
         let _ = shutdown_tx.send(());
     });
 
+    let (maintenance_tx, maintenance) = spawn_maintenance(config.clone());
+
     // Main processing loop
     loop {
         tokio::select! {
             Some(chunk) = chunk_rx.recv() => {
+                // Audio spoken during a paused stretch is never transcribed
+                if commands.is_paused(chunk.timestamp) {
+                    continue;
+                }
                 if stt_tx.send(chunk).await.is_err() {
                     tracing::error!("STT channel closed");
                     break;
                 }
             }
-            Some((segments, speaker)) = result_rx.recv() => {
-                for segment in &segments {
-                    if let Err(e) = writer.write_segment(segment, speaker.as_deref()) {
-                        tracing::error!("output error: {e}");
-                    }
-                }
+            Some((context, mut turns)) = result_rx.recv() => {
+                dedup.dedup_turns(&context, &mut turns);
+                corrector.correct_turns(&mut turns);
+                redactor.redact_turns(&mut turns);
+                write_outputs(&mut sinks, commands.process(&context, turns));
 
-                // Auto-commit if configured
-                if let Err(e) = crate::vcs::auto_commit(&config) {
-                    tracing::debug!("auto-commit skipped: {e}");
-                }
+                // Once every segment of the finished day is written,
+                // summarize it and apply the retention policy
+                let job = sinks.take_finished_day().map_or(Maintenance::Commit, |finished| {
+                    Maintenance::DayFinished {
+                        finished,
+                        today: calendar.today(),
+                    }
+                });
+                let _ = maintenance_tx.send(job);
+            }
+            _ = idle.tick() => {
+                write_outputs(&mut sinks, commands.release_idle());
             }
             _ = &mut shutdown_rx => {
                 tracing::info!("shutting down gracefully");
@@ -147,10 +199,13 @@ pub async fn run_recording(config: Config) -> Result<()> {
         }
     }
 
-    // Shutdown: stop capture and cancel UDP server
+    // Shutdown: stop capture, and cancel UDP server and control socket
     if let Some(cancel_tx) = cancel_tx {
         let _ = cancel_tx.send(true);
     }
+    if let Some(control) = control {
+        control.abort();
+    }
 
     // Drop capture to close the audio channel, which causes the audio pipeline
     // thread to flush its accumulator and exit.
@@ -168,22 +223,182 @@ pub async fn run_recording(config: Config) -> Result<()> {
     drop(stt_tx);
 
     // Drain all remaining transcription results.
-    while let Some((segments, speaker)) = result_rx.recv().await {
-        for segment in &segments {
-            if let Err(e) = writer.write_segment(segment, speaker.as_deref()) {
-                tracing::error!("output error: {e}");
-            }
-        }
+    while let Some((context, mut turns)) = result_rx.recv().await {
+        dedup.dedup_turns(&context, &mut turns);
+        corrector.correct_turns(&mut turns);
+        redactor.redact_turns(&mut turns);
+        write_outputs(&mut sinks, commands.process(&context, turns));
     }
+    write_outputs(&mut sinks, commands.finish());
+    sinks.shutdown();
 
-    // Final commit and push
+    // Final commit and push, once pending maintenance is done
+    if let Some(finished) = sinks.take_finished_day() {
+        let today = calendar.today();
+        let _ = maintenance_tx.send(Maintenance::DayFinished { finished, today });
+    }
+    drop(maintenance_tx);
+    let history_rewritten = maintenance.await.unwrap_or_else(|e| {
+        tracing::error!("maintenance task failed: {e}");
+        false
+    });
     if let Err(e) = crate::vcs::auto_commit(&config) {
         tracing::debug!("final commit: {e}");
     }
-    if let Err(e) = crate::vcs::auto_push(&config) {
+    if history_rewritten {
+        tracing::warn!(
+            "not pushing: pruning rewrote the history of the output repository; \
+             check it and force push"
+        );
+    } else if let Err(e) = crate::vcs::auto_push(&config) {
         tracing::debug!("final push: {e}");
     }
 
     tracing::info!("recording stopped");
     Ok(())
 }
+
+/// Work on the output directory handed off by the recording loop.
+enum Maintenance {
+    /// Commit what was written.
+    Commit,
+    /// Every segment of `finished` is written; summarize it, prune as of
+    /// `today` and commit.
+    DayFinished {
+        finished: NaiveDate,
+        today: NaiveDate,
+    },
+}
+
+/// Run maintenance in order on a blocking task, which is the only one to
+/// commit while recording: summaries can take minutes, and pruning archives
+/// and may rewrite history.  Queued commits are made as one.  The task ends
+/// when the sender is dropped, returning whether history was rewritten.
+fn spawn_maintenance(config: Config) -> (std::sync::mpsc::Sender<Maintenance>, JoinHandle<bool>) {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let task = tokio::task::spawn_blocking(move || {
+        let mut history_rewritten = false;
+        while let Ok(job) = rx.recv() {
+            for job in std::iter::once(job).chain(rx.try_iter()) {
+                let Maintenance::DayFinished { finished, today } = job else {
+                    continue;
+                };
+                if let Err(e) = crate::summary::auto_summarize(&config, finished) {
+                    tracing::warn!("summarizing {finished} failed: {e}");
+                }
+                match crate::retention::auto_prune(&config, today) {
+                    Ok(rewritten) => history_rewritten |= rewritten,
+                    Err(e) => tracing::warn!("pruning failed: {e}"),
+                }
+            }
+            if let Err(e) = crate::vcs::auto_commit(&config) {
+                tracing::debug!("auto-commit skipped: {e}");
+            }
+        }
+        history_rewritten
+    });
+    (tx, task)
+}
+
+/// Write what the voice command stage passed on, in order.
+fn write_outputs(sinks: &mut Sinks, outputs: Vec<Output>) {
+    for output in outputs {
+        match output {
+            Output::Turns(context, turns) => sinks.write_turns(&turns, &context),
+            Output::Marker(marker, turn) => sinks.write_marker(&marker, &turn),
+        }
+    }
+}
+
+/// Attribute each transcribed segment to a speaker using the slice of the
+/// chunk's audio it covers, then merge adjacent segments from the same speaker
+/// into turns.
+///
+/// Segments from non-enrolled voices are dropped or redacted according to
+/// `speaker.unknown_policy`; redacted text is discarded here and never reaches
+/// any writer.  A segment that can't be identified is treated the same way,
+/// so a failure never lets through words the policy would hold back.
+/// Without a speaker identifier the segments are passed through
+/// unattributed.
+fn attribute_segments<F>(
+    identify: Option<F>,
+    unknown_policy: UnknownPolicy,
+    chunk: &AudioChunk,
+    segments: Vec<TranscriptionSegment>,
+) -> Vec<AttributedSegment>
+where
+    F: FnMut(&[f32], f32, f32) -> Result<Option<SpeakerMatch>>,
+{
+    let Some(mut identify) = identify else {
+        return segments
+            .into_iter()
+            .map(AttributedSegment::unattributed)
+            .collect();
+    };
+
+    let mut attributed = Vec::with_capacity(segments.len());
+    for segment in segments {
+        let offset_secs = (segment.timestamp - chunk.timestamp).num_milliseconds() as f32 / 1000.0;
+        match identify(&chunk.samples_f32, offset_secs, segment.duration_secs) {
+            Ok(Some(m)) if m.redact => attributed.push(AttributedSegment::redacted(segment)),
+            Ok(Some(m)) => attributed.push(AttributedSegment {
+                segment,
+                speaker: m.name,
+                speaker_confidence: Some(m.confidence),
+                redacted: false,
+            }),
+            Ok(None) => {} // unknown_policy: drop suppressed this segment
+            Err(e) => {
+                tracing::warn!("speaker identification error: {e}");
+                match unknown_policy {
+                    UnknownPolicy::Redact => attributed.push(AttributedSegment::redacted(segment)),
+                    UnknownPolicy::Drop => {}
+                    UnknownPolicy::Keep => {
+                        attributed.push(AttributedSegment::unattributed(segment));
+                    }
+                }
+            }
+        }
+    }
+
+    merge_turns(attributed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::HooverError;
+    use chrono::Utc;
+
+    #[test]
+    fn failed_identification_follows_the_unknown_policy() {
+        let chunk = AudioChunk {
+            samples_f32: Vec::new(),
+            samples_i16: Vec::new(),
+            timestamp: Utc::now(),
+            duration_secs: 10.0,
+            source: "microphone",
+        };
+        let segment = TranscriptionSegment {
+            text: "secret words".to_string(),
+            timestamp: chunk.timestamp,
+            duration_secs: 2.0,
+            confidence: None,
+        };
+        let failing = |_: &[f32], _: f32, _: f32| -> Result<Option<SpeakerMatch>> {
+            Err(HooverError::Speaker("empty slice".to_string()))
+        };
+        let attribute =
+            |policy| attribute_segments(Some(failing), policy, &chunk, vec![segment.clone()]);
+
+        let redacted = attribute(UnknownPolicy::Redact);
+        assert_eq!(redacted.len(), 1);
+        assert!(redacted[0].redacted);
+        assert!(!redacted[0].segment.text.contains("secret"));
+        assert!(attribute(UnknownPolicy::Drop).is_empty());
+        assert_eq!(
+            attribute(UnknownPolicy::Keep)[0].segment.text,
+            "secret words"
+        );
+    }
+}
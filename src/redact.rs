@@ -0,0 +1,398 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::PathBuf;
+
+use regex::{Captures, Regex};
+
+use crate::config::{Config, PiiDetector, RedactionConfig};
+use crate::error::{HooverError, Result};
+use crate::output::calendar::Calendar;
+use crate::speaker::AttributedSegment;
+use crate::vault::replace_file;
+
+/// File in the output directory holding the number of redactions per day
+/// and kind.
+pub const AUDIT_FILE: &str = "redactions.json";
+
+/// Redactions per day (`YYYY-MM-DD`) and kind, as kept in [`AUDIT_FILE`].
+pub type AuditCounts = BTreeMap<String, BTreeMap<String, usize>>;
+
+/// One pattern and the placeholder that replaces its matches.
+struct Detector {
+    /// Key in the audit counts.
+    kind: String,
+    placeholder: String,
+    regex: Regex,
+    /// Confirms a match, for patterns that also match other numbers.
+    check: fn(&str) -> bool,
+}
+
+/// Replaces personal data in transcribed text with typed placeholders such
+/// as `[EMAIL]`, before any output sink sees it.
+pub struct Redactor {
+    /// Applied in order, so earlier detectors claim text first.
+    detectors: Vec<Detector>,
+    audit_file: PathBuf,
+    calendar: Calendar,
+}
+
+impl Redactor {
+    /// Build the redactor configured in `redaction`; it does nothing unless
+    /// `redaction.enabled` is set.
+    pub fn new(config: &Config) -> Result<Self> {
+        let detectors = if config.redaction.enabled {
+            detectors(&config.redaction)?
+        } else {
+            Vec::new()
+        };
+        Ok(Self {
+            detectors,
+            audit_file: Config::expand_path(&config.output.directory).join(AUDIT_FILE),
+            calendar: Calendar::new(&config.output)?,
+        })
+    }
+
+    /// `text` with every match replaced, and the number of matches per kind.
+    #[must_use]
+    pub fn redact(&self, text: &str) -> (String, BTreeMap<String, usize>) {
+        let mut text = text.to_string();
+        let mut counts = BTreeMap::new();
+        for detector in &self.detectors {
+            let mut found = 0;
+            let replaced = detector.regex.replace_all(&text, |caps: &Captures<'_>| {
+                let matched = &caps[0];
+                if (detector.check)(matched) {
+                    found += 1;
+                    detector.placeholder.clone()
+                } else {
+                    matched.to_string()
+                }
+            });
+            if found > 0 {
+                text = replaced.into_owned();
+                *counts.entry(detector.kind.clone()).or_default() += found;
+            }
+        }
+        (text, counts)
+    }
+
+    /// Redact the text of every turn in place and add the counts to the
+    /// audit file.  Turns already replaced by a placeholder are skipped.
+    pub fn redact_turns(&self, turns: &mut [AttributedSegment]) {
+        if self.detectors.is_empty() {
+            return;
+        }
+
+        let mut audit = AuditCounts::new();
+        for turn in turns.iter_mut().filter(|turn| !turn.redacted) {
+            let (text, counts) = self.redact(&turn.segment.text);
+            if counts.is_empty() {
+                continue;
+            }
+            turn.segment.text = text;
+            let day = self.calendar.day_of(turn.segment.timestamp);
+            let day_counts = audit.entry(day.format("%Y-%m-%d").to_string()).or_default();
+            for (kind, count) in counts {
+                *day_counts.entry(kind).or_default() += count;
+            }
+        }
+
+        if !audit.is_empty()
+            && let Err(e) = self.record(&audit)
+        {
+            tracing::warn!("failed to update {}: {e}", self.audit_file.display());
+        }
+    }
+
+    fn record(&self, counts: &AuditCounts) -> Result<()> {
+        let mut audit = read_audit(&self.audit_file)?;
+        for (day, kinds) in counts {
+            let day_counts = audit.entry(day.clone()).or_default();
+            for (kind, count) in kinds {
+                *day_counts.entry(kind.clone()).or_default() += count;
+            }
+        }
+
+        if let Some(parent) = self.audit_file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut data = serde_json::to_string_pretty(&audit)
+            .map_err(|e| HooverError::Output(format!("failed to serialize audit counts: {e}")))?;
+        data.push('\n');
+        // Replaced atomically, so a crash never leaves invalid JSON.
+        replace_file(&self.audit_file, data.as_bytes())
+    }
+}
+
+/// Read the redaction counts kept in `path`; a missing file has none.
+pub fn read_audit(path: &std::path::Path) -> Result<AuditCounts> {
+    match fs::read_to_string(path) {
+        Ok(data) => serde_json::from_str(&data).map_err(|e| {
+            HooverError::Output(format!("invalid audit file {}: {e}", path.display()))
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(AuditCounts::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn detectors(config: &RedactionConfig) -> Result<Vec<Detector>> {
+    let compile = |pattern: &str| {
+        Regex::new(pattern)
+            .map_err(|e| HooverError::Config(format!("invalid redaction pattern {pattern}: {e}")))
+    };
+    let mut detectors = Vec::new();
+
+    // Email first, since addresses may contain digits; IBANs and cards before
+    // phone numbers, whose digit groups they contain.
+    for kind in [
+        PiiDetector::Email,
+        PiiDetector::Iban,
+        PiiDetector::Card,
+        PiiDetector::Ip,
+        PiiDetector::Phone,
+    ] {
+        if !config.detectors.contains(&kind) {
+            continue;
+        }
+        let (name, pattern, check): (&str, &str, fn(&str) -> bool) = match kind {
+            PiiDetector::Email => (
+                "email",
+                r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}",
+                |_| true,
+            ),
+            PiiDetector::Iban => (
+                "iban",
+                r"\b[A-Z]{2}\d{2}(?: ?[A-Z0-9]{4}){2,7}(?: ?[A-Z0-9]{1,3})?\b",
+                is_iban,
+            ),
+            PiiDetector::Card => ("card", r"\b\d(?:[ -]?\d){12,18}\b", passes_luhn),
+            PiiDetector::Ip => (
+                "ip",
+                r"(?i)\b(?:\d{1,3}\.){3,}\d{1,3}\b|\b[0-9a-f]{0,4}(?::[0-9a-f]{0,4}){2,7}",
+                is_ip,
+            ),
+            PiiDetector::Phone => (
+                "phone",
+                concat!(
+                    r"\+\d{1,3}(?:[\s.-]?\(\d{1,4}\))?(?:[\s.-]?\d{1,4}){2,6}\b",
+                    r"|\(\d{2,4}\)[\s.-]?\d{3,4}(?:[\s.-]?\d{2,4}){1,2}\b",
+                    r"|\b\d{2,4}(?:[.-]\d{2,4}){2,4}\b",
+                ),
+                is_phone,
+            ),
+        };
+        detectors.push(Detector {
+            kind: name.to_string(),
+            placeholder: format!("[{}]", name.to_uppercase()),
+            regex: compile(pattern)?,
+            check,
+        });
+    }
+
+    for rule in &config.rules {
+        detectors.push(Detector {
+            kind: rule.name.clone(),
+            placeholder: format!("[{}]", rule.name.to_uppercase()),
+            regex: compile(&rule.pattern)?,
+            check: |_| true,
+        });
+    }
+
+    let mut people: Vec<&str> = config
+        .people
+        .iter()
+        .map(|name| name.trim())
+        .filter(|name| !name.is_empty())
+        .collect();
+    if !people.is_empty() {
+        // Longest first, so "Alice Smith" wins over "Alice".
+        people.sort_by_key(|name| std::cmp::Reverse(name.len()));
+        let names: Vec<String> = people.iter().map(|name| regex::escape(name)).collect();
+        detectors.push(Detector {
+            kind: "person".to_string(),
+            placeholder: "[PERSON]".to_string(),
+            regex: compile(&format!(r"(?i)\b(?:{})\b", names.join("|")))?,
+            check: |_| true,
+        });
+    }
+
+    Ok(detectors)
+}
+
+fn digits(text: &str) -> Vec<u32> {
+    text.chars().filter_map(|c| c.to_digit(10)).collect()
+}
+
+fn passes_luhn(text: &str) -> bool {
+    let digits = digits(text);
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| match (i % 2, d * 2) {
+            (0, _) => d,
+            (_, doubled) if doubled > 9 => doubled - 9,
+            (_, doubled) => doubled,
+        })
+        .sum();
+    (13..=19).contains(&digits.len()) && sum.is_multiple_of(10)
+}
+
+/// The ISO 13616 check: with the first four characters moved to the end and
+/// letters counted as 10 to 35, the number is 1 modulo 97.
+fn is_iban(text: &str) -> bool {
+    let compact: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+    if !(15..=34).contains(&compact.len()) {
+        return false;
+    }
+    let mut remainder = 0u32;
+    for c in compact[4..].iter().chain(&compact[..4]) {
+        let Some(value) = c.to_digit(36) else {
+            return false;
+        };
+        remainder = if value < 10 {
+            (remainder * 10 + value) % 97
+        } else {
+            (remainder * 100 + value) % 97
+        };
+    }
+    remainder == 1
+}
+
+fn is_ip(text: &str) -> bool {
+    text.parse::<Ipv4Addr>().is_ok() || text.parse::<Ipv6Addr>().is_ok()
+}
+
+/// Whether `text` is shaped like a phone number: 7 to 15 digits with a
+/// leading `+` or an area code in parentheses, or else in groups split by a
+/// single kind of separator that don't read as a date or a number with
+/// thousands separators.
+fn is_phone(text: &str) -> bool {
+    if !(7..=15).contains(&digits(text).len()) {
+        return false;
+    }
+    if text.starts_with(['+', '(']) {
+        return true;
+    }
+    let mut separators = text.chars().filter(|c| !c.is_ascii_digit());
+    let first = separators.next();
+    if !separators.all(|c| Some(c) == first) {
+        return false;
+    }
+    let groups: Vec<usize> = text.split(['.', '-']).map(str::len).collect();
+    let is_date = matches!(groups.as_slice(), [1 | 2, 1 | 2, 4] | [4, 1 | 2, 1 | 2]);
+    let is_grouped_number = groups[1..].iter().all(|&len| len == 3);
+    !is_date && !is_grouped_number
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::Utc;
+
+    use crate::stt::TranscriptionSegment;
+
+    fn redactor(dir: &std::path::Path, extra: &str) -> Redactor {
+        let yaml = format!(
+            "output:\n  directory: {}\nredaction:\n  enabled: true\n{extra}",
+            dir.display()
+        );
+        let config: Config = serde_yaml_ng::from_str(&yaml).unwrap_or_else(|e| panic!("{e}"));
+        Redactor::new(&config).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    #[test]
+    fn built_in_detectors_use_typed_placeholders() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let redactor = redactor(dir.path(), "");
+
+        let (text, counts) = redactor.redact(
+            "Mail jane.doe@example.com or call +1 555-123-4567, \
+             card 4111 1111 1111 1111, IBAN DE89 3704 0044 0532 0130 00, \
+             server 192.168.1.20 or 2001:db8::1.",
+        );
+        assert_eq!(
+            text,
+            "Mail [EMAIL] or call [PHONE], card [CARD], IBAN [IBAN], \
+             server [IP] or [IP]."
+        );
+        for kind in ["email", "phone", "card", "iban"] {
+            assert_eq!(counts.get(kind), Some(&1), "{kind}");
+        }
+        assert_eq!(counts.get("ip"), Some(&2));
+
+        let (text, counts) =
+            redactor.redact("Ring +44 (20) 7946 0958, (555) 123-4567 or 555.123.4567.");
+        assert_eq!(text, "Ring [PHONE], [PHONE] or [PHONE].");
+        assert_eq!(counts.get("phone"), Some(&3));
+    }
+
+    #[test]
+    fn checks_reject_ordinary_numbers() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let redactor = redactor(dir.path(), "");
+
+        let text = "Meet at 10:30:45 on 2026-03-02, version 1.2.3.4.5, about 300 people \
+                    and card 4111 1111 1111 1112.";
+        let (redacted, counts) = redactor.redact(text);
+        assert_eq!(redacted, text);
+        assert!(counts.is_empty());
+
+        let text = "On 17.10.2026 and 17/10/2026 we paid 1.250.000 or 12.250.000, \
+                    in 2019 2020 we spent 500, and 2019-2020 was the same.";
+        let (redacted, counts) = redactor.redact(text);
+        assert_eq!(redacted, text);
+        assert!(counts.is_empty());
+        assert!(!passes_luhn("4111 1111 1111 1112"));
+        assert!(!is_iban("DE89 3704 0044 0532 0130 01"));
+        assert!(!is_ip("999.1.1.1"));
+    }
+
+    #[test]
+    fn rules_and_people_are_replaced() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let redactor = redactor(
+            dir.path(),
+            "  detectors: []\n  rules:\n    - name: ticket\n      pattern: 'TICKET-\\d+'\n  people: [Alice, Alice Smith]\n",
+        );
+
+        let (text, counts) =
+            redactor.redact("alice smith filed TICKET-42 with Alice's team, not Malice.");
+        assert_eq!(
+            text,
+            "[PERSON] filed [TICKET] with [PERSON]'s team, not Malice."
+        );
+        assert_eq!(counts.get("person"), Some(&2));
+        assert_eq!(counts.get("ticket"), Some(&1));
+    }
+
+    #[test]
+    fn turns_are_redacted_and_counted_per_day() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let redactor = redactor(dir.path(), "");
+        let turn = |text: &str| {
+            AttributedSegment::unattributed(TranscriptionSegment {
+                text: text.to_string(),
+                timestamp: Utc::now(),
+                duration_secs: 1.0,
+                confidence: None,
+            })
+        };
+
+        let mut turns = vec![turn("write to bob@example.org"), turn("nothing here")];
+        redactor.redact_turns(&mut turns);
+        redactor.redact_turns(&mut [turn("or bob@example.net")]);
+        assert_eq!(turns[0].segment.text, "write to [EMAIL]");
+        assert_eq!(turns[1].segment.text, "nothing here");
+
+        let audit = read_audit(&dir.path().join(AUDIT_FILE)).unwrap_or_else(|e| panic!("{e}"));
+        let today = Calendar::default().today().format("%Y-%m-%d").to_string();
+        assert_eq!(audit[&today]["email"], 2);
+
+        let disabled: Config = serde_yaml_ng::from_str("{}").unwrap_or_else(|e| panic!("{e}"));
+        let disabled = Redactor::new(&disabled).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(disabled.redact("bob@example.org").0, "bob@example.org");
+    }
+}
@@ -0,0 +1,685 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::path::{Component, Path, PathBuf};
+
+use chrono::NaiveDate;
+
+use crate::config::{Config, RetentionAction};
+use crate::error::{HooverError, Result};
+use crate::index::SearchIndex;
+use crate::output::jsonl;
+use crate::output::layout::Layout;
+use crate::output::route::Routes;
+use crate::summary;
+use crate::vault::{self, Vault};
+use crate::vcs::git;
+
+/// What [`prune`] did, or would do on a dry run.
+#[derive(Debug, Default)]
+pub struct PruneReport {
+    /// Days reduced to their summary.
+    pub summarized: Vec<NaiveDate>,
+    /// Days old enough to be summarized that have no summary yet.
+    pub unsummarized: Vec<NaiveDate>,
+    /// Days deleted, moved or archived according to `retention.action`.
+    pub expired: Vec<NaiveDate>,
+    /// Archives written or extended.
+    pub archives: Vec<PathBuf>,
+    /// Commits rewritten with `retention.rewrite_history`.
+    pub rewritten_commits: usize,
+}
+
+impl PruneReport {
+    /// Whether nothing was (or would be) changed.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.summarized.is_empty() && self.expired.is_empty()
+    }
+}
+
+/// A day's files in one of the directories daily files are written to.
+///
+/// Paths are logical: an encrypted file is stored under the same name with
+/// [`vault::ENCRYPTED_SUFFIX`].
+struct Day {
+    dir: PathBuf,
+    date: NaiveDate,
+    markdown: PathBuf,
+    /// The day's JSONL log, when there is one.
+    log: Option<PathBuf>,
+    /// The day's summary sidecar, when there is one.
+    sidecar: Option<PathBuf>,
+    /// Every file on disk holding the day, plaintext or encrypted.
+    files: Vec<PathBuf>,
+}
+
+impl Day {
+    fn new(layout: &Layout, vault: &Vault, dir: &Path, date: NaiveDate, markdown: PathBuf) -> Self {
+        let log = jsonl::log_file(layout, dir, date);
+        let log = vault.stored_path(&log).map(|_| log);
+        let sidecar = summary::sidecar_file(layout, dir, date);
+        let sidecar = vault.stored_path(&sidecar).map(|_| sidecar);
+        let files = std::iter::once(&markdown)
+            .chain(&log)
+            .chain(&sidecar)
+            .flat_map(|file| [file.clone(), vault::encrypted_path(file)])
+            .filter(|file| file.exists())
+            .collect();
+        Self {
+            dir: dir.to_path_buf(),
+            date,
+            markdown,
+            log,
+            sidecar,
+            files,
+        }
+    }
+
+    fn logical_files(&self) -> impl Iterator<Item = &Path> {
+        std::iter::once(self.markdown.as_path())
+            .chain(self.log.as_deref())
+            .chain(self.sidecar.as_deref())
+    }
+}
+
+/// Check that `config` gives `prune` something it may do.
+fn check_prunable(config: &Config) -> Result<()> {
+    let retention = &config.retention;
+    if retention.keep_days.is_none() && retention.summaries_after_days.is_none() {
+        return Err(HooverError::Config(
+            "set retention.keep_days or retention.summaries_after_days to prune".to_string(),
+        ));
+    }
+    if config.output.layout.daily_note.is_some() {
+        return Err(HooverError::Config(
+            "daily notes are shared with other tools and can't be pruned".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Refuse to rewrite history while archived or moved days would be committed
+/// to the output repository, where the rewrite couldn't purge them.
+fn check_archive_outside_repo(config: &Config, routes: &Routes) -> Result<()> {
+    let retention = &config.retention;
+    if !config.vcs.enabled
+        || !retention.rewrite_history
+        || retention.action == RetentionAction::Delete
+    {
+        return Ok(());
+    }
+    let output_dir = lexical(&Config::expand_path(&config.output.directory));
+    for dir in routes.all_directories() {
+        let archive = lexical(&resolve_archive_dir(dir, &retention.archive_dir));
+        if archive.starts_with(&output_dir) {
+            return Err(HooverError::Config(format!(
+                "retention.archive_dir resolves to {}, inside the output repository, so \
+                 rewrite_history can't purge what is pruned; move it outside or use \
+                 action: delete",
+                archive.display()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// `path` with `.` and `..` components resolved without touching the file
+/// system, as the archive directory may not exist yet.
+fn lexical(path: &Path) -> PathBuf {
+    let mut resolved = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            component => resolved.push(component),
+        }
+    }
+    resolved
+}
+
+/// Apply `retention` to the daily files in every output directory, as of
+/// `today`.
+///
+/// Days older than `keep_days` are deleted, moved or archived; days older
+/// than `summaries_after_days` are cut down to their summary.  Pruned days
+/// are dropped from the search index, and with VCS enabled the result is
+/// committed (and history rewritten when `rewrite_history` is set).  With
+/// `dry_run` nothing is changed.
+pub fn prune(config: &Config, today: NaiveDate, dry_run: bool) -> Result<PruneReport> {
+    check_prunable(config)?;
+    let retention = &config.retention;
+
+    let layout = Layout::new(&config.output.layout)?;
+    let routes = Routes::new(&config.output)?;
+    check_archive_outside_repo(config, &routes)?;
+    let vault = Vault::new(&config.output)?;
+    let older_than = |days: Option<u32>, date: NaiveDate| {
+        days.is_some_and(|days| (today - date).num_days() > i64::from(days))
+    };
+
+    let mut report = PruneReport::default();
+    let mut expired = Vec::new();
+    let mut summarized = Vec::new();
+    for dir in routes.all_directories() {
+        for (date, path) in layout.day_files(dir)? {
+            let day = Day::new(&layout, &vault, dir, date, path);
+            if older_than(retention.keep_days, date) {
+                expired.push(day);
+                continue;
+            }
+            if !older_than(retention.summaries_after_days, date) {
+                continue;
+            }
+            let content = vault.read(&day.markdown)?.unwrap_or_default();
+            let Some(end) = layout.transcript_start(&content, date) else {
+                continue;
+            };
+            // With the summary in a sidecar, the day is cut to its header.
+            if day.sidecar.is_none()
+                && content[layout.body_start(&content, date)..end]
+                    .trim()
+                    .is_empty()
+            {
+                report.unsummarized.push(date);
+                continue;
+            }
+            let summary = format!("{}\n", content[..end].trim_end());
+            summarized.push((day, summary));
+        }
+    }
+
+    report.summarized = summarized.iter().map(|(day, _)| day.date).collect();
+    report.expired = expired.iter().map(|day| day.date).collect();
+    report.archives = match retention.action {
+        RetentionAction::Archive => archive_groups(&expired, &retention.archive_dir)
+            .into_keys()
+            .map(|(archive, _)| archive)
+            .collect(),
+        RetentionAction::Move | RetentionAction::Delete => Vec::new(),
+    };
+    for dates in [
+        &mut report.summarized,
+        &mut report.unsummarized,
+        &mut report.expired,
+    ] {
+        dates.sort();
+        dates.dedup();
+    }
+    report.archives.dedup();
+    if dry_run || report.is_empty() {
+        return Ok(report);
+    }
+
+    for (day, summary) in &summarized {
+        vault.write(&day.markdown, summary)?;
+        if let Some(log) = &day.log {
+            for file in [log.clone(), vault::encrypted_path(log)] {
+                if file.exists() {
+                    fs::remove_file(file)?;
+                }
+            }
+        }
+    }
+    expire(&expired, retention.action, &retention.archive_dir)?;
+    tracing::info!(
+        "pruned {} days, summarized {}",
+        report.expired.len(),
+        report.summarized.len()
+    );
+
+    if let Some(path) = config.output.index_path()
+        && path.exists()
+    {
+        let dates: Vec<String> = report
+            .expired
+            .iter()
+            .chain(&report.summarized)
+            .map(|date| date.format("%Y-%m-%d").to_string())
+            .collect();
+        SearchIndex::open(&path)?.remove_days(&dates)?;
+    }
+
+    let output_dir = Config::expand_path(&config.output.directory);
+    if config.vcs.enabled && output_dir.join(".git").exists() {
+        git::add_and_commit(&output_dir, &commit_message(&report, retention.action))?;
+        if retention.rewrite_history {
+            let changes = history_changes(&output_dir, &vault, &expired, &summarized)?;
+            report.rewritten_commits = git::rewrite_paths(&output_dir, &changes)?;
+        }
+    }
+
+    Ok(report)
+}
+
+/// Prune at day rollover when `retention.auto` is on.  Returns whether the
+/// history of the output repository was rewritten.
+pub fn auto_prune(config: &Config, today: NaiveDate) -> Result<bool> {
+    if !config.retention.auto {
+        return Ok(false);
+    }
+
+    let report = prune(config, today, false)?;
+    if report.rewritten_commits > 0 {
+        tracing::warn!(
+            "rewrote {} commits of the output repository; it needs a force push",
+            report.rewritten_commits
+        );
+    }
+    Ok(report.rewritten_commits > 0)
+}
+
+/// Expired days grouped by the monthly archive they go to and the directory
+/// they come from.
+fn archive_groups<'a>(
+    days: &'a [Day],
+    archive_dir: &str,
+) -> BTreeMap<(PathBuf, &'a Path), Vec<&'a Day>> {
+    let mut groups: BTreeMap<_, Vec<&Day>> = BTreeMap::new();
+    for day in days {
+        let archive = resolve_archive_dir(&day.dir, archive_dir)
+            .join(format!("{}.tar.zst", day.date.format("%Y-%m")));
+        groups
+            .entry((archive, day.dir.as_path()))
+            .or_default()
+            .push(day);
+    }
+    groups
+}
+
+fn resolve_archive_dir(dir: &Path, archive_dir: &str) -> PathBuf {
+    dir.join(Config::expand_path(archive_dir))
+}
+
+fn expire(days: &[Day], action: RetentionAction, archive_dir: &str) -> Result<()> {
+    match action {
+        RetentionAction::Archive => {
+            for ((archive, dir), days) in archive_groups(days, archive_dir) {
+                let members: Vec<&Path> = days
+                    .iter()
+                    .flat_map(|day| &day.files)
+                    .filter_map(|file| file.strip_prefix(dir).ok())
+                    .collect();
+                add_to_archive(dir, &archive, &members)?;
+            }
+        }
+        RetentionAction::Move => {
+            for day in days {
+                let target = resolve_archive_dir(&day.dir, archive_dir);
+                for file in &day.files {
+                    let relative = file.strip_prefix(&day.dir).unwrap_or(file);
+                    move_file(file, &target.join(relative))?;
+                }
+            }
+        }
+        RetentionAction::Delete => {}
+    }
+
+    for day in days {
+        for file in &day.files {
+            match fs::remove_file(file) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+            remove_empty_parents(file, &day.dir);
+        }
+    }
+    Ok(())
+}
+
+/// Add `members`, relative to `dir`, to the `tar.zst` archive at `archive`,
+/// keeping what it already holds.  The archive is replaced only once the new
+/// one is complete.
+fn add_to_archive(dir: &Path, archive: &Path, members: &[&Path]) -> Result<()> {
+    let parent = archive.parent().unwrap_or(dir);
+    fs::create_dir_all(parent)?;
+    let name = archive
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let partial = parent.join(format!(".{name}.partial"));
+
+    let written = write_archive(dir, archive, &partial, members);
+    if let Err(e) = written {
+        let _ = fs::remove_file(&partial);
+        return Err(HooverError::Output(format!(
+            "failed to write {}: {e}",
+            archive.display()
+        )));
+    }
+    fs::rename(&partial, archive)?;
+    tracing::info!(
+        "archived {} files into {}",
+        members.len(),
+        archive.display()
+    );
+    Ok(())
+}
+
+/// Write to `partial` what `archive`, if it exists, holds plus `members`,
+/// which replace entries of the same name.
+fn write_archive(
+    dir: &Path,
+    archive: &Path,
+    partial: &Path,
+    members: &[&Path],
+) -> std::io::Result<()> {
+    let mut builder = tar::Builder::new(zstd::Encoder::new(File::create(partial)?, 0)?);
+    if archive.exists() {
+        let mut existing = tar::Archive::new(zstd::Decoder::new(File::open(archive)?)?);
+        for entry in existing.entries()? {
+            let mut entry = entry?;
+            let path = entry.path()?.into_owned();
+            // Archives written by `tar -C dir .` name members `./file`.
+            let path = path.strip_prefix(".").unwrap_or(&path);
+            if members.contains(&path) {
+                continue;
+            }
+            let mut header = entry.header().clone();
+            builder.append_data(&mut header, path, &mut entry)?;
+        }
+    }
+    for member in members {
+        builder.append_path_with_name(dir.join(member), member)?;
+    }
+    builder.into_inner()?.finish()?.sync_all()
+}
+
+fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if fs::rename(from, to).is_err() {
+        // `rename` cannot cross file systems.
+        fs::copy(from, to)?;
+        fs::remove_file(from)?;
+    }
+    Ok(())
+}
+
+/// Remove directories left empty between `file` and `dir`, as nested layouts
+/// such as `%Y/%m/%Y-%m-%d.md` leave behind.
+fn remove_empty_parents(file: &Path, dir: &Path) {
+    let mut current = file.parent();
+    while let Some(parent) = current
+        && parent != dir
+        && parent.starts_with(dir)
+    {
+        if fs::remove_dir(parent).is_err() {
+            break;
+        }
+        current = parent.parent();
+    }
+}
+
+fn commit_message(report: &PruneReport, action: RetentionAction) -> String {
+    let verb = match action {
+        RetentionAction::Archive => "archive",
+        RetentionAction::Move => "move",
+        RetentionAction::Delete => "delete",
+    };
+    let mut parts = Vec::new();
+    if let (Some(first), Some(last)) = (report.expired.first(), report.expired.last()) {
+        parts.push(format!("{verb} {first}..{last}"));
+    }
+    if let (Some(first), Some(last)) = (report.summarized.first(), report.summarized.last()) {
+        parts.push(format!("summarize {first}..{last}"));
+    }
+    format!("prune transcripts: {}", parts.join(", "))
+}
+
+/// The history rewrite for pruned files inside the output repository:
+/// expired files and logs are removed, summarized days get their summary as
+/// just written.  Both the plaintext and the encrypted name are covered, so a
+/// summarized day keeps only the form it is stored in now.
+fn history_changes(
+    output_dir: &Path,
+    vault: &Vault,
+    expired: &[Day],
+    summarized: &[(Day, String)],
+) -> Result<Vec<(String, Option<Vec<u8>>)>> {
+    let relative = |file: &Path| {
+        let relative = file.strip_prefix(output_dir).ok()?;
+        let parts = relative
+            .components()
+            .map(|c| c.as_os_str().to_str())
+            .collect::<Option<Vec<_>>>()?;
+        Some(parts.join("/"))
+    };
+
+    let both = |file: &Path| [file.to_path_buf(), vault::encrypted_path(file)];
+
+    let mut changes = Vec::new();
+    for day in expired {
+        for file in day.logical_files().flat_map(both) {
+            changes.extend(relative(&file).map(|file| (file, None)));
+        }
+    }
+    for (day, _) in summarized {
+        let stored = vault.file_path(&day.markdown);
+        for file in both(&day.markdown) {
+            let content = if file == stored {
+                Some(fs::read(&file)?)
+            } else {
+                None
+            };
+            changes.extend(relative(&file).map(|file| (file, content)));
+        }
+        for file in day.log.as_deref().into_iter().flat_map(both) {
+            changes.extend(relative(&file).map(|file| (file, None)));
+        }
+    }
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::config::SinkConfig;
+    use crate::index::{DEFAULT_SEARCH_LIMIT, IndexEntry};
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 1, day).unwrap_or_default()
+    }
+
+    fn config(dir: &Path, yaml: &str) -> Config {
+        let mut config: Config = serde_yaml_ng::from_str(yaml).unwrap_or_else(|e| panic!("{e}"));
+        config.output.directory = dir.to_string_lossy().to_string();
+        config
+    }
+
+    fn write_day(dir: &Path, day: u32, summary: &str) {
+        let content =
+            format!("# Day {day}\n\n{summary}## 10:00\n\n**Alice:** transcript of day {day}\n\n");
+        fs::write(dir.join(format!("{}.md", date(day))), content).unwrap_or_else(|e| panic!("{e}"));
+        fs::write(dir.join(format!("{}.jsonl", date(day))), "{}\n")
+            .unwrap_or_else(|e| panic!("{e}"));
+    }
+
+    #[test]
+    fn expired_days_are_deleted_and_old_days_summarized() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let index_path = dir.path().join("index.sqlite");
+        let mut config = config(
+            dir.path(),
+            "retention: {keep_days: 20, action: delete, summaries_after_days: 5}",
+        );
+        config.output.sinks = Some(vec![
+            SinkConfig::Markdown,
+            SinkConfig::Sqlite {
+                path: Some(index_path.to_string_lossy().to_string()),
+            },
+        ]);
+        let out = dir.path();
+
+        write_day(out, 1, "## Summary\n\nPlanning.\n\n");
+        write_day(out, 20, "## Summary\n\nBudget.\n\n");
+        write_day(out, 21, "");
+        write_day(out, 22, "");
+        write_day(out, 30, "");
+        for day in [1, 22] {
+            fs::write(
+                out.join(format!("{}.summary.md", date(day))),
+                "## Summary\n",
+            )
+            .unwrap_or_else(|e| panic!("{e}"));
+        }
+
+        let mut index = SearchIndex::open(&index_path).unwrap_or_else(|e| panic!("{e}"));
+        let entries: Vec<IndexEntry> = [1, 20, 21, 30]
+            .into_iter()
+            .map(|day| IndexEntry {
+                date: date(day).to_string(),
+                time: None,
+                speaker: None,
+                text: "transcript".to_string(),
+                source: None,
+            })
+            .collect();
+        index.rebuild(&entries).unwrap_or_else(|e| panic!("{e}"));
+
+        let today = date(31);
+        let preview = prune(&config, today, true).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(preview.expired, [date(1)]);
+        assert_eq!(preview.summarized, [date(20), date(22)]);
+        assert_eq!(preview.unsummarized, [date(21)]);
+        assert!(out.join("2026-01-01.md").exists());
+
+        prune(&config, today, false).unwrap_or_else(|e| panic!("{e}"));
+        assert!(!out.join("2026-01-01.md").exists());
+        assert!(!out.join("2026-01-01.jsonl").exists());
+        assert!(!out.join("2026-01-01.summary.md").exists());
+
+        let summarized =
+            fs::read_to_string(out.join("2026-01-20.md")).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(summarized, "# Day 20\n\n## Summary\n\nBudget.\n");
+        assert!(!out.join("2026-01-20.jsonl").exists());
+        assert!(out.join("2026-01-21.jsonl").exists());
+        let summarized =
+            fs::read_to_string(out.join("2026-01-22.md")).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(summarized, "# Day 22\n");
+        assert!(out.join("2026-01-22.summary.md").exists());
+        assert!(out.join("2026-01-30.md").exists());
+
+        let hits = index
+            .search("transcript", None, None, DEFAULT_SEARCH_LIMIT)
+            .unwrap_or_else(|e| panic!("{e}"));
+        let mut dates: Vec<String> = hits.into_iter().map(|hit| hit.date).collect();
+        dates.sort();
+        assert_eq!(dates, ["2026-01-21", "2026-01-30"]);
+    }
+
+    #[test]
+    fn expired_days_are_moved_from_nested_layouts() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let mut config = config(dir.path(), "retention: {keep_days: 10, action: move}");
+        config.output.layout.file = "%Y/%m/%Y-%m-%d.md".to_string();
+        let month = dir.path().join("2026/01");
+        fs::create_dir_all(&month).unwrap_or_else(|e| panic!("{e}"));
+        write_day(&month, 2, "");
+
+        prune(&config, date(20), false).unwrap_or_else(|e| panic!("{e}"));
+        assert!(!dir.path().join("2026").exists());
+        let moved = dir.path().join("archive/2026/01");
+        assert!(moved.join("2026-01-02.md").exists());
+        assert!(moved.join("2026-01-02.jsonl").exists());
+    }
+
+    #[test]
+    fn expired_days_are_rolled_into_monthly_archives() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let config = config(dir.path(), "retention: {keep_days: 10}");
+        write_day(dir.path(), 1, "");
+
+        let report = prune(&config, date(20), false).unwrap_or_else(|e| panic!("{e}"));
+        let archive = dir.path().join("archive/2026-01.tar.zst");
+        assert_eq!(report.archives, std::slice::from_ref(&archive));
+        assert!(!dir.path().join("2026-01-01.md").exists());
+
+        // A later run adds to the month's archive.
+        write_day(dir.path(), 2, "");
+        prune(&config, date(20), false).unwrap_or_else(|e| panic!("{e}"));
+
+        let file = File::open(&archive).unwrap_or_else(|e| panic!("{e}"));
+        let decoder = zstd::Decoder::new(file).unwrap_or_else(|e| panic!("{e}"));
+        let listing: Vec<String> = tar::Archive::new(decoder)
+            .entries()
+            .unwrap_or_else(|e| panic!("{e}"))
+            .map(|entry| {
+                let entry = entry.unwrap_or_else(|e| panic!("{e}"));
+                let path = entry.path().unwrap_or_else(|e| panic!("{e}"));
+                path.to_string_lossy().into_owned()
+            })
+            .collect();
+        assert_eq!(listing.len(), 4);
+        for file in [
+            "2026-01-01.md",
+            "2026-01-01.jsonl",
+            "2026-01-02.md",
+            "2026-01-02.jsonl",
+        ] {
+            assert!(
+                listing.iter().any(|f| f == file),
+                "{file} missing from {listing:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn pruning_is_committed_and_history_rewritten() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let config = config(
+            dir.path(),
+            "vcs: {enabled: true}\nretention: {keep_days: 10, action: delete, rewrite_history: true}",
+        );
+        write_day(dir.path(), 1, "");
+        write_day(dir.path(), 15, "");
+        git::add_and_commit(dir.path(), "auto-commit").unwrap_or_else(|e| panic!("{e}"));
+
+        let report = prune(&config, date(20), false).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(report.rewritten_commits, 2);
+
+        let log = git::commit_log(dir.path(), 10).unwrap_or_else(|e| panic!("{e}"));
+        assert!(
+            log.iter()
+                .any(|entry| entry.contains("prune transcripts: delete 2026-01-01..2026-01-01"))
+        );
+        let repo = git2::Repository::open(dir.path()).unwrap_or_else(|e| panic!("{e}"));
+        let mut revwalk = repo.revwalk().unwrap_or_else(|e| panic!("{e}"));
+        revwalk.push_head().unwrap_or_else(|e| panic!("{e}"));
+        for oid in revwalk {
+            let tree = oid
+                .and_then(|oid| repo.find_commit(oid))
+                .and_then(|commit| commit.tree())
+                .unwrap_or_else(|e| panic!("{e}"));
+            assert!(tree.get_path(Path::new("2026-01-01.md")).is_err());
+            assert!(tree.get_path(Path::new("2026-01-15.md")).is_ok());
+        }
+    }
+
+    #[test]
+    fn history_is_not_rewritten_with_archives_in_the_repository() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let outside = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let yaml =
+            "vcs: {enabled: true}\nretention: {keep_days: 10, action: move, rewrite_history: true}";
+        let config = config(dir.path(), yaml);
+        assert!(prune(&config, date(20), true).is_err());
+
+        let mut config = config;
+        config.retention.archive_dir = "../archive".to_string();
+        assert!(prune(&config, date(20), true).is_ok());
+        config.retention.archive_dir = outside.path().to_string_lossy().to_string();
+        assert!(prune(&config, date(20), true).is_ok());
+    }
+
+    #[test]
+    fn pruning_needs_a_threshold() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let config = config(dir.path(), "{}");
+        assert!(prune(&config, date(20), true).is_err());
+    }
+}
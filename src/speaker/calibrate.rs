@@ -0,0 +1,424 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::audio::vad::SileroVad;
+use crate::config::Config;
+use crate::error::{HooverError, Result};
+use crate::vault::Vault;
+
+use super::enroll::{detect_speech_windows, resolve_speaker_model};
+use super::identify::load_all_profiles;
+use super::{extract_embedding, load_embedding_model, model_fingerprint};
+
+/// Column label for windows that match no profile above the threshold.
+const UNKNOWN_LABEL: &str = "unknown";
+
+/// Scores of one audio window against every enrolled profile.
+#[derive(Debug, Clone)]
+pub struct WindowScores {
+    /// Speaker the window is labelled as (may have no profile).
+    pub label: String,
+    /// Best-centroid similarity per profile, in `CalibrationReport::profiles` order.
+    pub scores: Vec<f32>,
+}
+
+/// Equal error rate and the threshold where it occurs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EqualErrorRate {
+    /// Average of the false accept and false reject rates at `threshold`.
+    pub rate: f32,
+    pub threshold: f32,
+}
+
+/// Result of scoring labelled audio against the enrolled profiles.
+#[derive(Debug, Clone)]
+pub struct CalibrationReport {
+    /// Enrolled profile names, sorted.
+    pub profiles: Vec<String>,
+    pub windows: Vec<WindowScores>,
+    /// Threshold used for the confusion matrix (`speaker.min_confidence`).
+    pub threshold: f32,
+}
+
+impl CalibrationReport {
+    /// Predicted speaker for a window: the best-scoring profile when it clears
+    /// the threshold, otherwise `unknown`.
+    fn predict(&self, window: &WindowScores) -> Option<usize> {
+        window
+            .scores
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .filter(|(_, score)| **score >= self.threshold)
+            .map(|(i, _)| i)
+    }
+
+    /// Labels seen in the test audio, sorted.
+    fn labels(&self) -> Vec<String> {
+        let mut labels: Vec<String> = self.windows.iter().map(|w| w.label.clone()).collect();
+        labels.sort();
+        labels.dedup();
+        labels
+    }
+
+    /// Confusion matrix: one row per label, one column per profile plus a
+    /// final `unknown` column.
+    #[must_use]
+    pub fn confusion_matrix(&self) -> Vec<(String, Vec<usize>)> {
+        self.labels()
+            .into_iter()
+            .map(|label| {
+                let mut row = vec![0; self.profiles.len() + 1];
+                for window in self.windows.iter().filter(|w| w.label == label) {
+                    let col = self.predict(window).unwrap_or(self.profiles.len());
+                    row[col] += 1;
+                }
+                (label, row)
+            })
+            .collect()
+    }
+
+    /// Genuine scores (windows of this speaker against their own profile) and
+    /// impostor scores (everyone else's windows against it) for one profile.
+    #[must_use]
+    pub fn profile_scores(&self, profile_idx: usize) -> (Vec<f32>, Vec<f32>) {
+        let name = &self.profiles[profile_idx];
+        let (genuine, impostor): (Vec<&WindowScores>, Vec<&WindowScores>) =
+            self.windows.iter().partition(|w| &w.label == name);
+        (
+            genuine.iter().map(|w| w.scores[profile_idx]).collect(),
+            impostor.iter().map(|w| w.scores[profile_idx]).collect(),
+        )
+    }
+
+    /// Equal error rate over all profiles combined.
+    #[must_use]
+    pub fn equal_error_rate(&self) -> Option<EqualErrorRate> {
+        let mut genuine = Vec::new();
+        let mut impostor = Vec::new();
+        for i in 0..self.profiles.len() {
+            let (g, imp) = self.profile_scores(i);
+            genuine.extend(g);
+            impostor.extend(imp);
+        }
+        equal_error_rate(&genuine, &impostor)
+    }
+}
+
+impl fmt::Display for CalibrationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut columns = self.profiles.clone();
+        columns.push(UNKNOWN_LABEL.to_string());
+        let matrix = self.confusion_matrix();
+        let label_width = matrix
+            .iter()
+            .map(|(label, _)| label.len())
+            .chain(std::iter::once("actual".len()))
+            .max()
+            .unwrap_or(0);
+
+        writeln!(
+            f,
+            "Confusion matrix at threshold {:.2} (rows: actual, columns: predicted)",
+            self.threshold
+        )?;
+        write!(f, "  {:label_width$}", "actual")?;
+        for column in &columns {
+            write!(f, "  {column:>8}")?;
+        }
+        writeln!(f)?;
+        for (label, row) in &matrix {
+            write!(f, "  {label:label_width$}")?;
+            for count in row {
+                write!(f, "  {count:>8}")?;
+            }
+            writeln!(f)?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "Score distributions")?;
+        for (i, name) in self.profiles.iter().enumerate() {
+            let (genuine, impostor) = self.profile_scores(i);
+            writeln!(f, "  {name}")?;
+            writeln!(f, "    genuine   {}", Distribution::of(&genuine))?;
+            writeln!(f, "    impostor  {}", Distribution::of(&impostor))?;
+        }
+
+        writeln!(f)?;
+        match self.equal_error_rate() {
+            Some(eer) => {
+                writeln!(
+                    f,
+                    "Equal error rate: {:.1}% at threshold {:.2}",
+                    eer.rate * 100.0,
+                    eer.threshold
+                )?;
+                writeln!(
+                    f,
+                    "Recommended speaker.min_confidence: {:.2}",
+                    eer.threshold
+                )
+            }
+            None => writeln!(
+                f,
+                "Equal error rate: not available (needs audio from at least one enrolled \
+                 speaker and one other speaker)"
+            ),
+        }
+    }
+}
+
+/// Summary statistics for a set of scores.
+struct Distribution {
+    count: usize,
+    min: f32,
+    mean: f32,
+    max: f32,
+}
+
+impl Distribution {
+    fn of(scores: &[f32]) -> Self {
+        let count = scores.len();
+        let min = scores.iter().copied().fold(f32::INFINITY, f32::min);
+        let max = scores.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let mean = scores.iter().sum::<f32>() / count.max(1) as f32;
+        Self {
+            count,
+            min,
+            mean,
+            max,
+        }
+    }
+}
+
+impl fmt::Display for Distribution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.count == 0 {
+            return write!(f, "n=0");
+        }
+        write!(
+            f,
+            "n={:<5} min {:.2}  mean {:.2}  max {:.2}",
+            self.count, self.min, self.mean, self.max
+        )
+    }
+}
+
+/// Find the threshold where the false accept rate (impostor scores at or
+/// above it) and false reject rate (genuine scores below it) are closest.
+///
+/// Returns `None` when either set of scores is empty.
+#[must_use]
+pub fn equal_error_rate(genuine: &[f32], impostor: &[f32]) -> Option<EqualErrorRate> {
+    if genuine.is_empty() || impostor.is_empty() {
+        return None;
+    }
+
+    let mut genuine = genuine.to_vec();
+    let mut impostor = impostor.to_vec();
+    genuine.sort_by(f32::total_cmp);
+    impostor.sort_by(f32::total_cmp);
+
+    let mut best: Option<(f32, EqualErrorRate)> = None;
+    for &threshold in genuine.iter().chain(&impostor) {
+        let rejected = genuine.partition_point(|&s| s < threshold);
+        let accepted = impostor.len() - impostor.partition_point(|&s| s < threshold);
+        let frr = rejected as f32 / genuine.len() as f32;
+        let far = accepted as f32 / impostor.len() as f32;
+        let gap = (far - frr).abs();
+        let candidate = EqualErrorRate {
+            rate: f32::midpoint(far, frr),
+            threshold,
+        };
+        // Prefer the smallest gap, then the lowest error rate.
+        if best.is_none_or(|(best_gap, best_eer)| (gap, candidate.rate) < (best_gap, best_eer.rate))
+        {
+            best = Some((gap, candidate));
+        }
+    }
+
+    best.map(|(_, eer)| eer)
+}
+
+/// Turn `--file NAME=PATH` arguments and an optional `DIR/NAME/*` tree into
+/// a list of labelled audio files.
+pub fn collect_labelled_files(
+    files: &[String],
+    dir: Option<&Path>,
+) -> Result<Vec<(String, PathBuf)>> {
+    let mut labelled = Vec::new();
+
+    for arg in files {
+        let (name, path) = arg.split_once('=').ok_or_else(|| {
+            HooverError::Speaker(format!("expected NAME=PATH for --file, got '{arg}'"))
+        })?;
+        labelled.push((name.to_string(), PathBuf::from(path)));
+    }
+
+    if let Some(dir) = dir {
+        let mut speakers: Vec<PathBuf> = fs::read_dir(dir)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<std::io::Result<_>>()?;
+        speakers.retain(|p| p.is_dir());
+        speakers.sort();
+
+        for speaker_dir in speakers {
+            let name = speaker_dir
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let mut paths: Vec<PathBuf> = fs::read_dir(&speaker_dir)?
+                .map(|entry| entry.map(|e| e.path()))
+                .collect::<std::io::Result<_>>()?;
+            paths.retain(|p| p.is_file());
+            paths.sort();
+            labelled.extend(paths.into_iter().map(|p| (name.clone(), p)));
+        }
+    }
+
+    if labelled.is_empty() {
+        return Err(HooverError::Speaker(
+            "no test audio given — use --file NAME=PATH or --dir DIR".to_string(),
+        ));
+    }
+
+    Ok(labelled)
+}
+
+/// Score every speech window of the labelled files against all enrolled
+/// profiles.
+pub fn run_speaker_test(
+    config: &Config,
+    inputs: &[(String, PathBuf)],
+) -> Result<CalibrationReport> {
+    let model_path = resolve_speaker_model(config.speaker.model_path.as_deref())?;
+    let mut session = load_embedding_model(&model_path, config.stt.gpu)?;
+    let model_hash = model_fingerprint(&model_path)?;
+    let profiles_dir = Config::expand_path(&config.speaker.profiles_dir);
+    let vault = Vault::for_profiles(&config.output)?;
+
+    let mut profiles = load_all_profiles(&profiles_dir, &vault, &model_hash)?;
+    if profiles.is_empty() {
+        return Err(HooverError::Speaker(
+            "no enrolled speakers to test against".to_string(),
+        ));
+    }
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut vad = SileroVad::new(&crate::audio::resolve_vad_model()?)?;
+    let mut windows = Vec::new();
+
+    for (label, path) in inputs {
+        let samples = crate::audio::file::load_audio_file(path)?;
+        let speech = detect_speech_windows(&mut vad, &samples);
+        tracing::info!(
+            "{}: {} speech window(s) labelled '{label}'",
+            path.display(),
+            speech.len()
+        );
+
+        for range in speech {
+            let embedding = extract_embedding(&mut session, &samples[range])?;
+            let scores = profiles
+                .iter()
+                .map(|p| p.best_centroid(&embedding).map_or(0.0, |(_, score)| score))
+                .collect();
+            windows.push(WindowScores {
+                label: label.clone(),
+                scores,
+            });
+        }
+    }
+
+    if windows.is_empty() {
+        return Err(HooverError::Speaker(
+            "no speech found in the test audio".to_string(),
+        ));
+    }
+
+    Ok(CalibrationReport {
+        profiles: profiles.into_iter().map(|p| p.name).collect(),
+        windows,
+        threshold: config.speaker.min_confidence,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(label: &str, scores: &[f32]) -> WindowScores {
+        WindowScores {
+            label: label.to_string(),
+            scores: scores.to_vec(),
+        }
+    }
+
+    #[test]
+    fn eer_of_separable_scores_is_zero() {
+        let eer = equal_error_rate(&[0.7, 0.8, 0.9], &[0.1, 0.2, 0.3])
+            .unwrap_or_else(|| panic!("no eer"));
+        assert!(eer.rate.abs() < f32::EPSILON);
+        assert!(eer.threshold > 0.3 && eer.threshold <= 0.7);
+    }
+
+    #[test]
+    fn eer_of_overlapping_scores() {
+        let eer = equal_error_rate(&[0.4, 0.6, 0.8, 0.9], &[0.1, 0.2, 0.5, 0.7])
+            .unwrap_or_else(|| panic!("no eer"));
+        assert!((eer.rate - 0.25).abs() < 1e-6);
+        assert!(equal_error_rate(&[], &[0.1]).is_none());
+    }
+
+    #[test]
+    fn confusion_matrix_counts_predictions() {
+        let report = CalibrationReport {
+            profiles: vec!["alice".to_string(), "bob".to_string()],
+            windows: vec![
+                window("alice", &[0.9, 0.2]),
+                window("alice", &[0.3, 0.6]),
+                window("bob", &[0.1, 0.8]),
+                window("carol", &[0.2, 0.3]),
+            ],
+            threshold: 0.5,
+        };
+
+        let matrix = report.confusion_matrix();
+        assert_eq!(
+            matrix,
+            vec![
+                ("alice".to_string(), vec![1, 1, 0]),
+                ("bob".to_string(), vec![0, 1, 0]),
+                ("carol".to_string(), vec![0, 0, 1]),
+            ]
+        );
+
+        let (genuine, impostor) = report.profile_scores(0);
+        assert_eq!(genuine, vec![0.9, 0.3]);
+        assert_eq!(impostor, vec![0.1, 0.2]);
+        assert!(
+            report
+                .to_string()
+                .contains("Recommended speaker.min_confidence")
+        );
+    }
+
+    #[test]
+    fn collects_files_from_arguments_and_directories() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        fs::create_dir(dir.path().join("bob")).unwrap_or_else(|e| panic!("{e}"));
+        fs::write(dir.path().join("bob/b.wav"), b"").unwrap_or_else(|e| panic!("{e}"));
+        fs::write(dir.path().join("bob/a.wav"), b"").unwrap_or_else(|e| panic!("{e}"));
+        fs::write(dir.path().join("stray.wav"), b"").unwrap_or_else(|e| panic!("{e}"));
+
+        let files = collect_labelled_files(&["alice=/tmp/a.wav".to_string()], Some(dir.path()))
+            .unwrap_or_else(|e| panic!("{e}"));
+        let labels: Vec<&str> = files.iter().map(|(l, _)| l.as_str()).collect();
+        assert_eq!(labels, vec!["alice", "bob", "bob"]);
+        assert!(files[1].1.ends_with("bob/a.wav"));
+
+        assert!(collect_labelled_files(&["no-separator".to_string()], None).is_err());
+        assert!(collect_labelled_files(&[], None).is_err());
+    }
+}
@@ -0,0 +1,430 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use chrono::NaiveDate;
+
+use crate::config::Config;
+use crate::error::{HooverError, Result};
+use crate::output::calendar::Calendar;
+use crate::output::layout::Layout;
+use crate::vault::{Vault, replace_file};
+
+use super::cosine_similarity;
+use super::enroll::SpeakerProfile;
+use super::manage::validate_name;
+
+/// Prefix for anonymous cluster labels written to the transcript.
+pub const CLUSTER_LABEL_PREFIX: &str = "Speaker ";
+
+/// How long updated centroids may wait before they are saved.
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// An anonymous speaker discovered during a day's recording.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Cluster {
+    /// Short label, e.g. `A` (written as `Speaker A`).
+    pub label: String,
+    /// L2-normalized running mean of all embeddings assigned to the cluster.
+    pub centroid: Vec<f32>,
+    /// Number of embeddings assigned so far.
+    pub count: u32,
+    /// Fingerprint of the speaker model that produced the centroid.
+    #[serde(default)]
+    pub model_hash: String,
+}
+
+impl Cluster {
+    /// The speaker name used in transcripts for this cluster.
+    #[must_use]
+    pub fn display_name(&self) -> String {
+        format!("{CLUSTER_LABEL_PREFIX}{}", self.label)
+    }
+}
+
+/// Online clustering of embeddings that don't match any enrolled profile.
+///
+/// Clusters are scoped to a single day: labels stay stable for the whole day
+/// (including across restarts, since centroids are persisted) and start over
+/// from `A` the next day.
+pub struct SpeakerClusters {
+    dir: PathBuf,
+    vault: Vault,
+    calendar: Calendar,
+    date: NaiveDate,
+    clusters: Vec<Cluster>,
+    /// Labels in the cluster file as of the last load or save, to tell
+    /// clusters removed by `hoover speakers name` from new ones.
+    saved_labels: Vec<String>,
+    /// When the clusters were last saved, if they have changed since.
+    dirty_since: Option<Instant>,
+    threshold: f32,
+    model_hash: String,
+}
+
+impl SpeakerClusters {
+    /// Load (or start) today's clusters from `profiles_dir`, with days as
+    /// defined by `calendar`.  Cluster files are sealed with `vault`, like the
+    /// profiles next to them.
+    pub fn new(
+        profiles_dir: &Path,
+        threshold: f32,
+        model_hash: String,
+        calendar: Calendar,
+        vault: Vault,
+    ) -> Result<Self> {
+        let dir = clusters_dir(profiles_dir);
+        let date = calendar.today();
+        let clusters = load_clusters(&dir, &vault, date)?;
+
+        Ok(Self {
+            dir,
+            vault,
+            calendar,
+            date,
+            saved_labels: labels(&clusters),
+            clusters,
+            dirty_since: None,
+            threshold,
+            model_hash,
+        })
+    }
+
+    /// Assign an embedding to the closest cluster, creating a new one when
+    /// nothing is similar enough.  Returns the cluster's display name.
+    ///
+    /// New clusters are saved right away, updated centroids at most every
+    /// [`SAVE_INTERVAL`].
+    pub fn assign(&mut self, embedding: &[f32]) -> String {
+        let today = self.calendar.today();
+        if today != self.date {
+            self.flush();
+            self.date = today;
+            self.clusters = load_clusters(&self.dir, &self.vault, today).unwrap_or_else(|e| {
+                tracing::warn!("failed to load speaker clusters for {today}: {e}");
+                Vec::new()
+            });
+            self.saved_labels = labels(&self.clusters);
+        }
+
+        let count = self.clusters.len();
+        let name = self.assign_inner(embedding);
+
+        let dirty_since = *self.dirty_since.get_or_insert_with(Instant::now);
+        if self.clusters.len() > count || dirty_since.elapsed() >= SAVE_INTERVAL {
+            self.flush();
+        }
+
+        name
+    }
+
+    /// Save pending changes.  The cluster file is read first: clusters
+    /// removed from it since the last save, as `hoover speakers name` does,
+    /// are dropped rather than written back, and clusters added to it are
+    /// kept.
+    pub fn flush(&mut self) {
+        if self.dirty_since.is_none() {
+            return;
+        }
+        let on_disk = match load_clusters(&self.dir, &self.vault, self.date) {
+            Ok(on_disk) => on_disk,
+            Err(e) => {
+                tracing::warn!("failed to reload speaker clusters: {e}");
+                return;
+            }
+        };
+        self.clusters.retain(|cluster| {
+            on_disk.iter().any(|c| c.label == cluster.label)
+                || !self.saved_labels.contains(&cluster.label)
+        });
+        for cluster in on_disk {
+            if self.clusters.iter().all(|c| c.label != cluster.label) {
+                self.clusters.push(cluster);
+            }
+        }
+
+        match save_clusters(&self.dir, &self.vault, self.date, &self.clusters) {
+            Ok(()) => {
+                self.saved_labels = labels(&self.clusters);
+                self.dirty_since = None;
+            }
+            Err(e) => tracing::warn!("failed to save speaker clusters: {e}"),
+        }
+    }
+
+    fn assign_inner(&mut self, embedding: &[f32]) -> String {
+        let best = self
+            .clusters
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.model_hash == self.model_hash)
+            .map(|(i, c)| (i, cosine_similarity(embedding, &c.centroid)))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+
+        if let Some((idx, score)) = best
+            && score >= self.threshold
+        {
+            let cluster = &mut self.clusters[idx];
+            let n = cluster.count as f32;
+            for (c, &v) in cluster.centroid.iter_mut().zip(embedding) {
+                *c = (*c).mul_add(n, v) / (n + 1.0);
+            }
+            normalize(&mut cluster.centroid);
+            cluster.count += 1;
+            return cluster.display_name();
+        }
+
+        let mut centroid = embedding.to_vec();
+        normalize(&mut centroid);
+        // Labels freed by `hoover speakers name` are reused.
+        let label = (0..=self.clusters.len())
+            .map(cluster_label)
+            .find(|l| self.clusters.iter().all(|c| &c.label != l))
+            .unwrap_or_default();
+        let cluster = Cluster {
+            label,
+            centroid,
+            count: 1,
+            model_hash: self.model_hash.clone(),
+        };
+        let name = cluster.display_name();
+        tracing::info!("new unknown speaker: {name}");
+        self.clusters.push(cluster);
+        name
+    }
+}
+
+fn labels(clusters: &[Cluster]) -> Vec<String> {
+    clusters.iter().map(|c| c.label.clone()).collect()
+}
+
+/// Spreadsheet-style label for the n-th cluster: `A`..`Z`, `AA`, `AB`, ...
+fn cluster_label(mut n: usize) -> String {
+    let mut label = Vec::new();
+    loop {
+        label.push(b'A' + (n % 26) as u8);
+        if n < 26 {
+            break;
+        }
+        n = n / 26 - 1;
+    }
+    label.reverse();
+    String::from_utf8_lossy(&label).into_owned()
+}
+
+fn normalize(v: &mut [f32]) {
+    let norm: f32 = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        for x in v.iter_mut() {
+            *x /= norm;
+        }
+    }
+}
+
+fn clusters_dir(profiles_dir: &Path) -> PathBuf {
+    profiles_dir.join("clusters")
+}
+
+fn clusters_file(dir: &Path, date: NaiveDate) -> PathBuf {
+    dir.join(format!("{}.json", date.format("%Y-%m-%d")))
+}
+
+fn load_clusters(dir: &Path, vault: &Vault, date: NaiveDate) -> Result<Vec<Cluster>> {
+    let path = clusters_file(dir, date);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let data = vault.open(fs::read(&path)?)?;
+    serde_json::from_slice(&data)
+        .map_err(|e| HooverError::Speaker(format!("invalid cluster file {}: {e}", path.display())))
+}
+
+fn save_clusters(dir: &Path, vault: &Vault, date: NaiveDate, clusters: &[Cluster]) -> Result<()> {
+    fs::create_dir_all(dir)?;
+    let data = serde_json::to_vec(clusters)
+        .map_err(|e| HooverError::Speaker(format!("failed to serialize clusters: {e}")))?;
+    replace_file(&clusters_file(dir, date), &vault.seal(&data)?)
+}
+
+/// List the anonymous speaker clusters recorded on `date`.
+pub fn list_clusters(profiles_dir: &Path, vault: &Vault, date: NaiveDate) -> Result<Vec<Cluster>> {
+    load_clusters(&clusters_dir(profiles_dir), vault, date)
+}
+
+/// Promote an anonymous cluster to a named speaker profile.
+///
+/// `cluster` may be given as `A` or `Speaker A`.  The cluster centroid becomes
+/// the profile embedding (added to an existing profile of the same name),
+/// the cluster is removed from the day's cluster file, and past `Speaker A`
+/// labels in that day's transcript are rewritten to `name`.
+///
+/// Returns the number of transcript labels rewritten.
+pub fn promote_cluster(
+    config: &Config,
+    date: NaiveDate,
+    cluster: &str,
+    name: &str,
+) -> Result<usize> {
+    validate_name(name)?;
+    let label = cluster
+        .strip_prefix(CLUSTER_LABEL_PREFIX)
+        .unwrap_or(cluster)
+        .trim()
+        .to_uppercase();
+
+    let layout = Layout::new(&config.output.layout)?;
+    let vault = Vault::new(&config.output)?;
+    let profiles_vault = Vault::for_profiles(&config.output)?;
+    let profiles_dir = Config::expand_path(&config.speaker.profiles_dir);
+    let dir = clusters_dir(&profiles_dir);
+    let mut clusters = load_clusters(&dir, &profiles_vault, date)?;
+    let idx = clusters
+        .iter()
+        .position(|c| c.label == label)
+        .ok_or_else(|| {
+            HooverError::Speaker(format!("no speaker cluster '{label}' recorded on {date}"))
+        })?;
+    let promoted = clusters.remove(idx);
+
+    let profile_path = profiles_dir.join(format!("{name}.bin"));
+    let profile = if profile_path.exists() {
+        let mut existing = SpeakerProfile::load(&profile_path, &profiles_vault)?;
+        if !existing.model_hash.is_empty() && existing.model_hash != promoted.model_hash {
+            return Err(HooverError::Speaker(format!(
+                "profile '{name}' was created with a different speaker model than cluster '{label}'"
+            )));
+        }
+        existing.model_hash.clone_from(&promoted.model_hash);
+        existing.add_embedding(&promoted.centroid, promoted.count)?;
+        existing
+    } else {
+        SpeakerProfile::new(
+            name,
+            &promoted.model_hash,
+            promoted.centroid.clone(),
+            promoted.count,
+        )
+    };
+    profile.save(&profiles_dir, &profiles_vault)?;
+    save_clusters(&dir, &profiles_vault, date, &clusters)?;
+
+    let output_dir = Config::expand_path(&config.output.directory);
+    let path = layout.day_file(&output_dir, date);
+    crate::output::markdown::rename_speaker(&path, &layout, &vault, &promoted.display_name(), name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_follow_spreadsheet_order() {
+        assert_eq!(cluster_label(0), "A");
+        assert_eq!(cluster_label(25), "Z");
+        assert_eq!(cluster_label(26), "AA");
+        assert_eq!(cluster_label(27), "AB");
+    }
+
+    #[test]
+    fn similar_embeddings_share_a_cluster() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let mut clusters = SpeakerClusters::new(
+            dir.path(),
+            0.8,
+            "h".to_string(),
+            Calendar::default(),
+            Vault::default(),
+        )
+        .unwrap_or_else(|e| panic!("{e}"));
+
+        assert_eq!(clusters.assign(&[1.0, 0.0, 0.0]), "Speaker A");
+        assert_eq!(clusters.assign(&[0.95, 0.05, 0.0]), "Speaker A");
+        assert_eq!(clusters.assign(&[0.0, 1.0, 0.0]), "Speaker B");
+        assert_eq!(clusters.clusters[0].count, 2);
+    }
+
+    #[test]
+    fn clusters_persist_across_restarts() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let mut clusters = SpeakerClusters::new(
+            dir.path(),
+            0.8,
+            "h".to_string(),
+            Calendar::default(),
+            Vault::default(),
+        )
+        .unwrap_or_else(|e| panic!("{e}"));
+        clusters.assign(&[1.0, 0.0]);
+        clusters.assign(&[0.0, 1.0]);
+
+        let mut reloaded = SpeakerClusters::new(
+            dir.path(),
+            0.8,
+            "h".to_string(),
+            Calendar::default(),
+            Vault::default(),
+        )
+        .unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(reloaded.assign(&[0.0, 1.0]), "Speaker B");
+
+        let listed = list_clusters(dir.path(), &Vault::default(), Calendar::default().today())
+            .unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(listed.len(), 2);
+    }
+
+    #[test]
+    fn promote_creates_profile_and_rewrites_labels() {
+        let profiles = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let output = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let mut config: Config = serde_yaml_ng::from_str("{}").unwrap_or_else(|e| panic!("{e}"));
+        config.speaker.profiles_dir = profiles.path().to_string_lossy().to_string();
+        config.output.directory = output.path().to_string_lossy().to_string();
+
+        let mut clusters = SpeakerClusters::new(
+            profiles.path(),
+            0.8,
+            "h".to_string(),
+            Calendar::default(),
+            Vault::default(),
+        )
+        .unwrap_or_else(|e| panic!("{e}"));
+        clusters.assign(&[1.0, 0.0]);
+
+        let date = Calendar::default().today();
+        let layout = Layout::new(&config.output.layout).unwrap_or_else(|e| panic!("{e}"));
+        let day = layout.day_file(output.path(), date);
+        fs::write(
+            &day,
+            "# Today\n\n**Speaker A:** hello\n\n**Speaker AB:** hi\n",
+        )
+        .unwrap_or_else(|e| panic!("{e}"));
+
+        let rewritten =
+            promote_cluster(&config, date, "a", "Alice").unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(rewritten, 1);
+
+        let content = fs::read_to_string(&day).unwrap_or_else(|e| panic!("{e}"));
+        assert!(content.contains("**Alice:** hello"));
+        assert!(content.contains("**Speaker AB:** hi"));
+
+        let profile = SpeakerProfile::load(&profiles.path().join("Alice.bin"), &Vault::default())
+            .unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(profile.dimension(), 2);
+        assert_eq!(profile.model_hash, "h");
+        assert!(
+            list_clusters(profiles.path(), &Vault::default(), date)
+                .unwrap_or_else(|e| panic!("{e}"))
+                .is_empty()
+        );
+
+        // The running recorder doesn't write the promoted cluster back.
+        clusters.assign(&[1.0, 0.0]);
+        clusters.assign(&[0.0, 1.0]);
+        clusters.flush();
+        let listed = list_clusters(profiles.path(), &Vault::default(), date)
+            .unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(labels(&listed), ["B"]);
+
+        assert!(promote_cluster(&config, date, "B", "../Bob").is_err());
+    }
+}
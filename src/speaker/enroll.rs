@@ -1,95 +1,327 @@
 use std::fs;
+use std::ops::Range;
 use std::path::PathBuf;
 
+use chrono::{DateTime, Utc};
+
 use crate::audio::capture::AudioCapture;
 use crate::audio::resample::Resampler;
+use crate::audio::vad::SileroVad;
 use crate::config::Config;
 use crate::error::{HooverError, Result};
+use crate::vault::Vault;
+
+use super::manage::validate_name;
+use super::{cosine_similarity, extract_embedding, load_embedding_model, model_fingerprint};
+
+/// Magic bytes at the start of a versioned profile file.
+const PROFILE_MAGIC: &[u8; 4] = b"HVSP";
+
+/// Current profile format version.
+const PROFILE_VERSION: u32 = 3;
+
+/// Profile flag: continuous training is disabled (version 3+).
+const FLAG_LOCKED: u32 = 1;
 
-use super::{extract_embedding, load_embedding_model};
+/// A new embedding joins an existing centroid when at least this similar;
+/// otherwise it starts a new centroid (a different room, microphone, ...).
+const CENTROID_MERGE_THRESHOLD: f32 = 0.8;
 
-/// Speaker profile: a name and averaged embedding vector.
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+/// Upper bound on centroids per speaker.
+const MAX_CENTROIDS: usize = 4;
+
+/// One cluster of a speaker's voice, e.g. a particular microphone or room.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Centroid {
+    /// L2-normalized mean embedding.
+    pub embedding: Vec<f32>,
+    /// Number of embeddings averaged into this centroid.
+    pub sample_count: u32,
+}
+
+/// Speaker profile: a name plus the embeddings describing their voice.
+///
+/// The anchor is the embedding from the original enrollment and is never
+/// changed by continuous training, so it can be used to keep the centroids
+/// from drifting to another voice.
+#[derive(Debug, Clone, PartialEq)]
 pub struct SpeakerProfile {
     pub name: String,
-    pub embedding: Vec<f32>,
+    /// Hex SHA-256 of the ONNX model that produced the embeddings.  Empty for
+    /// profiles migrated from the unversioned format.
+    pub model_hash: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub anchor: Vec<f32>,
+    pub centroids: Vec<Centroid>,
+    /// When set, continuous training leaves the profile untouched.
+    pub locked: bool,
 }
 
 impl SpeakerProfile {
-    pub fn load(path: &std::path::Path) -> Result<Self> {
-        let data = fs::read(path)?;
-        bincode_deserialize(&data)
+    /// Create a profile from a freshly enrolled embedding.
+    #[must_use]
+    pub fn new(name: &str, model_hash: &str, embedding: Vec<f32>, sample_count: u32) -> Self {
+        let now = Utc::now();
+        Self {
+            name: name.to_string(),
+            model_hash: model_hash.to_string(),
+            created_at: now,
+            updated_at: now,
+            anchor: embedding.clone(),
+            centroids: vec![Centroid {
+                embedding,
+                sample_count,
+            }],
+            locked: false,
+        }
+    }
+
+    /// Embedding dimension.
+    #[must_use]
+    pub const fn dimension(&self) -> usize {
+        self.anchor.len()
+    }
+
+    /// Total number of embeddings across all centroids.
+    #[must_use]
+    pub fn sample_count(&self) -> u32 {
+        self.centroids.iter().map(|c| c.sample_count).sum()
+    }
+
+    /// Highest similarity between `embedding` and any centroid, with the
+    /// index of that centroid.
+    #[must_use]
+    pub fn best_centroid(&self, embedding: &[f32]) -> Option<(usize, f32)> {
+        self.centroids
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (i, cosine_similarity(embedding, &c.embedding)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    /// Add enrollment data to the profile.
+    ///
+    /// The embedding is merged (weighted by sample count) into the closest
+    /// centroid when similar enough, or becomes a new centroid.  Once
+    /// `MAX_CENTROIDS` exist it is always merged into the closest one.
+    pub fn add_embedding(&mut self, embedding: &[f32], sample_count: u32) -> Result<()> {
+        if embedding.len() != self.dimension() {
+            return Err(HooverError::Speaker(format!(
+                "profile '{}' has {} dimensions but the new embedding has {}",
+                self.name,
+                self.dimension(),
+                embedding.len()
+            )));
+        }
+
+        match self.best_centroid(embedding) {
+            Some((idx, score))
+                if score >= CENTROID_MERGE_THRESHOLD || self.centroids.len() >= MAX_CENTROIDS =>
+            {
+                let centroid = &mut self.centroids[idx];
+                let old = centroid.sample_count.max(1) as f32;
+                let new = sample_count.max(1) as f32;
+                for (c, &v) in centroid.embedding.iter_mut().zip(embedding) {
+                    *c = (*c).mul_add(old, v * new) / (old + new);
+                }
+                normalize(&mut centroid.embedding);
+                centroid.sample_count += sample_count;
+            }
+            _ => {
+                let mut embedding = embedding.to_vec();
+                normalize(&mut embedding);
+                self.centroids.push(Centroid {
+                    embedding,
+                    sample_count,
+                });
+            }
+        }
+
+        self.updated_at = Utc::now();
+        Ok(())
+    }
+
+    /// Load a profile, decrypting it if it was saved encrypted.
+    pub fn load(path: &std::path::Path, vault: &Vault) -> Result<Self> {
+        let data = vault.open(fs::read(path)?)?;
+        decode_profile(&data)
     }
 
-    pub fn save(&self, dir: &std::path::Path) -> Result<PathBuf> {
+    /// Save the profile to `dir`, encrypted when `vault` has a key.
+    pub fn save(&self, dir: &std::path::Path, vault: &Vault) -> Result<PathBuf> {
         fs::create_dir_all(dir)?;
         let path = dir.join(format!("{}.bin", self.name));
-        let data = bincode_serialize(self);
+        let data = vault.seal(&encode_profile(self))?;
         fs::write(&path, data)?;
         Ok(path)
     }
 }
 
-fn bincode_serialize(profile: &SpeakerProfile) -> Vec<u8> {
-    // Simple binary format: name_len(u32) + name_bytes + embedding_len(u32) + embedding_f32s
-    let mut data = Vec::new();
-    let name_bytes = profile.name.as_bytes();
-    data.extend_from_slice(&(name_bytes.len() as u32).to_le_bytes());
-    data.extend_from_slice(name_bytes);
-    data.extend_from_slice(&(profile.embedding.len() as u32).to_le_bytes());
-    for &v in &profile.embedding {
+fn put_u32(data: &mut Vec<u8>, v: usize) {
+    data.extend_from_slice(&(v as u32).to_le_bytes());
+}
+
+fn put_str(data: &mut Vec<u8>, s: &str) {
+    put_u32(data, s.len());
+    data.extend_from_slice(s.as_bytes());
+}
+
+fn put_floats(data: &mut Vec<u8>, values: &[f32]) {
+    for &v in values {
         data.extend_from_slice(&v.to_le_bytes());
     }
+}
+
+fn encode_profile(profile: &SpeakerProfile) -> Vec<u8> {
+    // magic + version(u32) + name + model_hash + dimension(u32)
+    // + created(i64) + updated(i64) + flags(u32) + anchor + centroid_count(u32)
+    // + centroids (sample_count(u32) + embedding each).
+    // Strings are u32 length + UTF-8; embeddings are `dimension` f32s.
+    let mut data = Vec::new();
+    data.extend_from_slice(PROFILE_MAGIC);
+    data.extend_from_slice(&PROFILE_VERSION.to_le_bytes());
+    put_str(&mut data, &profile.name);
+    put_str(&mut data, &profile.model_hash);
+    put_u32(&mut data, profile.dimension());
+    data.extend_from_slice(&profile.created_at.timestamp().to_le_bytes());
+    data.extend_from_slice(&profile.updated_at.timestamp().to_le_bytes());
+    let flags = if profile.locked { FLAG_LOCKED } else { 0 };
+    data.extend_from_slice(&flags.to_le_bytes());
+    put_floats(&mut data, &profile.anchor);
+    put_u32(&mut data, profile.centroids.len());
+    for centroid in &profile.centroids {
+        data.extend_from_slice(&centroid.sample_count.to_le_bytes());
+        put_floats(&mut data, &centroid.embedding);
+    }
     data
 }
 
-fn bincode_deserialize(data: &[u8]) -> Result<SpeakerProfile> {
-    if data.len() < 4 {
-        return Err(HooverError::Speaker("profile data too short".to_string()));
+/// Cursor over profile bytes that turns short reads into errors.
+struct ProfileReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ProfileReader<'a> {
+    const fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
     }
-    let mut pos = 0;
 
-    let name_len = u32::from_le_bytes(
-        data[pos..pos + 4]
+    fn take<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + N)
+            .ok_or_else(|| HooverError::Speaker("profile data truncated".to_string()))?;
+        self.pos += N;
+        bytes
             .try_into()
-            .map_err(|_| HooverError::Speaker("invalid profile data".to_string()))?,
-    ) as usize;
-    pos += 4;
+            .map_err(|_| HooverError::Speaker("invalid profile data".to_string()))
+    }
 
-    if data.len() < pos + name_len + 4 {
-        return Err(HooverError::Speaker("profile data truncated".to_string()));
+    fn u32(&mut self) -> Result<u32> {
+        self.take().map(u32::from_le_bytes)
     }
-    let name = String::from_utf8(data[pos..pos + name_len].to_vec())
-        .map_err(|e| HooverError::Speaker(format!("invalid profile name: {e}")))?;
-    pos += name_len;
 
-    let emb_len = u32::from_le_bytes(
-        data[pos..pos + 4]
-            .try_into()
-            .map_err(|_| HooverError::Speaker("invalid profile data".to_string()))?,
-    ) as usize;
-    pos += 4;
+    fn i64(&mut self) -> Result<i64> {
+        self.take().map(i64::from_le_bytes)
+    }
 
-    if data.len() < pos + emb_len * 4 {
-        return Err(HooverError::Speaker("profile data truncated".to_string()));
+    fn string(&mut self) -> Result<String> {
+        let len = self.u32()? as usize;
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + len)
+            .ok_or_else(|| HooverError::Speaker("profile data truncated".to_string()))?;
+        self.pos += len;
+        String::from_utf8(bytes.to_vec())
+            .map_err(|e| HooverError::Speaker(format!("invalid profile string: {e}")))
     }
 
-    let mut embedding = Vec::with_capacity(emb_len);
-    for _ in 0..emb_len {
-        let val = f32::from_le_bytes(
-            data[pos..pos + 4]
-                .try_into()
-                .map_err(|_| HooverError::Speaker("invalid float data".to_string()))?,
-        );
-        embedding.push(val);
-        pos += 4;
+    fn floats(&mut self, len: usize) -> Result<Vec<f32>> {
+        if self.data.len() < self.pos + len * 4 {
+            return Err(HooverError::Speaker("profile data truncated".to_string()));
+        }
+        (0..len)
+            .map(|_| self.take().map(f32::from_le_bytes))
+            .collect()
+    }
+
+    fn timestamp(&mut self) -> Result<DateTime<Utc>> {
+        let secs = self.i64()?;
+        DateTime::from_timestamp(secs, 0)
+            .ok_or_else(|| HooverError::Speaker(format!("invalid profile timestamp {secs}")))
+    }
+}
+
+fn decode_profile(data: &[u8]) -> Result<SpeakerProfile> {
+    if !data.starts_with(PROFILE_MAGIC) {
+        return decode_legacy_profile(data);
+    }
+
+    let mut reader = ProfileReader::new(&data[PROFILE_MAGIC.len()..]);
+    let version = reader.u32()?;
+    if !(2..=PROFILE_VERSION).contains(&version) {
+        return Err(HooverError::Speaker(format!(
+            "unsupported profile version {version} (expected at most {PROFILE_VERSION})"
+        )));
+    }
+
+    let name = reader.string()?;
+    let model_hash = reader.string()?;
+    let dimension = reader.u32()? as usize;
+    let created_at = reader.timestamp()?;
+    let updated_at = reader.timestamp()?;
+    // Version 2 predates flags.
+    let flags = if version >= 3 { reader.u32()? } else { 0 };
+    let anchor = reader.floats(dimension)?;
+    let count = reader.u32()?;
+    let mut centroids = Vec::new();
+    for _ in 0..count {
+        let sample_count = reader.u32()?;
+        let embedding = reader.floats(dimension)?;
+        centroids.push(Centroid {
+            embedding,
+            sample_count,
+        });
+    }
+
+    if centroids.is_empty() {
+        return Err(HooverError::Speaker(format!(
+            "profile '{name}' has no centroids"
+        )));
     }
 
-    Ok(SpeakerProfile { name, embedding })
+    Ok(SpeakerProfile {
+        name,
+        model_hash,
+        created_at,
+        updated_at,
+        anchor,
+        centroids,
+        locked: flags & FLAG_LOCKED != 0,
+    })
+}
+
+/// Read the original unversioned format: name + a single embedding.
+///
+/// The model is unknown, so `model_hash` is left empty; the identifier
+/// adopts the configured model for such profiles on first load.
+fn decode_legacy_profile(data: &[u8]) -> Result<SpeakerProfile> {
+    let mut reader = ProfileReader::new(data);
+    let name = reader.string()?;
+    let len = reader.u32()? as usize;
+    let embedding = reader.floats(len)?;
+
+    let mut profile = SpeakerProfile::new(&name, "", embedding, 0);
+    let epoch = DateTime::<Utc>::UNIX_EPOCH;
+    profile.created_at = epoch;
+    profile.updated_at = epoch;
+    Ok(profile)
 }
 
 /// List all enrolled speaker profile names from the profiles directory.
-pub fn list_profiles(profiles_dir: &std::path::Path) -> Result<Vec<String>> {
+pub fn list_profiles(profiles_dir: &std::path::Path, vault: &Vault) -> Result<Vec<String>> {
     if !profiles_dir.exists() {
         return Ok(Vec::new());
     }
@@ -99,7 +331,7 @@ pub fn list_profiles(profiles_dir: &std::path::Path) -> Result<Vec<String>> {
         let entry = entry?;
         let path = entry.path();
         if path.extension().and_then(|e| e.to_str()) == Some("bin") {
-            match SpeakerProfile::load(&path) {
+            match SpeakerProfile::load(&path, vault) {
                 Ok(profile) => names.push(profile.name),
                 Err(e) => tracing::warn!("failed to load profile {}: {e}", path.display()),
             }
@@ -122,11 +354,138 @@ pub fn remove_profile(profiles_dir: &std::path::Path, name: &str) -> Result<()>
     Ok(())
 }
 
-/// Run speaker enrollment: record audio, extract embeddings, save profile.
-pub async fn run_enrollment(config: &Config, name: &str) -> Result<()> {
+/// Length of each enrollment window in samples (3 seconds at 16kHz).
+const WINDOW_SAMPLES: usize = 16000 * 3;
+
+/// Windows shorter than this (1 second) are not embedded.
+const MIN_WINDOW_SAMPLES: usize = 16000;
+
+/// Number of samples per Silero VAD frame.
+const VAD_FRAME_SAMPLES: usize = 512;
+
+/// Frames with a speech probability at or above this count as speech.
+const VAD_SPEECH_THRESHOLD: f32 = 0.5;
+
+/// Minimum fraction of speech frames for a window to be used.
+const MIN_SPEECH_FRACTION: f32 = 0.5;
+
+/// Windows this many standard deviations below the mean similarity to the
+/// centroid are rejected as outliers.
+const OUTLIER_STD_DEVS: f32 = 2.0;
+
+/// Run speaker enrollment: gather audio, extract embeddings, save profile.
+///
+/// Audio comes from `files` when given, otherwise from the microphone until
+/// Ctrl+C.  Non-speech windows are dropped with the VAD, outlier windows are
+/// rejected, and with `append` the result is blended into the speaker's
+/// existing profile instead of replacing it.
+pub async fn run_enrollment(
+    config: &Config,
+    name: &str,
+    files: &[PathBuf],
+    append: bool,
+) -> Result<()> {
+    validate_name(name)?;
+    let profiles_dir = Config::expand_path(&config.speaker.profiles_dir);
+    let vault = Vault::for_profiles(&config.output)?;
+    let profile_path = profiles_dir.join(format!("{name}.bin"));
+    let existing = if append {
+        if !profile_path.exists() {
+            return Err(HooverError::Speaker(format!(
+                "no profile found for '{name}' to append to"
+            )));
+        }
+        Some(SpeakerProfile::load(&profile_path, &vault)?)
+    } else {
+        None
+    };
+
     let model_path = resolve_speaker_model(config.speaker.model_path.as_deref())?;
     let mut session = load_embedding_model(&model_path, config.stt.gpu)?;
+    let model_hash = model_fingerprint(&model_path)?;
+
+    let all_samples = if files.is_empty() {
+        record_microphone(config, name).await?
+    } else {
+        let mut samples = Vec::new();
+        for file in files {
+            let decoded = crate::audio::file::load_audio_file(file)?;
+            tracing::info!(
+                "Loaded {:.1} seconds of audio from {}",
+                decoded.len() as f32 / 16000.0,
+                file.display()
+            );
+            samples.extend_from_slice(&decoded);
+        }
+        samples
+    };
+
+    let duration_secs = all_samples.len() as f32 / 16000.0;
+    tracing::info!("Got {duration_secs:.1} seconds of audio");
+
+    if duration_secs < 3.0 {
+        return Err(HooverError::Speaker(
+            "recording too short — need at least 3 seconds for enrollment".to_string(),
+        ));
+    }
+
+    let mut vad = SileroVad::new(&crate::audio::resolve_vad_model()?)?;
+    let windows = detect_speech_windows(&mut vad, &all_samples);
+    let total_windows = all_samples.len().div_ceil(WINDOW_SAMPLES);
+    tracing::info!(
+        "{} of {total_windows} windows contain speech",
+        windows.len()
+    );
 
+    let mut embeddings = Vec::new();
+    for window in windows {
+        embeddings.push(extract_embedding(&mut session, &all_samples[window])?);
+    }
+
+    if embeddings.is_empty() {
+        return Err(HooverError::Speaker(
+            "no speech found in the enrollment audio".to_string(),
+        ));
+    }
+
+    let (kept, rejected) = reject_outliers(embeddings);
+    if rejected > 0 {
+        tracing::info!("Rejected {rejected} outlier window(s)");
+    }
+
+    let embedding = average_embedding(&kept);
+    let quality = quality_score(&kept, &embedding);
+    let sample_count = kept.len() as u32;
+
+    let profile = match existing {
+        Some(mut profile) => {
+            if !profile.model_hash.is_empty() && profile.model_hash != model_hash {
+                return Err(HooverError::Speaker(format!(
+                    "existing profile for '{name}' was created with a different speaker model; \
+                     re-enroll without --append"
+                )));
+            }
+            profile.model_hash = model_hash;
+            profile.add_embedding(&embedding, sample_count)?;
+            profile
+        }
+        None => SpeakerProfile::new(name, &model_hash, embedding, sample_count),
+    };
+
+    let saved_path = profile.save(&profiles_dir, &vault)?;
+    tracing::info!("Speaker profile saved to {}", saved_path.display());
+
+    println!(
+        "Enrollment quality for '{name}': {quality:.2} ({}) from {} window(s), {rejected} rejected",
+        quality_label(quality),
+        kept.len()
+    );
+
+    Ok(())
+}
+
+/// Record from the microphone until Ctrl+C, returning 16kHz mono samples.
+async fn record_microphone(config: &Config, name: &str) -> Result<Vec<f32>> {
     tracing::info!("Recording audio for speaker enrollment of '{name}'...");
     tracing::info!("Speak for 10-30 seconds, then press Ctrl+C to stop.");
 
@@ -166,72 +525,128 @@ pub async fn run_enrollment(config: &Config, name: &str) -> Result<()> {
     }
 
     capture.pause()?;
+    Ok(all_samples)
+}
 
-    let duration_secs = all_samples.len() as f32 / 16000.0;
-    tracing::info!("Recorded {duration_secs:.1} seconds of audio");
-
-    if duration_secs < 3.0 {
-        return Err(HooverError::Speaker(
-            "recording too short — need at least 3 seconds for enrollment".to_string(),
-        ));
-    }
+/// Split 16kHz mono audio into 3-second windows and return those that are
+/// mostly speech according to the VAD.
+pub(super) fn detect_speech_windows(vad: &mut SileroVad, samples: &[f32]) -> Vec<Range<usize>> {
+    vad.reset();
+    let frame_probs: Vec<f32> = samples
+        .chunks_exact(VAD_FRAME_SAMPLES)
+        .map(|frame| vad.process_chunk(frame).unwrap_or(0.0))
+        .collect();
+    speech_windows(samples.len(), &frame_probs)
+}
 
-    // Split into 3-second segments and extract embeddings
-    let segment_samples = 16000 * 3;
-    let mut embeddings = Vec::new();
+/// Split `len` samples into 3-second windows and keep those where at least
+/// half of the VAD frames are speech.  `frame_probs` holds one speech
+/// probability per 512-sample frame.
+fn speech_windows(len: usize, frame_probs: &[f32]) -> Vec<Range<usize>> {
+    (0..len)
+        .step_by(WINDOW_SAMPLES)
+        .map(|start| start..(start + WINDOW_SAMPLES).min(len))
+        .filter(|window| window.len() >= MIN_WINDOW_SAMPLES)
+        .filter(|window| {
+            let frames = &frame_probs[(window.start / VAD_FRAME_SAMPLES).min(frame_probs.len())
+                ..(window.end / VAD_FRAME_SAMPLES).min(frame_probs.len())];
+            if frames.is_empty() {
+                return false;
+            }
+            let speech = frames
+                .iter()
+                .filter(|&&p| p >= VAD_SPEECH_THRESHOLD)
+                .count();
+            speech as f32 / frames.len() as f32 >= MIN_SPEECH_FRACTION
+        })
+        .collect()
+}
 
-    for chunk_start in (0..all_samples.len()).step_by(segment_samples) {
-        let chunk_end = (chunk_start + segment_samples).min(all_samples.len());
-        if chunk_end - chunk_start < 16000 {
-            break; // skip segments < 1 second
-        }
-        let segment = &all_samples[chunk_start..chunk_end];
-        let emb = extract_embedding(&mut session, segment)?;
-        embeddings.push(emb);
+/// Drop embeddings that sit far from the rest (noise, another voice, ...).
+///
+/// Returns the kept embeddings and how many were rejected.  With fewer than
+/// three windows there is not enough data to tell outliers apart, so nothing
+/// is rejected.
+fn reject_outliers(embeddings: Vec<Vec<f32>>) -> (Vec<Vec<f32>>, usize) {
+    if embeddings.len() < 3 {
+        return (embeddings, 0);
     }
 
-    if embeddings.is_empty() {
-        return Err(HooverError::Speaker(
-            "failed to extract any embeddings".to_string(),
-        ));
-    }
+    let centroid = average_embedding(&embeddings);
+    let scores: Vec<f32> = embeddings
+        .iter()
+        .map(|e| cosine_similarity(e, &centroid))
+        .collect();
+    let n = scores.len() as f32;
+    let mean = scores.iter().sum::<f32>() / n;
+    let std_dev = (scores.iter().map(|s| (s - mean).powi(2)).sum::<f32>() / n).sqrt();
+    let cutoff = OUTLIER_STD_DEVS.mul_add(-std_dev, mean);
+
+    let total = embeddings.len();
+    let kept: Vec<Vec<f32>> = embeddings
+        .into_iter()
+        .zip(scores)
+        .filter(|(_, score)| *score >= cutoff)
+        .map(|(e, _)| e)
+        .collect();
+    let rejected = total - kept.len();
+    (kept, rejected)
+}
 
-    // Average embeddings
-    let dim = embeddings[0].len();
+/// Average embeddings and L2-normalize the result so the profile lives on
+/// the unit sphere, matching the normalization applied during continuous
+/// training.
+fn average_embedding(embeddings: &[Vec<f32>]) -> Vec<f32> {
+    let dim = embeddings.first().map_or(0, Vec::len);
     let mut avg = vec![0.0f32; dim];
-    for emb in &embeddings {
-        for (i, &v) in emb.iter().enumerate() {
-            avg[i] += v;
+    for emb in embeddings {
+        for (a, &v) in avg.iter_mut().zip(emb) {
+            *a += v;
         }
     }
-    let n = embeddings.len() as f32;
+    let n = embeddings.len().max(1) as f32;
     for v in &mut avg {
         *v /= n;
     }
+    normalize(&mut avg);
+    avg
+}
 
-    // L2-normalize so the profile lives on the unit sphere, matching
-    // the normalization applied during continuous training.
-    let norm: f32 = avg.iter().map(|x| x * x).sum::<f32>().sqrt();
+fn normalize(v: &mut [f32]) {
+    let norm: f32 = v.iter().map(|x| x * x).sum::<f32>().sqrt();
     if norm > 0.0 {
-        for v in &mut avg {
-            *v /= norm;
+        for x in v.iter_mut() {
+            *x /= norm;
         }
     }
+}
 
-    let profile = SpeakerProfile {
-        name: name.to_string(),
-        embedding: avg,
-    };
-
-    let profiles_dir = Config::expand_path(&config.speaker.profiles_dir);
-    let saved_path = profile.save(&profiles_dir)?;
-    tracing::info!("Speaker profile saved to {}", saved_path.display());
+/// Mean cosine similarity of the enrollment windows to the final centroid.
+///
+/// Close to 1.0 means the windows agree on a single, clean voice; low values
+/// point at noisy audio or several speakers in the recording.
+fn quality_score(embeddings: &[Vec<f32>], centroid: &[f32]) -> f32 {
+    if embeddings.is_empty() {
+        return 0.0;
+    }
+    embeddings
+        .iter()
+        .map(|e| cosine_similarity(e, centroid))
+        .sum::<f32>()
+        / embeddings.len() as f32
+}
 
-    Ok(())
+const fn quality_label(score: f32) -> &'static str {
+    if score >= 0.8 {
+        "good"
+    } else if score >= 0.65 {
+        "fair"
+    } else {
+        "poor — consider re-enrolling with cleaner audio"
+    }
 }
 
-const SPEAKER_MODEL_URL: &str =
-    "https://huggingface.co/Wespeaker/wespeaker-ecapa-tdnn512-LM/resolve/main/voxceleb_ECAPA512_LM.onnx";
+const SPEAKER_MODEL_URL: &str = "https://huggingface.co/Wespeaker/wespeaker-ecapa-tdnn512-LM/resolve/main/voxceleb_ECAPA512_LM.onnx";
 
 pub(crate) fn resolve_speaker_model(custom_path: Option<&str>) -> Result<std::path::PathBuf> {
     if let Some(path) = custom_path {
@@ -263,36 +678,157 @@ pub(crate) fn resolve_speaker_model(custom_path: Option<&str>) -> Result<std::pa
 mod tests {
     use super::*;
 
+    fn legacy_bytes(name: &str, embedding: &[f32]) -> Vec<u8> {
+        let mut data = Vec::new();
+        put_str(&mut data, name);
+        put_u32(&mut data, embedding.len());
+        put_floats(&mut data, embedding);
+        data
+    }
+
     #[test]
     fn profile_round_trip() {
-        let profile = SpeakerProfile {
-            name: "test_speaker".to_string(),
-            embedding: vec![0.1, 0.2, 0.3, -0.5, 1.0],
-        };
+        let mut profile = SpeakerProfile::new("test_speaker", "abc123", vec![0.6, 0.8, 0.0], 5);
+        profile
+            .add_embedding(&[0.0, 0.0, 1.0], 2)
+            .unwrap_or_else(|e| panic!("{e}"));
+        profile.locked = true;
 
-        let data = bincode_serialize(&profile);
-        let restored = bincode_deserialize(&data).unwrap_or_else(|e| panic!("{e}"));
+        let data = encode_profile(&profile);
+        assert!(data.starts_with(PROFILE_MAGIC));
+        let restored = decode_profile(&data).unwrap_or_else(|e| panic!("{e}"));
 
         assert_eq!(restored.name, profile.name);
-        assert_eq!(restored.embedding.len(), profile.embedding.len());
-        for (a, b) in restored.embedding.iter().zip(profile.embedding.iter()) {
-            assert!((a - b).abs() < 1e-6);
-        }
+        assert_eq!(restored.model_hash, "abc123");
+        assert_eq!(restored.anchor, profile.anchor);
+        assert_eq!(restored.centroids, profile.centroids);
+        assert_eq!(restored.sample_count(), 7);
+        assert!(restored.locked);
+        assert_eq!(
+            restored.created_at.timestamp(),
+            profile.created_at.timestamp()
+        );
+    }
+
+    #[test]
+    fn version_2_profile_is_unlocked() {
+        let mut profile = SpeakerProfile::new("v2", "h", vec![1.0, 0.0], 1);
+        profile.locked = true;
+        let mut data = encode_profile(&profile);
+
+        // Rewrite as version 2, which has no flags field.
+        data[4..8].copy_from_slice(&2u32.to_le_bytes());
+        let flags_at = 4 + 4 + (4 + 2) + (4 + 1) + 4 + 8 + 8;
+        data.drain(flags_at..flags_at + 4);
+
+        let restored = decode_profile(&data).unwrap_or_else(|e| panic!("{e}"));
+        assert!(!restored.locked);
+        assert_eq!(restored.anchor, vec![1.0, 0.0]);
+    }
+
+    #[test]
+    fn legacy_profile_is_migrated() {
+        let data = legacy_bytes("bob", &[0.1, 0.2, 0.3, -0.5, 1.0]);
+        let profile = decode_profile(&data).unwrap_or_else(|e| panic!("{e}"));
+
+        assert_eq!(profile.name, "bob");
+        assert!(profile.model_hash.is_empty());
+        assert_eq!(profile.dimension(), 5);
+        assert_eq!(profile.centroids.len(), 1);
+        assert_eq!(profile.centroids[0].embedding, profile.anchor);
+    }
+
+    #[test]
+    fn truncated_profile_is_rejected() {
+        let data = encode_profile(&SpeakerProfile::new("a", "h", vec![1.0, 0.0], 1));
+        assert!(decode_profile(&data[..data.len() - 2]).is_err());
+        assert!(decode_profile(&legacy_bytes("a", &[1.0])[..6]).is_err());
+    }
+
+    #[test]
+    fn similar_embeddings_merge_into_a_centroid() {
+        let mut profile = SpeakerProfile::new("a", "h", vec![1.0, 0.0, 0.0], 3);
+        profile
+            .add_embedding(&[0.95, 0.05, 0.0], 1)
+            .unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(profile.centroids.len(), 1);
+        assert_eq!(profile.centroids[0].sample_count, 4);
+        assert_eq!(profile.anchor, vec![1.0, 0.0, 0.0]);
+
+        profile
+            .add_embedding(&[0.0, 1.0, 0.0], 1)
+            .unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(profile.centroids.len(), 2);
+        assert!(profile.add_embedding(&[1.0, 0.0], 1).is_err());
     }
 
     #[test]
     fn profile_save_and_load() {
         let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
-        let profile = SpeakerProfile {
-            name: "alice".to_string(),
-            embedding: vec![1.0, 2.0, 3.0],
-        };
+        let profile = SpeakerProfile::new("alice", "h", vec![1.0, 2.0, 3.0], 1);
 
-        let path = profile.save(dir.path()).unwrap_or_else(|e| panic!("{e}"));
+        let path = profile
+            .save(dir.path(), &Vault::default())
+            .unwrap_or_else(|e| panic!("{e}"));
         assert!(path.exists());
 
-        let loaded = SpeakerProfile::load(&path).unwrap_or_else(|e| panic!("{e}"));
+        let loaded =
+            SpeakerProfile::load(&path, &Vault::default()).unwrap_or_else(|e| panic!("{e}"));
         assert_eq!(loaded.name, "alice");
-        assert_eq!(loaded.embedding.len(), 3);
+        assert_eq!(loaded.dimension(), 3);
+
+        let vault = Vault::with_key(&[5; 32]);
+        let path = profile
+            .save(dir.path(), &vault)
+            .unwrap_or_else(|e| panic!("{e}"));
+        assert!(SpeakerProfile::load(&path, &Vault::default()).is_err());
+        let loaded = SpeakerProfile::load(&path, &vault).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(loaded.name, "alice");
+    }
+
+    #[test]
+    fn speech_windows_skip_silence() {
+        let len = WINDOW_SAMPLES * 3;
+        let frames_per_window = WINDOW_SAMPLES / VAD_FRAME_SAMPLES;
+        let mut probs = vec![0.9; frames_per_window];
+        probs.extend(vec![0.1; frames_per_window]);
+        probs.extend(vec![0.9; frames_per_window]);
+
+        let windows = speech_windows(len, &probs);
+        assert_eq!(
+            windows,
+            vec![0..WINDOW_SAMPLES, WINDOW_SAMPLES * 2..WINDOW_SAMPLES * 3]
+        );
+    }
+
+    #[test]
+    fn speech_windows_drop_short_tail() {
+        let len = WINDOW_SAMPLES + MIN_WINDOW_SAMPLES / 2;
+        let probs = vec![0.9; len / VAD_FRAME_SAMPLES];
+        assert_eq!(speech_windows(len, &probs), vec![0..WINDOW_SAMPLES]);
+    }
+
+    #[test]
+    fn outliers_are_rejected() {
+        let mut embeddings = vec![
+            vec![1.0, 0.0, 0.0],
+            vec![0.98, 0.02, 0.0],
+            vec![0.99, 0.0, 0.01],
+            vec![0.97, 0.01, 0.02],
+            vec![1.0, 0.01, 0.0],
+        ];
+        embeddings.push(vec![0.0, 0.0, 1.0]);
+
+        let (kept, rejected) = reject_outliers(embeddings);
+        assert_eq!(rejected, 1);
+        assert_eq!(kept.len(), 5);
+        assert!(quality_score(&kept, &average_embedding(&kept)) > 0.99);
+    }
+
+    #[test]
+    fn too_few_windows_keep_everything() {
+        let (kept, rejected) = reject_outliers(vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+        assert_eq!(rejected, 0);
+        assert_eq!(kept.len(), 2);
     }
 }
@@ -2,9 +2,12 @@ use std::path::{Path, PathBuf};
 
 use ort::session::Session;
 
-use crate::config::SpeakerConfig;
+use crate::config::{SpeakerConfig, UnknownPolicy};
 use crate::error::Result;
+use crate::output::calendar::Calendar;
+use crate::vault::Vault;
 
+use super::cluster::SpeakerClusters;
 use super::enroll::SpeakerProfile;
 use super::{cosine_similarity, extract_embedding};
 
@@ -17,6 +20,11 @@ const EMA_ALPHA: f32 = 0.01;
 /// marginal matches don't gradually drag the profile off-center.
 const EMA_UPDATE_THRESHOLD: f32 = 0.85;
 
+/// Continuous training never moves a centroid further than this from the
+/// speaker's enrollment anchor, so a run of misidentifications can't slowly
+/// turn one speaker's profile into another's.
+const ANCHOR_MIN_SIMILARITY: f32 = 0.7;
+
 /// Save updated profiles to disk every N successful identifications.
 const SAVE_INTERVAL: u32 = 10;
 
@@ -26,14 +34,20 @@ const SEGMENT_SAMPLES: usize = 16000 * 3;
 /// Minimum usable segment length (1 second at 16 kHz).
 const MIN_SEGMENT_SAMPLES: usize = 16000;
 
+/// Audio sample rate expected by the embedding model.
+const SAMPLE_RATE: f32 = 16000.0;
+
 /// Speaker identifier: holds loaded profiles and the embedding model session.
 pub struct SpeakerIdentifier {
     profiles: Vec<SpeakerProfile>,
     session: Session,
     min_confidence: f32,
-    filter_unknown: bool,
+    unknown_policy: UnknownPolicy,
     profiles_dir: PathBuf,
+    vault: Vault,
     updates_since_save: u32,
+    /// Anonymous clusters for unmatched voices (when `cluster_unknown` is on).
+    clusters: Option<SpeakerClusters>,
 }
 
 /// Result of a speaker identification attempt.
@@ -41,24 +55,47 @@ pub struct SpeakerIdentifier {
 pub struct SpeakerMatch {
     pub name: Option<String>,
     pub confidence: f32,
+    /// The voice matched no enrolled profile and `unknown_policy` is
+    /// `redact`: the segment's words must not be written.
+    pub redact: bool,
 }
 
 impl SpeakerIdentifier {
-    pub fn new(config: &SpeakerConfig, gpu: bool) -> Result<Self> {
+    pub fn new(
+        config: &SpeakerConfig,
+        gpu: bool,
+        calendar: Calendar,
+        vault: Vault,
+    ) -> Result<Self> {
         let model_path = super::enroll::resolve_speaker_model(config.model_path.as_deref())?;
         let session = super::load_embedding_model(&model_path, gpu)?;
+        let model_hash = super::model_fingerprint(&model_path)?;
         let profiles_dir = crate::config::Config::expand_path(&config.profiles_dir);
-        let profiles = load_all_profiles(&profiles_dir)?;
+        let profiles = load_all_profiles(&profiles_dir, &vault, &model_hash)?;
 
         tracing::info!("loaded {} speaker profiles", profiles.len());
 
+        let clusters = if config.cluster_unknown {
+            Some(SpeakerClusters::new(
+                &profiles_dir,
+                config.cluster_threshold,
+                model_hash,
+                calendar,
+                vault.clone(),
+            )?)
+        } else {
+            None
+        };
+
         Ok(Self {
             profiles,
             session,
             min_confidence: config.min_confidence,
-            filter_unknown: config.filter_unknown,
+            unknown_policy: config.unknown_policy(),
             profiles_dir,
+            vault,
             updates_since_save: 0,
+            clusters,
         })
     }
 
@@ -72,18 +109,26 @@ impl SpeakerIdentifier {
     /// embedding is refined using an exponential moving average of the new
     /// embedding. Updated profiles are saved to disk periodically.
     ///
-    /// Returns `None` if `filter_unknown` is true and no speaker matches.
+    /// Voices that match no profile are handled according to
+    /// `unknown_policy`: dropped, flagged for redaction, or kept and assigned
+    /// to an anonymous cluster (`Speaker A`, `Speaker B`, ...) when
+    /// `cluster_unknown` is enabled.  Other people's voices are never
+    /// clustered unless they are kept.
+    ///
+    /// Returns `None` if `unknown_policy` is `drop` and no speaker matches.
     pub fn identify(&mut self, samples: &[f32]) -> Result<Option<SpeakerMatch>> {
-        if self.profiles.is_empty() {
+        if self.profiles.is_empty() && self.clusters.is_none() {
             return Ok(Some(SpeakerMatch {
                 name: None,
                 confidence: 0.0,
+                redact: false,
             }));
         }
 
-        let mut best_idx = 0;
+        let mut best_idx = (0, 0);
         let mut best_score = f32::NEG_INFINITY;
         let mut best_embedding: Option<Vec<f32>> = None;
+        let mut windows: Vec<Vec<f32>> = Vec::new();
 
         // Split into 3-second segments (same window used during enrollment)
         // and find the segment+profile pair with the highest score.
@@ -97,56 +142,43 @@ impl SpeakerIdentifier {
             let embedding = extract_embedding(&mut self.session, segment)?;
 
             for (i, profile) in self.profiles.iter().enumerate() {
-                let score = cosine_similarity(&embedding, &profile.embedding);
-                if score > best_score {
+                if let Some((c, score)) = profile.best_centroid(&embedding)
+                    && score > best_score
+                {
                     best_score = score;
-                    best_idx = i;
+                    best_idx = (i, c);
                     best_embedding = Some(embedding.clone());
                 }
             }
+            windows.push(embedding);
         }
 
         // Fallback: if the audio was too short to segment, use it directly.
-        if best_embedding.is_none() {
+        if windows.is_empty() {
             let embedding = extract_embedding(&mut self.session, samples)?;
             for (i, profile) in self.profiles.iter().enumerate() {
-                let score = cosine_similarity(&embedding, &profile.embedding);
-                if score > best_score {
+                if let Some((c, score)) = profile.best_centroid(&embedding)
+                    && score > best_score
+                {
                     best_score = score;
-                    best_idx = i;
+                    best_idx = (i, c);
                     best_embedding = Some(embedding.clone());
                 }
             }
+            windows.push(embedding);
         }
 
-        let Some(embedding) = best_embedding else {
-            return Ok(Some(SpeakerMatch { name: None, confidence: 0.0 }));
-        };
-
-        if best_score >= self.min_confidence {
-            let name = self.profiles[best_idx].name.clone();
+        if let Some(embedding) = best_embedding
+            && best_score >= self.min_confidence
+        {
+            let (profile_idx, centroid_idx) = best_idx;
+            let name = self.profiles[profile_idx].name.clone();
 
             // Only refine the profile when the match is strong enough to
             // avoid drifting the embedding on marginal identifications.
-            if best_score >= EMA_UPDATE_THRESHOLD {
-                let profile = &mut self.profiles[best_idx];
-                for (stored, &new) in profile.embedding.iter_mut().zip(embedding.iter()) {
-                    *stored = (1.0 - EMA_ALPHA).mul_add(*stored, EMA_ALPHA * new);
-                }
-                // Re-normalize to the unit sphere so cosine similarity stays
-                // well-behaved over many updates.
-                let norm: f32 = profile
-                    .embedding
-                    .iter()
-                    .map(|x| x * x)
-                    .sum::<f32>()
-                    .sqrt();
-                if norm > 0.0 {
-                    for v in &mut profile.embedding {
-                        *v /= norm;
-                    }
-                }
-
+            if best_score >= EMA_UPDATE_THRESHOLD
+                && refine_centroid(&mut self.profiles[profile_idx], centroid_idx, &embedding)
+            {
                 self.updates_since_save += 1;
                 if self.updates_since_save >= SAVE_INTERVAL {
                     self.save_profiles();
@@ -154,39 +186,176 @@ impl SpeakerIdentifier {
                 }
             }
 
-            Ok(Some(SpeakerMatch {
+            return Ok(Some(SpeakerMatch {
                 name: Some(name),
                 confidence: best_score,
-            }))
-        } else if self.filter_unknown {
-            Ok(None)
+                redact: false,
+            }));
+        }
+
+        let confidence = if best_score.is_finite() {
+            best_score
         } else {
-            Ok(Some(SpeakerMatch {
-                name: None,
-                confidence: best_score,
-            }))
+            0.0
+        };
+
+        // Without any enrolled profiles there is nothing to filter against.
+        if !self.profiles.is_empty() {
+            match self.unknown_policy {
+                UnknownPolicy::Drop => return Ok(None),
+                UnknownPolicy::Redact => {
+                    return Ok(Some(SpeakerMatch {
+                        name: None,
+                        confidence,
+                        redact: true,
+                    }));
+                }
+                UnknownPolicy::Keep => {}
+            }
         }
+
+        let name = self
+            .clusters
+            .as_mut()
+            .map(|clusters| clusters.assign(&mean_embedding(&windows)));
+
+        Ok(Some(SpeakerMatch {
+            name,
+            confidence,
+            redact: false,
+        }))
+    }
+
+    /// Identify the speaker of a single transcription segment within a chunk.
+    ///
+    /// `offset_secs` and `duration_secs` locate the segment inside `samples`
+    /// (16kHz mono).  Segments shorter than one second are widened around
+    /// their centre so the embedding model has enough audio to work with.
+    ///
+    /// Returns `None` if `unknown_policy` is `drop` and no speaker matches.
+    pub fn identify_segment(
+        &mut self,
+        samples: &[f32],
+        offset_secs: f32,
+        duration_secs: f32,
+    ) -> Result<Option<SpeakerMatch>> {
+        let (start, end) = segment_bounds(samples.len(), offset_secs, duration_secs);
+        self.identify(&samples[start..end])
     }
 
     /// Save all profiles that have been updated back to disk.
+    ///
+    /// Changes made with `hoover speakers` while recording win: profiles that
+    /// were removed, renamed or merged away are not recreated, and profiles
+    /// locked on disk are not overwritten.
     fn save_profiles(&self) {
         for profile in &self.profiles {
-            if let Err(e) = profile.save(&self.profiles_dir) {
+            let path = self.profiles_dir.join(format!("{}.bin", profile.name));
+            match SpeakerProfile::load(&path, &self.vault) {
+                Ok(on_disk) if !on_disk.locked => {}
+                _ => continue,
+            }
+            if let Err(e) = profile.save(&self.profiles_dir, &self.vault) {
                 tracing::warn!("failed to save profile '{}': {e}", profile.name);
             }
         }
         tracing::debug!("saved {} speaker profiles", self.profiles.len());
     }
 
-    /// Flush any pending profile updates to disk (for graceful shutdown).
-    pub fn flush(&self) {
+    /// Flush any pending profile and cluster updates to disk (for graceful
+    /// shutdown).
+    pub fn flush(&mut self) {
         if self.updates_since_save > 0 {
             self.save_profiles();
         }
+        if let Some(clusters) = &mut self.clusters {
+            clusters.flush();
+        }
+    }
+}
+
+/// Blend `embedding` into one of the profile's centroids with an exponential
+/// moving average.  Locked profiles are never changed, and the update is
+/// discarded when it would pull the centroid too far from the enrollment
+/// anchor.  Returns whether the profile changed.
+fn refine_centroid(profile: &mut SpeakerProfile, centroid_idx: usize, embedding: &[f32]) -> bool {
+    if profile.locked {
+        return false;
+    }
+
+    let mut updated = profile.centroids[centroid_idx].embedding.clone();
+    for (stored, &new) in updated.iter_mut().zip(embedding) {
+        *stored = (1.0 - EMA_ALPHA).mul_add(*stored, EMA_ALPHA * new);
+    }
+    // Re-normalize to the unit sphere so cosine similarity stays
+    // well-behaved over many updates.
+    let norm: f32 = updated.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        for v in &mut updated {
+            *v /= norm;
+        }
+    }
+
+    if cosine_similarity(&updated, &profile.anchor) < ANCHOR_MIN_SIMILARITY {
+        return false;
+    }
+
+    let centroid = &mut profile.centroids[centroid_idx];
+    centroid.embedding = updated;
+    centroid.sample_count += 1;
+    profile.updated_at = chrono::Utc::now();
+    true
+}
+
+/// Average a set of embeddings (all of the same dimension).
+fn mean_embedding(embeddings: &[Vec<f32>]) -> Vec<f32> {
+    let Some(first) = embeddings.first() else {
+        return Vec::new();
+    };
+    let mut mean = vec![0.0f32; first.len()];
+    for emb in embeddings {
+        for (m, &v) in mean.iter_mut().zip(emb) {
+            *m += v;
+        }
+    }
+    let n = embeddings.len() as f32;
+    for m in &mut mean {
+        *m /= n;
+    }
+    mean
+}
+
+/// Convert a segment's offset and duration into a sample range, widening it
+/// to at least `MIN_SEGMENT_SAMPLES` (clamped to the available audio).
+fn segment_bounds(len: usize, offset_secs: f32, duration_secs: f32) -> (usize, usize) {
+    if len <= MIN_SEGMENT_SAMPLES {
+        return (0, len);
+    }
+
+    let start = ((offset_secs.max(0.0) * SAMPLE_RATE) as usize).min(len);
+    let end = (start + (duration_secs.max(0.0) * SAMPLE_RATE) as usize).min(len);
+    if end - start >= MIN_SEGMENT_SAMPLES {
+        return (start, end);
     }
+
+    let centre = usize::midpoint(start, end);
+    let start = centre
+        .saturating_sub(MIN_SEGMENT_SAMPLES / 2)
+        .min(len - MIN_SEGMENT_SAMPLES);
+    (start, start + MIN_SEGMENT_SAMPLES)
 }
 
-fn load_all_profiles(dir: &Path) -> Result<Vec<SpeakerProfile>> {
+/// Load every profile compatible with the speaker model `model_hash`.
+///
+/// Profiles from the unversioned format carry no model hash; they are assumed
+/// to come from the current model and rewritten in the current format.
+/// Profiles created with a different model are skipped, since their
+/// similarities would be meaningless, and must be re-enrolled.
+pub(super) fn load_all_profiles(
+    dir: &Path,
+    vault: &Vault,
+    model_hash: &str,
+) -> Result<Vec<SpeakerProfile>> {
     if !dir.exists() {
         return Ok(Vec::new());
     }
@@ -195,13 +364,124 @@ fn load_all_profiles(dir: &Path) -> Result<Vec<SpeakerProfile>> {
     for entry in std::fs::read_dir(dir)? {
         let entry = entry?;
         let path = entry.path();
-        if path.extension().and_then(|e| e.to_str()) == Some("bin") {
-            match SpeakerProfile::load(&path) {
-                Ok(profile) => profiles.push(profile),
-                Err(e) => tracing::warn!("failed to load speaker profile {}: {e}", path.display()),
+        if path.extension().and_then(|e| e.to_str()) != Some("bin") {
+            continue;
+        }
+
+        let mut profile = match SpeakerProfile::load(&path, vault) {
+            Ok(profile) => profile,
+            Err(e) => {
+                tracing::warn!("failed to load speaker profile {}: {e}", path.display());
+                continue;
+            }
+        };
+
+        if profile.model_hash.is_empty() {
+            profile.model_hash = model_hash.to_string();
+            match profile.save(dir, vault) {
+                Ok(_) => tracing::info!("migrated speaker profile '{}'", profile.name),
+                Err(e) => tracing::warn!("failed to migrate profile '{}': {e}", profile.name),
             }
+        } else if profile.model_hash != model_hash {
+            tracing::warn!(
+                "speaker profile '{}' was created with a different speaker model; \
+                 skipping it (re-enroll with `hoover enroll {}`)",
+                profile.name,
+                profile.name
+            );
+            continue;
         }
+
+        profiles.push(profile);
     }
 
     Ok(profiles)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segment_bounds_uses_offset_and_duration() {
+        let len = 16000 * 10;
+        assert_eq!(segment_bounds(len, 2.0, 3.0), (32000, 80000));
+    }
+
+    #[test]
+    fn segment_bounds_widens_short_segments() {
+        let len = 16000 * 10;
+        let (start, end) = segment_bounds(len, 5.0, 0.2);
+        assert_eq!(end - start, MIN_SEGMENT_SAMPLES);
+        assert!(start <= 80000 && end >= 83200);
+    }
+
+    #[test]
+    fn segment_bounds_clamps_to_chunk_end() {
+        let len = 16000 * 10;
+        let tail = (len - MIN_SEGMENT_SAMPLES, len);
+        assert_eq!(segment_bounds(len, 9.9, 0.5), tail);
+        assert_eq!(segment_bounds(len, 12.0, 1.0), tail);
+    }
+
+    #[test]
+    fn mean_embedding_averages_windows() {
+        let mean = mean_embedding(&[vec![1.0, 0.0], vec![0.0, 1.0]]);
+        assert_eq!(mean.len(), 2);
+        assert!((mean[0] - 0.5).abs() < 1e-6);
+        assert!((mean[1] - 0.5).abs() < 1e-6);
+        assert!(mean_embedding(&[]).is_empty());
+    }
+
+    #[test]
+    fn refine_centroid_respects_anchor() {
+        let mut profile = SpeakerProfile::new("a", "h", vec![1.0, 0.0], 1);
+        assert!(refine_centroid(&mut profile, 0, &[0.9, 0.1]));
+        assert_eq!(profile.centroids[0].sample_count, 2);
+        assert_eq!(profile.anchor, vec![1.0, 0.0]);
+
+        profile.centroids[0].embedding = vec![0.7, 0.7141];
+        let before = profile.centroids[0].clone();
+        assert!(!refine_centroid(&mut profile, 0, &[0.0, 1.0]));
+        assert_eq!(profile.centroids[0], before);
+    }
+
+    #[test]
+    fn refine_centroid_skips_locked_profiles() {
+        let mut profile = SpeakerProfile::new("a", "h", vec![1.0, 0.0], 1);
+        profile.locked = true;
+        assert!(!refine_centroid(&mut profile, 0, &[0.9, 0.1]));
+        assert_eq!(profile.centroids[0].embedding, vec![1.0, 0.0]);
+    }
+
+    #[test]
+    fn load_all_profiles_migrates_and_skips_mismatches() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        SpeakerProfile::new("current", "model-a", vec![1.0, 0.0], 1)
+            .save(dir.path(), &Vault::default())
+            .unwrap_or_else(|e| panic!("{e}"));
+        SpeakerProfile::new("legacy", "", vec![0.0, 1.0], 0)
+            .save(dir.path(), &Vault::default())
+            .unwrap_or_else(|e| panic!("{e}"));
+        SpeakerProfile::new("other", "model-b", vec![1.0, 0.0], 1)
+            .save(dir.path(), &Vault::default())
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        let mut names: Vec<String> = load_all_profiles(dir.path(), &Vault::default(), "model-a")
+            .unwrap_or_else(|e| panic!("{e}"))
+            .into_iter()
+            .map(|p| p.name)
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["current", "legacy"]);
+
+        let migrated = SpeakerProfile::load(&dir.path().join("legacy.bin"), &Vault::default())
+            .unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(migrated.model_hash, "model-a");
+    }
+
+    #[test]
+    fn segment_bounds_short_chunk_uses_everything() {
+        assert_eq!(segment_bounds(8000, 0.1, 0.1), (0, 8000));
+    }
+}
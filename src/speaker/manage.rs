@@ -0,0 +1,311 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+
+use crate::error::{HooverError, Result};
+use crate::vault::Vault;
+
+use super::enroll::{Centroid, SpeakerProfile};
+
+/// Identifies exported profile files.
+const EXPORT_FORMAT: &str = "hoover-speaker-profile";
+
+/// Version of the export format.
+const EXPORT_VERSION: u32 = 1;
+
+/// Portable JSON representation of a speaker profile, used to move profiles
+/// between machines.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct ExportedProfile {
+    format: String,
+    version: u32,
+    name: String,
+    model_hash: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    locked: bool,
+    anchor: Vec<f32>,
+    centroids: Vec<Centroid>,
+}
+
+fn profile_path(profiles_dir: &Path, name: &str) -> PathBuf {
+    profiles_dir.join(format!("{name}.bin"))
+}
+
+fn load_named(profiles_dir: &Path, vault: &Vault, name: &str) -> Result<SpeakerProfile> {
+    let path = profile_path(profiles_dir, name);
+    if !path.exists() {
+        return Err(HooverError::Speaker(format!(
+            "no profile found for '{name}'"
+        )));
+    }
+    SpeakerProfile::load(&path, vault)
+}
+
+/// Profile names become file names, so keep them to a single path component.
+pub(crate) fn validate_name(name: &str) -> Result<()> {
+    if name.trim().is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        return Err(HooverError::Speaker(format!(
+            "invalid speaker name '{name}'"
+        )));
+    }
+    Ok(())
+}
+
+fn ensure_absent(profiles_dir: &Path, name: &str) -> Result<()> {
+    if profile_path(profiles_dir, name).exists() {
+        return Err(HooverError::Speaker(format!(
+            "a profile named '{name}' already exists"
+        )));
+    }
+    Ok(())
+}
+
+/// Rename a profile, updating the name stored inside it as well as the file.
+pub fn rename_profile(profiles_dir: &Path, vault: &Vault, from: &str, to: &str) -> Result<()> {
+    validate_name(to)?;
+    let mut profile = load_named(profiles_dir, vault, from)?;
+    ensure_absent(profiles_dir, to)?;
+
+    profile.name = to.to_string();
+    profile.updated_at = Utc::now();
+    profile.save(profiles_dir, vault)?;
+    fs::remove_file(profile_path(profiles_dir, from))?;
+    Ok(())
+}
+
+/// Merge the profile `from` into `into` and delete `from`.
+///
+/// Each of `from`'s centroids is added to `into` (merged into a close
+/// centroid or kept separately); `into` keeps its own anchor.
+pub fn merge_profiles(
+    profiles_dir: &Path,
+    vault: &Vault,
+    from: &str,
+    into: &str,
+) -> Result<SpeakerProfile> {
+    if from == into {
+        return Err(HooverError::Speaker(format!(
+            "cannot merge '{from}' into itself"
+        )));
+    }
+
+    let source = load_named(profiles_dir, vault, from)?;
+    let mut target = load_named(profiles_dir, vault, into)?;
+
+    if !source.model_hash.is_empty()
+        && !target.model_hash.is_empty()
+        && source.model_hash != target.model_hash
+    {
+        return Err(HooverError::Speaker(format!(
+            "'{from}' and '{into}' were created with different speaker models"
+        )));
+    }
+    if target.model_hash.is_empty() {
+        target.model_hash = source.model_hash;
+    }
+
+    for centroid in &source.centroids {
+        target.add_embedding(&centroid.embedding, centroid.sample_count)?;
+    }
+    target.save(profiles_dir, vault)?;
+    fs::remove_file(profile_path(profiles_dir, from))?;
+    Ok(target)
+}
+
+/// Lock or unlock a profile against continuous training.
+pub fn set_locked(profiles_dir: &Path, vault: &Vault, name: &str, locked: bool) -> Result<()> {
+    let mut profile = load_named(profiles_dir, vault, name)?;
+    profile.locked = locked;
+    profile.updated_at = Utc::now();
+    profile.save(profiles_dir, vault)?;
+    Ok(())
+}
+
+/// Write a profile to `path` as portable JSON.
+pub fn export_profile(profiles_dir: &Path, vault: &Vault, name: &str, path: &Path) -> Result<()> {
+    let profile = load_named(profiles_dir, vault, name)?;
+    let exported = ExportedProfile {
+        format: EXPORT_FORMAT.to_string(),
+        version: EXPORT_VERSION,
+        name: profile.name,
+        model_hash: profile.model_hash,
+        created_at: profile.created_at,
+        updated_at: profile.updated_at,
+        locked: profile.locked,
+        anchor: profile.anchor,
+        centroids: profile.centroids,
+    };
+
+    let data = serde_json::to_string_pretty(&exported)
+        .map_err(|e| HooverError::Speaker(format!("failed to serialize profile: {e}")))?;
+    fs::write(path, data)?;
+    Ok(())
+}
+
+/// Import a profile exported with [`export_profile`].
+///
+/// `name` overrides the name stored in the file.  An existing profile with
+/// the same name is only replaced when `force` is set.  Returns the name the
+/// profile was saved under.
+pub fn import_profile(
+    profiles_dir: &Path,
+    vault: &Vault,
+    path: &Path,
+    name: Option<&str>,
+    force: bool,
+) -> Result<String> {
+    let data = fs::read_to_string(path)?;
+    let exported: ExportedProfile = serde_json::from_str(&data).map_err(|e| {
+        HooverError::Speaker(format!("invalid profile file {}: {e}", path.display()))
+    })?;
+
+    if exported.format != EXPORT_FORMAT {
+        return Err(HooverError::Speaker(format!(
+            "{} is not a hoover speaker profile",
+            path.display()
+        )));
+    }
+    if exported.version > EXPORT_VERSION {
+        return Err(HooverError::Speaker(format!(
+            "unsupported profile export version {} (expected at most {EXPORT_VERSION})",
+            exported.version
+        )));
+    }
+
+    let dimension = exported.anchor.len();
+    if dimension == 0
+        || exported.centroids.is_empty()
+        || exported
+            .centroids
+            .iter()
+            .any(|c| c.embedding.len() != dimension)
+    {
+        return Err(HooverError::Speaker(format!(
+            "profile file {} has missing or inconsistent embeddings",
+            path.display()
+        )));
+    }
+
+    let name = name.unwrap_or(&exported.name).to_string();
+    validate_name(&name)?;
+    if !force {
+        ensure_absent(profiles_dir, &name)?;
+    }
+
+    let profile = SpeakerProfile {
+        name: name.clone(),
+        model_hash: exported.model_hash,
+        created_at: exported.created_at,
+        updated_at: exported.updated_at,
+        anchor: exported.anchor,
+        centroids: exported.centroids,
+        locked: exported.locked,
+    };
+    profile.save(profiles_dir, vault)?;
+    Ok(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn save(dir: &Path, name: &str, embedding: Vec<f32>) {
+        SpeakerProfile::new(name, "h", embedding, 1)
+            .save(dir, &Vault::default())
+            .unwrap_or_else(|e| panic!("{e}"));
+    }
+
+    #[test]
+    fn rename_updates_embedded_name() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        save(dir.path(), "alice", vec![1.0, 0.0]);
+        save(dir.path(), "bob", vec![0.0, 1.0]);
+
+        rename_profile(dir.path(), &Vault::default(), "alice", "Alice")
+            .unwrap_or_else(|e| panic!("{e}"));
+        assert!(!dir.path().join("alice.bin").exists());
+        let renamed = SpeakerProfile::load(&dir.path().join("Alice.bin"), &Vault::default())
+            .unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(renamed.name, "Alice");
+
+        assert!(rename_profile(dir.path(), &Vault::default(), "Alice", "bob").is_err());
+        assert!(rename_profile(dir.path(), &Vault::default(), "Alice", "../evil").is_err());
+    }
+
+    #[test]
+    fn merge_combines_centroids_and_removes_source() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        save(dir.path(), "alice", vec![1.0, 0.0]);
+        save(dir.path(), "alice-laptop", vec![0.0, 1.0]);
+
+        let merged = merge_profiles(dir.path(), &Vault::default(), "alice-laptop", "alice")
+            .unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(merged.centroids.len(), 2);
+        assert_eq!(merged.anchor, vec![1.0, 0.0]);
+        assert!(!dir.path().join("alice-laptop.bin").exists());
+        assert!(merge_profiles(dir.path(), &Vault::default(), "alice", "alice").is_err());
+    }
+
+    #[test]
+    fn lock_persists() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        save(dir.path(), "alice", vec![1.0, 0.0]);
+
+        set_locked(dir.path(), &Vault::default(), "alice", true).unwrap_or_else(|e| panic!("{e}"));
+        let profile = SpeakerProfile::load(&dir.path().join("alice.bin"), &Vault::default())
+            .unwrap_or_else(|e| panic!("{e}"));
+        assert!(profile.locked);
+        assert!(set_locked(dir.path(), &Vault::default(), "nobody", true).is_err());
+    }
+
+    #[test]
+    fn export_import_round_trip() {
+        let source = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let target = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        save(source.path(), "alice", vec![0.6, 0.8]);
+        set_locked(source.path(), &Vault::default(), "alice", true)
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        let file = source.path().join("alice.json");
+        export_profile(source.path(), &Vault::default(), "alice", &file)
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        let name = import_profile(target.path(), &Vault::default(), &file, None, false)
+            .unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(name, "alice");
+        let imported = SpeakerProfile::load(&target.path().join("alice.bin"), &Vault::default())
+            .unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(imported.anchor, vec![0.6, 0.8]);
+        assert_eq!(imported.model_hash, "h");
+        assert!(imported.locked);
+
+        assert!(import_profile(target.path(), &Vault::default(), &file, None, false).is_err());
+        import_profile(target.path(), &Vault::default(), &file, None, true)
+            .unwrap_or_else(|e| panic!("{e}"));
+        let renamed = import_profile(
+            target.path(),
+            &Vault::default(),
+            &file,
+            Some("Alice Smith"),
+            false,
+        )
+        .unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(renamed, "Alice Smith");
+    }
+
+    #[test]
+    fn import_rejects_other_files() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let file = dir.path().join("other.json");
+        fs::write(
+            &file,
+            r#"{"format":"something-else","version":1,"name":"x","model_hash":"",
+                "created_at":"2026-01-01T00:00:00Z","updated_at":"2026-01-01T00:00:00Z",
+                "locked":false,"anchor":[1.0],"centroids":[{"embedding":[1.0],"sample_count":1}]}"#,
+        )
+        .unwrap_or_else(|e| panic!("{e}"));
+        assert!(import_profile(dir.path(), &Vault::default(), &file, None, false).is_err());
+    }
+}
@@ -1,12 +1,127 @@
+pub mod calibrate;
+pub mod cluster;
 pub mod enroll;
 pub mod identify;
+pub mod manage;
 
+use std::fmt::Write as _;
+use std::io::Read;
 use std::path::Path;
 
 use mel_spec::fbank::{Fbank, FbankConfig};
 use ort::session::Session;
+use sha2::{Digest, Sha256};
 
 use crate::error::{HooverError, Result};
+use crate::stt::TranscriptionSegment;
+
+/// A transcription segment attributed to a speaker.
+#[derive(Debug, Clone)]
+pub struct AttributedSegment {
+    pub segment: TranscriptionSegment,
+    /// Name of the matched speaker, or `None` when unknown.
+    pub speaker: Option<String>,
+    /// Cosine similarity of the best match, when identification ran.
+    pub speaker_confidence: Option<f32>,
+    /// The words were replaced by a placeholder (`unknown_policy: redact`).
+    pub redacted: bool,
+}
+
+impl AttributedSegment {
+    /// Wrap a segment without any speaker information.
+    #[must_use]
+    pub const fn unattributed(segment: TranscriptionSegment) -> Self {
+        Self {
+            segment,
+            speaker: None,
+            speaker_confidence: None,
+            redacted: false,
+        }
+    }
+
+    /// Replace a segment from a non-enrolled speaker with a placeholder.
+    ///
+    /// The transcribed text is discarded here, before the segment leaves the
+    /// STT thread, so no writer, sink or network path ever sees it.
+    #[must_use]
+    pub fn redacted(mut segment: TranscriptionSegment) -> Self {
+        segment.text = redaction_placeholder(segment.duration_secs);
+        segment.confidence = None;
+        Self {
+            segment,
+            speaker: None,
+            speaker_confidence: None,
+            redacted: true,
+        }
+    }
+}
+
+/// Placeholder written instead of a redacted segment's words.
+#[must_use]
+pub fn redaction_placeholder(duration_secs: f32) -> String {
+    format!(
+        "_[other speaker, {}s]_",
+        duration_secs.max(0.0).round() as u64
+    )
+}
+
+/// Whether `text` is a placeholder written by [`redaction_placeholder`].
+#[must_use]
+pub fn is_redaction_placeholder(text: &str) -> bool {
+    text.starts_with("_[other speaker, ") && text.ends_with("s]_")
+}
+
+/// Merge adjacent segments attributed to the same enrolled speaker into a
+/// single turn.
+///
+/// Unknown segments are never merged, since two unmatched segments may come
+/// from different people.  Adjacent redacted segments are collapsed into one
+/// placeholder covering their combined duration.
+#[must_use]
+pub fn merge_turns(segments: Vec<AttributedSegment>) -> Vec<AttributedSegment> {
+    let mut turns: Vec<AttributedSegment> = Vec::with_capacity(segments.len());
+
+    for next in segments {
+        if let Some(prev) = turns.last_mut()
+            && prev.redacted
+            && next.redacted
+        {
+            extend_to(&mut prev.segment, &next.segment);
+            prev.segment.text = redaction_placeholder(prev.segment.duration_secs);
+            continue;
+        }
+
+        if let Some(prev) = turns.last_mut()
+            && prev.speaker.is_some()
+            && prev.speaker == next.speaker
+        {
+            extend_to(&mut prev.segment, &next.segment);
+            prev.segment.text = format!(
+                "{} {}",
+                prev.segment.text.trim_end(),
+                next.segment.text.trim_start()
+            );
+            prev.segment.confidence = match (prev.segment.confidence, next.segment.confidence) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                _ => None,
+            };
+            prev.speaker_confidence = match (prev.speaker_confidence, next.speaker_confidence) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+            continue;
+        }
+        turns.push(next);
+    }
+
+    turns
+}
+
+/// Extend `segment`'s duration to the end of `next`.
+fn extend_to(segment: &mut TranscriptionSegment, next: &TranscriptionSegment) {
+    let end = next.timestamp + chrono::Duration::milliseconds((next.duration_secs * 1000.0) as i64);
+    segment.duration_secs = (end - segment.timestamp).num_milliseconds() as f32 / 1000.0;
+}
 
 /// Load the ONNX speaker embedding model.
 pub fn load_embedding_model(model_path: &Path, gpu: bool) -> Result<Session> {
@@ -45,6 +160,27 @@ pub fn load_embedding_model(model_path: &Path, gpu: bool) -> Result<Session> {
         .map_err(|e| HooverError::Speaker(format!("failed to load speaker embedding model: {e}")))
 }
 
+/// Hex SHA-256 of a speaker model file, stored in profiles so embeddings from
+/// different models are never compared.
+pub fn model_fingerprint(model_path: &Path) -> Result<String> {
+    let mut file = std::fs::File::open(model_path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+
+    let mut hex = String::with_capacity(64);
+    for byte in hasher.finalize() {
+        let _ = write!(hex, "{byte:02x}");
+    }
+    Ok(hex)
+}
+
 /// Extract a speaker embedding from 16kHz mono audio samples.
 ///
 /// Computes 80-dim log Mel filterbank (Fbank) features from the raw audio, then
@@ -119,6 +255,91 @@ pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::Utc;
+
+    fn attributed(text: &str, offset_secs: i64, speaker: Option<&str>) -> AttributedSegment {
+        AttributedSegment {
+            segment: TranscriptionSegment {
+                text: text.to_string(),
+                timestamp: chrono::DateTime::<Utc>::UNIX_EPOCH
+                    + chrono::Duration::seconds(offset_secs),
+                duration_secs: 2.0,
+                confidence: None,
+            },
+            speaker: speaker.map(str::to_string),
+            speaker_confidence: speaker.map(|_| 0.9),
+            redacted: false,
+        }
+    }
+
+    #[test]
+    fn merge_turns_joins_same_speaker() {
+        let turns = merge_turns(vec![
+            attributed("hello", 0, Some("Alice")),
+            attributed("there", 2, Some("Alice")),
+            attributed("hi Alice", 4, Some("Bob")),
+        ]);
+        assert_eq!(turns.len(), 2);
+        assert_eq!(turns[0].segment.text, "hello there");
+        assert_eq!(turns[0].speaker.as_deref(), Some("Alice"));
+        assert!((turns[0].segment.duration_secs - 4.0).abs() < 1e-6);
+        assert_eq!(turns[1].segment.text, "hi Alice");
+        assert_eq!(turns[1].speaker.as_deref(), Some("Bob"));
+    }
+
+    #[test]
+    fn merge_turns_keeps_unknown_segments_apart() {
+        let turns = merge_turns(vec![
+            attributed("first", 0, None),
+            attributed("second", 2, None),
+        ]);
+        assert_eq!(turns.len(), 2);
+    }
+
+    #[test]
+    fn merge_turns_only_merges_adjacent() {
+        let turns = merge_turns(vec![
+            attributed("one", 0, Some("Alice")),
+            attributed("two", 2, Some("Bob")),
+            attributed("three", 4, Some("Alice")),
+        ]);
+        assert_eq!(turns.len(), 3);
+    }
+
+    #[test]
+    fn redaction_drops_text() {
+        let redacted = AttributedSegment::redacted(attributed("secret plans", 0, None).segment);
+        assert!(redacted.redacted);
+        assert_eq!(redacted.segment.text, "_[other speaker, 2s]_");
+        assert!(!redacted.segment.text.contains("secret"));
+        assert!(is_redaction_placeholder(&redacted.segment.text));
+        assert!(!is_redaction_placeholder("other speaker"));
+    }
+
+    #[test]
+    fn merge_turns_collapses_redacted_runs() {
+        let turns = merge_turns(vec![
+            attributed("hello", 0, Some("Alice")),
+            AttributedSegment::redacted(attributed("private", 2, None).segment),
+            AttributedSegment::redacted(attributed("stuff", 4, None).segment),
+            attributed("back again", 6, Some("Alice")),
+        ]);
+        assert_eq!(turns.len(), 3);
+        assert_eq!(turns[1].segment.text, "_[other speaker, 4s]_");
+        assert!((turns[1].segment.duration_secs - 4.0).abs() < 1e-6);
+        assert_eq!(turns[2].segment.text, "back again");
+    }
+
+    #[test]
+    fn model_fingerprint_is_sha256_hex() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let path = dir.path().join("model.onnx");
+        std::fs::write(&path, b"abc").unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(
+            model_fingerprint(&path).unwrap_or_else(|e| panic!("{e}")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
 
     #[test]
     fn cosine_similarity_identical() {
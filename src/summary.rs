@@ -0,0 +1,510 @@
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+use crate::config::{Config, SummaryConfig, SummaryTarget};
+use crate::error::{HooverError, Result};
+use crate::output::layout::Layout;
+use crate::output::markdown::parse_day;
+use crate::output::route::Routes;
+use crate::vault::Vault;
+
+/// Rough characters per token, on the low side so chunks fit the window.
+const CHARS_PER_TOKEN: usize = 3;
+
+/// Tokens of the context window kept for the instructions and the reply.
+const RESERVED_TOKENS: usize = 1024;
+
+/// Smallest chunk sent, however small the configured context window.
+const MIN_CHUNK_TOKENS: usize = 64;
+
+const SUMMARY_PROMPT: &str = "You summarize a day of transcribed speech. Reply in \
+Markdown with exactly two sections: \"## Summary\", a few short paragraphs on what was \
+discussed and decided, and \"## Action items\", a bullet list of tasks with their owner \
+when known, or \"- None\". Write nothing else.";
+
+const NOTES_PROMPT: &str = "You take notes on part of a day of transcribed speech. List \
+the topics discussed, the decisions made and the tasks mentioned, with their owner when \
+known, as short bullet points. Write nothing else.";
+
+/// A day's summary and action items.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DaySummary {
+    pub summary: String,
+    pub action_items: Vec<String>,
+}
+
+impl DaySummary {
+    /// Read a summary from a model's reply.  Text outside an `Action items`
+    /// section counts as summary, so a reply that ignores the format is kept.
+    #[must_use]
+    pub fn parse(reply: &str) -> Self {
+        let mut summary = Vec::new();
+        let mut action_items = Vec::new();
+        let mut in_actions = false;
+        for line in reply.lines().map(str::trim_end) {
+            let heading = line
+                .trim_start_matches(['#', '*', ' '])
+                .trim_end_matches(['*', ':', ' '])
+                .to_lowercase();
+            if line.starts_with('#') || (line.starts_with("**") && line.ends_with(['*', ':'])) {
+                in_actions = heading.starts_with("action");
+                if in_actions || heading.starts_with("summary") {
+                    continue;
+                }
+            }
+            if !in_actions {
+                summary.push(line);
+                continue;
+            }
+            let item = line
+                .trim_start()
+                .trim_start_matches(['-', '*', '•'])
+                .trim_start_matches(|c: char| c.is_ascii_digit())
+                .trim_start_matches(['.', ')'])
+                .trim();
+            if !item.is_empty() && !item.eq_ignore_ascii_case("none") {
+                action_items.push(item.to_string());
+            }
+        }
+
+        Self {
+            summary: summary.join("\n").trim().to_string(),
+            action_items,
+        }
+    }
+
+    /// The `Summary` and `Action items` sections as headings of `level`,
+    /// followed by a blank line.
+    #[must_use]
+    pub fn to_markdown(&self, level: usize) -> String {
+        let items = if self.action_items.is_empty() {
+            "- None\n".to_string()
+        } else {
+            self.action_items
+                .iter()
+                .fold(String::new(), |mut items, item| {
+                    let _ = writeln!(items, "- {item}");
+                    items
+                })
+        };
+        let hashes = "#".repeat(level);
+        format!(
+            "{hashes} Summary\n\n{}\n\n{hashes} Action items\n\n{items}\n",
+            self.summary
+        )
+    }
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    choices: Vec<ChatChoice>,
+}
+
+#[derive(Deserialize)]
+struct ChatChoice {
+    message: ChatMessage,
+}
+
+#[derive(Deserialize)]
+struct ChatMessage {
+    content: String,
+}
+
+/// Summarizes text with an OpenAI-compatible chat completions endpoint.
+pub struct Summarizer {
+    agent: ureq::Agent,
+    url: String,
+    model: String,
+    api_key: Option<String>,
+    /// Longest text sent in one request, in characters.
+    chunk_chars: usize,
+}
+
+impl Summarizer {
+    #[must_use]
+    pub fn new(config: &SummaryConfig) -> Self {
+        let agent: ureq::Agent = ureq::Agent::config_builder()
+            .timeout_global(Some(Duration::from_secs(config.timeout_secs)))
+            .build()
+            .into();
+        let tokens = usize::try_from(config.context_tokens).unwrap_or(usize::MAX);
+        Self {
+            agent,
+            url: config.url.clone(),
+            model: config.model.clone(),
+            api_key: config.api_key.clone(),
+            chunk_chars: tokens
+                .saturating_sub(RESERVED_TOKENS)
+                .max(MIN_CHUNK_TOKENS)
+                .saturating_mul(CHARS_PER_TOKEN),
+        }
+    }
+
+    /// Summarize `transcript`.
+    ///
+    /// A transcript too long for the context window is split into chunks
+    /// that are each cut down to notes, repeatedly until the notes fit, and
+    /// the notes are summarized instead.
+    pub fn summarize(&self, transcript: &str) -> Result<DaySummary> {
+        let mut text = transcript.to_string();
+        while text.len() > self.chunk_chars {
+            let notes = chunks(&text, self.chunk_chars)
+                .iter()
+                .map(|chunk| self.complete(NOTES_PROMPT, chunk))
+                .collect::<Result<Vec<_>>>()?
+                .join("\n\n");
+            if notes.len() >= text.len() {
+                // The notes don't get any shorter; send what fits.
+                text = chunks(&notes, self.chunk_chars).swap_remove(0);
+                break;
+            }
+            text = notes;
+        }
+
+        Ok(DaySummary::parse(&self.complete(SUMMARY_PROMPT, &text)?))
+    }
+
+    fn complete(&self, system: &str, user: &str) -> Result<String> {
+        let body = serde_json::json!({
+            "model": self.model,
+            "messages": [
+                { "role": "system", "content": system },
+                { "role": "user", "content": user },
+            ],
+            "temperature": 0.2,
+        });
+        let body = serde_json::to_vec(&body)
+            .map_err(|e| HooverError::Summary(format!("failed to serialize request: {e}")))?;
+
+        let mut request = self
+            .agent
+            .post(&self.url)
+            .header("Content-Type", "application/json");
+        if let Some(key) = &self.api_key {
+            request = request.header("Authorization", &format!("Bearer {key}"));
+        }
+        let mut response = request.send(&body[..]).map_err(|e| {
+            HooverError::Network(format!("summary request to {} failed: {e}", self.url))
+        })?;
+        let reply = response
+            .body_mut()
+            .read_to_string()
+            .map_err(|e| HooverError::Network(format!("failed to read summary response: {e}")))?;
+
+        let reply: ChatResponse = serde_json::from_str(&reply).map_err(|e| {
+            HooverError::Summary(format!("unexpected response from {}: {e}", self.url))
+        })?;
+        reply
+            .choices
+            .into_iter()
+            .next()
+            .map(|choice| choice.message.content)
+            .ok_or_else(|| HooverError::Summary(format!("{} returned no choices", self.url)))
+    }
+}
+
+/// Split `text` into chunks of at most `max` bytes, between lines where
+/// possible.
+fn chunks(text: &str, max: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    for line in text.lines() {
+        if !current.is_empty() && current.len() + line.len() + 1 > max {
+            chunks.push(std::mem::take(&mut current));
+        }
+        let mut rest = line;
+        while rest.len() > max {
+            let mut end = max;
+            while !rest.is_char_boundary(end) {
+                end -= 1;
+            }
+            chunks.push(rest[..end].to_string());
+            rest = &rest[end..];
+        }
+        current.push_str(rest);
+        current.push('\n');
+    }
+    if !current.trim().is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// Path of the summary sidecar for `date` inside `output_dir`: the daily
+/// markdown file's path with a `.summary.md` extension.
+#[must_use]
+pub fn sidecar_file(layout: &Layout, output_dir: &Path, date: NaiveDate) -> PathBuf {
+    layout
+        .day_file(output_dir, date)
+        .with_extension("summary.md")
+}
+
+/// Summarize the day `date` in every output directory holding it, writing
+/// the summary where `summary.target` says.
+///
+/// Days that already have a summary are skipped unless `force` is set.
+/// Returns the files written.
+pub fn summarize_day(config: &Config, date: NaiveDate, force: bool) -> Result<Vec<PathBuf>> {
+    let layout = Layout::new(&config.output.layout)?;
+    let routes = Routes::new(&config.output)?;
+    let vault = Vault::new(&config.output)?;
+    let summarizer = Summarizer::new(&config.summary);
+
+    let mut written = Vec::new();
+    for dir in routes.all_directories() {
+        let day_file = layout.day_file(dir, date);
+        let Some(content) = vault.read(&day_file)? else {
+            continue;
+        };
+        let body_start = layout.body_start(&content, date);
+        let transcript_start = layout.transcript_start(&content, date);
+
+        let existing = match config.summary.target {
+            SummaryTarget::Inline => {
+                let Some(end) = transcript_start else {
+                    return Err(HooverError::Config(
+                        "inline summaries go before the first time heading; \
+                         enable output.timestamps or set summary.target to sidecar"
+                            .to_string(),
+                    ));
+                };
+                !content[body_start..end].trim().is_empty()
+            }
+            SummaryTarget::Sidecar => vault.has_content(&sidecar_file(&layout, dir, date)),
+        };
+        if existing && !force {
+            tracing::info!("{} already has a summary", day_file.display());
+            continue;
+        }
+
+        let transcript = day_text(&content, date, &layout);
+        if transcript.is_empty() {
+            continue;
+        }
+        let summary = summarizer.summarize(&transcript)?;
+
+        let path = match config.summary.target {
+            SummaryTarget::Inline => {
+                // Re-read in case the day was appended to while summarizing.
+                let content = vault.read(&day_file)?.unwrap_or(content);
+                let body_start = layout.body_start(&content, date);
+                let end = layout
+                    .transcript_start(&content, date)
+                    .unwrap_or_else(|| layout.body_end(&content, body_start));
+                let updated = format!(
+                    "{}\n{}{}",
+                    &content[..body_start],
+                    summary.to_markdown(layout.subheading_level()),
+                    &content[end..]
+                );
+                vault.write(&day_file, &updated)?;
+                day_file
+            }
+            SummaryTarget::Sidecar => {
+                let path = sidecar_file(&layout, dir, date);
+                let content = format!("{}{}", layout.title_header(date), summary.to_markdown(2));
+                vault.write(&path, &content)?;
+                path
+            }
+        };
+        tracing::info!("summarized {date} into {}", path.display());
+        written.push(path);
+    }
+
+    Ok(written)
+}
+
+/// Summarize `date` at day rollover when `summary.auto` is on.
+pub fn auto_summarize(config: &Config, date: NaiveDate) -> Result<()> {
+    if !config.summary.auto {
+        return Ok(());
+    }
+
+    summarize_day(config, date, false)?;
+    Ok(())
+}
+
+/// The transcript of a daily file as plain lines for the model, one per
+/// entry: `[time] speaker: text`.
+fn day_text(content: &str, date: NaiveDate, layout: &Layout) -> String {
+    parse_day(content, date, layout)
+        .into_iter()
+        .map(|entry| {
+            let mut line = String::new();
+            if let Some(time) = entry.time {
+                let _ = write!(line, "[{time}] ");
+            }
+            if let Some(speaker) = entry.speaker {
+                let _ = write!(line, "{speaker}: ");
+            }
+            line.push_str(&entry.text);
+            line.push('\n');
+            line
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread::JoinHandle;
+
+    /// Answer one chat completion request per reply, returning the request
+    /// bodies.
+    fn serve(replies: Vec<&'static str>) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap_or_else(|e| panic!("{e}"));
+        let url = format!(
+            "http://{}/v1/chat/completions",
+            listener.local_addr().unwrap_or_else(|e| panic!("{e}"))
+        );
+        let handle = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            for reply in replies {
+                let (stream, _) = listener.accept().unwrap_or_else(|e| panic!("{e}"));
+                let mut reader = BufReader::new(stream);
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader
+                        .read_line(&mut line)
+                        .unwrap_or_else(|e| panic!("{e}"));
+                    let line = line.trim_end().to_lowercase();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some(value) = line.strip_prefix("content-length:") {
+                        length = value.trim().parse().unwrap_or_else(|e| panic!("{e}"));
+                    }
+                }
+                let mut body = vec![0; length];
+                reader
+                    .read_exact(&mut body)
+                    .unwrap_or_else(|e| panic!("{e}"));
+                requests.push(String::from_utf8_lossy(&body).into_owned());
+
+                let json = serde_json::json!({
+                    "choices": [{ "message": { "role": "assistant", "content": reply } }]
+                })
+                .to_string();
+                write!(
+                    reader.get_mut(),
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+                     Content-Length: {}\r\nConnection: close\r\n\r\n{json}",
+                    json.len()
+                )
+                .unwrap_or_else(|e| panic!("{e}"));
+            }
+            requests
+        });
+        (url, handle)
+    }
+
+    fn config(dir: &Path, url: &str, yaml: &str) -> Config {
+        let yaml = format!(
+            "output:\n  directory: {}\nsummary:\n  url: {url}\n{yaml}",
+            dir.display()
+        );
+        serde_yaml_ng::from_str(&yaml).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    fn date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, 17).unwrap_or_else(|| panic!("bad date"))
+    }
+
+    const DAY: &str = "# 2026-10-17\n\n## 09:00\n\n**Alice:** Let's ship on Friday.\n\n\
+                       **Bob:** I'll write the release notes.\n\n";
+
+    const REPLY: &str = "## Summary\n\nThe team agreed to ship on Friday.\n\n\
+                         ## Action items\n\n- Bob: write the release notes\n";
+
+    #[test]
+    fn parse_reply() {
+        let summary = DaySummary::parse(REPLY);
+        assert_eq!(summary.summary, "The team agreed to ship on Friday.");
+        assert_eq!(summary.action_items, vec!["Bob: write the release notes"]);
+
+        let summary =
+            DaySummary::parse("**Summary:**\nA quiet day.\n\n**Action items:**\n1. None\n");
+        assert_eq!(summary.summary, "A quiet day.");
+        assert!(summary.action_items.is_empty());
+        assert!(
+            summary
+                .to_markdown(2)
+                .contains("## Action items\n\n- None\n")
+        );
+
+        let summary = DaySummary::parse("Just prose.");
+        assert_eq!(summary.summary, "Just prose.");
+    }
+
+    #[test]
+    fn chunks_split_between_lines() {
+        let text = "aaaa\nbbbb\ncccc\n";
+        assert_eq!(chunks(text, 10), vec!["aaaa\nbbbb\n", "cccc\n"]);
+        assert_eq!(chunks("ééééé", 4), vec!["éé", "éé", "é\n"]);
+    }
+
+    #[test]
+    fn summary_is_written_inline() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        std::fs::write(dir.path().join("2026-10-17.md"), DAY).unwrap_or_else(|e| panic!("{e}"));
+        let (url, server) = serve(vec![REPLY]);
+        let config = config(dir.path(), &url, "");
+
+        let written = summarize_day(&config, date(), false).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(written, vec![dir.path().join("2026-10-17.md")]);
+        let requests = server.join().unwrap_or_else(|_| panic!("server panicked"));
+        assert!(requests[0].contains("[09:00] Alice: Let's ship on Friday."));
+
+        let content = std::fs::read_to_string(&written[0]).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(
+            content,
+            format!(
+                "# 2026-10-17\n\n{}{}",
+                DaySummary::parse(REPLY).to_markdown(2),
+                &DAY[14..]
+            )
+        );
+        let layout = Layout::new(&config.output.layout).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(parse_day(&content, date(), &layout).len(), 2);
+
+        // Already summarized: nothing is sent.
+        let written = summarize_day(&config, date(), false).unwrap_or_else(|e| panic!("{e}"));
+        assert!(written.is_empty());
+    }
+
+    #[test]
+    fn long_days_are_summarized_from_notes() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let mut day = DAY.to_string();
+        for i in 0..40 {
+            let _ = write!(day, "**Alice:** Point number {i} about the release.\n\n");
+        }
+        std::fs::write(dir.path().join("2026-10-17.md"), &day).unwrap_or_else(|e| panic!("{e}"));
+        let (url, server) = serve(vec!["- notes one", "- notes two", "- notes three", REPLY]);
+        let config = config(
+            dir.path(),
+            &url,
+            "  context_tokens: 1300\n  target: sidecar\n",
+        );
+
+        let written = summarize_day(&config, date(), false).unwrap_or_else(|e| panic!("{e}"));
+        let sidecar = dir.path().join("2026-10-17.summary.md");
+        assert_eq!(written, vec![sidecar.clone()]);
+        let requests = server.join().unwrap_or_else(|_| panic!("server panicked"));
+        assert_eq!(requests.len(), 4);
+        assert!(requests[3].contains("notes one"));
+
+        let content = std::fs::read_to_string(sidecar).unwrap_or_else(|e| panic!("{e}"));
+        assert!(content.starts_with("# Sat, October 17, 2026\n\n## Summary\n"));
+        let unchanged = std::fs::read_to_string(dir.path().join("2026-10-17.md"))
+            .unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(unchanged, day);
+    }
+}
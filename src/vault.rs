@@ -0,0 +1,554 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use pbkdf2::pbkdf2_hmac;
+use rand::RngCore;
+use sha2::Sha256;
+
+use crate::config::{Config, KeySource, OutputConfig};
+use crate::error::{HooverError, Result};
+use crate::net::crypto::CryptoContext;
+use crate::output::jsonl;
+use crate::output::layout::Layout;
+use crate::output::route::Routes;
+use crate::vcs::git;
+
+/// Start of every encrypted file, followed by the nonce and the AES-256-GCM
+/// ciphertext.
+const MAGIC: &[u8; 8] = b"HVRENC01";
+
+/// Appended to the names of encrypted daily files and logs.
+pub const ENCRYPTED_SUFFIX: &str = ".enc";
+
+/// Salt for passphrase-derived keys, kept in the output directory.
+const SALT_FILE: &str = ".hoover-salt";
+
+/// PBKDF2-HMAC-SHA256 iterations for passphrase-derived keys.
+const PBKDF2_ROUNDS: u32 = 600_000;
+
+/// Reads and writes files as plain text, or sealed with AES-256-GCM when
+/// `output.encryption` is set.
+///
+/// Files are read in either form, so plaintext written before encryption
+/// was turned on stays readable and is replaced by ciphertext the next time
+/// it is written.
+#[derive(Clone, Default)]
+pub struct Vault {
+    crypto: Option<Arc<CryptoContext>>,
+}
+
+impl std::fmt::Debug for Vault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Vault")
+            .field("encrypted", &self.is_encrypted())
+            .finish()
+    }
+}
+
+impl Vault {
+    /// The vault for daily files and logs.
+    pub fn new(config: &OutputConfig) -> Result<Self> {
+        let Some(encryption) = &config.encryption else {
+            return Ok(Self::default());
+        };
+        if config.layout.daily_note.is_some() {
+            return Err(HooverError::Config(
+                "daily notes can't be encrypted; they are shared with other tools".to_string(),
+            ));
+        }
+        let output_dir = Config::expand_path(&config.directory);
+        let crypto = match &encryption.key {
+            KeySource::File { path } => CryptoContext::from_key_file(&Config::expand_path(path))?,
+            KeySource::Passphrase { env } => {
+                let passphrase = std::env::var(env).unwrap_or_default();
+                if passphrase.is_empty() {
+                    return Err(HooverError::Crypto(format!(
+                        "set {env} to the passphrase for encrypted transcripts"
+                    )));
+                }
+                let salt = load_or_create_salt(&output_dir)?;
+                CryptoContext::new(&derive_key(passphrase.as_bytes(), &salt, PBKDF2_ROUNDS))
+            }
+            KeySource::Keyring { description } => CryptoContext::new(&keyring_key(description)?),
+        };
+        Ok(Self {
+            crypto: Some(Arc::new(crypto)),
+        })
+    }
+
+    /// The vault for speaker profiles: encrypted only when
+    /// `output.encryption.profiles` is set.
+    pub fn for_profiles(config: &OutputConfig) -> Result<Self> {
+        match &config.encryption {
+            Some(encryption) if encryption.profiles => Self::new(config),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// A vault encrypting with `key`.
+    #[must_use]
+    pub fn with_key(key: &[u8; 32]) -> Self {
+        Self {
+            crypto: Some(Arc::new(CryptoContext::new(key))),
+        }
+    }
+
+    #[must_use]
+    pub const fn is_encrypted(&self) -> bool {
+        self.crypto.is_some()
+    }
+
+    /// Encrypt `plaintext`, or return it as is without a key.
+    pub fn seal(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let Some(crypto) = &self.crypto else {
+            return Ok(plaintext.to_vec());
+        };
+        let (ciphertext, nonce) = crypto.encrypt(plaintext)?;
+        let mut sealed = Vec::with_capacity(MAGIC.len() + nonce.len() + ciphertext.len());
+        sealed.extend_from_slice(MAGIC);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+        Ok(sealed)
+    }
+
+    /// Decrypt data written by [`Vault::seal`]; anything else is plaintext
+    /// and returned as is.
+    pub fn open(&self, data: Vec<u8>) -> Result<Vec<u8>> {
+        let Some(sealed) = data.strip_prefix(MAGIC) else {
+            return Ok(data);
+        };
+        let Some(crypto) = &self.crypto else {
+            return Err(HooverError::Crypto(
+                "file is encrypted; configure output.encryption to read it".to_string(),
+            ));
+        };
+        let (nonce, ciphertext) = sealed
+            .split_first_chunk::<12>()
+            .ok_or_else(|| HooverError::Crypto("encrypted file is truncated".to_string()))?;
+        crypto.decrypt(nonce, ciphertext)
+    }
+
+    /// Where `path` is written: with [`ENCRYPTED_SUFFIX`] when encrypting.
+    #[must_use]
+    pub fn file_path(&self, path: &Path) -> PathBuf {
+        if self.is_encrypted() {
+            encrypted_path(path)
+        } else {
+            path.to_path_buf()
+        }
+    }
+
+    /// The file currently holding `path`, encrypted or not.
+    #[must_use]
+    pub fn stored_path(&self, path: &Path) -> Option<PathBuf> {
+        [encrypted_path(path), path.to_path_buf()]
+            .into_iter()
+            .find(|p| p.exists())
+    }
+
+    /// The text of `path`, or `None` if it doesn't exist.
+    pub fn read(&self, path: &Path) -> Result<Option<String>> {
+        let Some(stored) = self.stored_path(path) else {
+            return Ok(None);
+        };
+        let data = fs::read(&stored).map_err(|e| {
+            HooverError::Output(format!("failed to read {}: {e}", stored.display()))
+        })?;
+        let data = self.open(data)?;
+        String::from_utf8(data)
+            .map(Some)
+            .map_err(|e| HooverError::Output(format!("{} is not UTF-8: {e}", stored.display())))
+    }
+
+    /// Replace the contents of `path`, atomically.  When encrypting, a
+    /// plaintext copy left from before is removed.
+    pub fn write(&self, path: &Path, content: &str) -> Result<()> {
+        let target = self.file_path(path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        replace_file(&target, &self.seal(content.as_bytes())?)?;
+        if target != path && path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    /// Append `text` to `path`, creating it if missing.  Encrypted files are
+    /// rewritten as a whole.
+    pub fn append(&self, path: &Path, text: &str) -> Result<()> {
+        if self.is_encrypted() || encrypted_path(path).exists() {
+            let mut content = self.read(path)?.unwrap_or_default();
+            content.push_str(text);
+            return self.write(path, &content);
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| file.write_all(text.as_bytes()))
+            .map_err(|e| HooverError::Output(format!("failed to write to {}: {e}", path.display())))
+    }
+
+    /// Append `line`, which ends in a newline, to `path` as a line of its
+    /// own: a line left unterminated by a crash is ended first, so the two
+    /// don't merge.
+    pub fn append_line(&self, path: &Path, line: &str) -> Result<()> {
+        if self.is_encrypted() || encrypted_path(path).exists() {
+            let mut content = self.read(path)?.unwrap_or_default();
+            if !content.is_empty() && !content.ends_with('\n') {
+                content.push('\n');
+            }
+            content.push_str(line);
+            return self.write(path, &content);
+        }
+
+        let torn = File::open(path)
+            .and_then(|mut file| {
+                if file.metadata()?.len() == 0 {
+                    return Ok(false);
+                }
+                let mut last = [0; 1];
+                file.seek(SeekFrom::End(-1))?;
+                file.read_exact(&mut last)?;
+                Ok(last != *b"\n")
+            })
+            .unwrap_or(false);
+        if torn {
+            self.append(path, &format!("\n{line}"))
+        } else {
+            self.append(path, line)
+        }
+    }
+
+    /// Whether `path` exists and is not empty.
+    #[must_use]
+    pub fn has_content(&self, path: &Path) -> bool {
+        // Sealing empty text still adds the magic, nonce and 16-byte tag.
+        let empty = |p: &Path, overhead: u64| fs::metadata(p).map_or(true, |m| m.len() <= overhead);
+        let sealed_overhead = (MAGIC.len() + 12 + 16) as u64;
+        !(empty(&encrypted_path(path), sealed_overhead) && empty(path, 0))
+    }
+}
+
+/// `path` with [`ENCRYPTED_SUFFIX`] appended.
+#[must_use]
+pub fn encrypted_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(ENCRYPTED_SUFFIX);
+    PathBuf::from(name)
+}
+
+/// The file an encrypted file stands for: `path` without
+/// [`ENCRYPTED_SUFFIX`].
+#[must_use]
+pub fn logical_path(path: &Path) -> PathBuf {
+    path.to_str()
+        .and_then(|p| p.strip_suffix(ENCRYPTED_SUFFIX))
+        .map_or_else(|| path.to_path_buf(), PathBuf::from)
+}
+
+/// Encrypt the plaintext daily files and logs in every output directory.
+///
+/// Speaker profiles are encrypted too when `output.encryption.profiles` is
+/// set.  With VCS enabled the result is committed.  Returns the number of
+/// files encrypted.
+pub fn seal_existing(config: &Config) -> Result<usize> {
+    let vault = Vault::new(&config.output)?;
+    if !vault.is_encrypted() {
+        return Err(HooverError::Config(
+            "set output.encryption to encrypt transcripts".to_string(),
+        ));
+    }
+
+    let layout = Layout::new(&config.output.layout)?;
+    let routes = Routes::new(&config.output)?;
+    let mut sealed = 0;
+    for dir in routes.all_directories() {
+        for (date, path) in layout.day_files(dir)? {
+            for file in [path, jsonl::log_file(&layout, dir, date)] {
+                if !file.exists() {
+                    continue;
+                }
+                if encrypted_path(&file).exists() {
+                    tracing::warn!(
+                        "{} has both a plaintext and an encrypted copy; leaving it",
+                        file.display()
+                    );
+                    continue;
+                }
+                let content = fs::read_to_string(&file)?;
+                vault.write(&file, &content)?;
+                sealed += 1;
+            }
+        }
+    }
+
+    let profiles = Vault::for_profiles(&config.output)?;
+    if profiles.is_encrypted() {
+        let profiles_dir = Config::expand_path(&config.speaker.profiles_dir);
+        for dir in [profiles_dir.clone(), profiles_dir.join("clusters")] {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries {
+                let path = entry?.path();
+                let is_profile = matches!(
+                    path.extension().and_then(|e| e.to_str()),
+                    Some("bin" | "json")
+                );
+                if !is_profile {
+                    continue;
+                }
+                let data = fs::read(&path)?;
+                if !data.starts_with(MAGIC) {
+                    replace_file(&path, &profiles.seal(&data)?)?;
+                    sealed += 1;
+                }
+            }
+        }
+    }
+
+    let output_dir = Config::expand_path(&config.output.directory);
+    if sealed > 0 && config.vcs.enabled && output_dir.join(".git").exists() {
+        git::add_and_commit(&output_dir, "encrypt transcripts")?;
+    }
+    Ok(sealed)
+}
+
+/// Replace `path` with `data` through a synced temporary file next to it,
+/// so a crash or a full disk leaves either the old or the new contents,
+/// never a truncated file.
+pub fn replace_file(path: &Path, data: &[u8]) -> Result<()> {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let partial = path.with_file_name(format!(".{name}.partial"));
+    let written = File::create(&partial).and_then(|mut file| {
+        file.write_all(data)?;
+        file.sync_all()
+    });
+    if let Err(e) = written {
+        let _ = fs::remove_file(&partial);
+        return Err(HooverError::Output(format!(
+            "failed to write {}: {e}",
+            path.display()
+        )));
+    }
+    fs::rename(&partial, path)
+        .map_err(|e| HooverError::Output(format!("failed to replace {}: {e}", path.display())))
+}
+
+fn load_or_create_salt(output_dir: &Path) -> Result<Vec<u8>> {
+    let path = output_dir.join(SALT_FILE);
+    match fs::read(&path) {
+        Ok(salt) if !salt.is_empty() => return Ok(salt),
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => {
+            return Err(HooverError::Crypto(format!(
+                "failed to read {}: {e}",
+                path.display()
+            )));
+        }
+    }
+
+    let mut salt = vec![0u8; 16];
+    rand::rng().fill_bytes(&mut salt);
+    fs::create_dir_all(output_dir)?;
+    fs::write(&path, &salt)
+        .map_err(|e| HooverError::Crypto(format!("failed to write {}: {e}", path.display())))?;
+    Ok(salt)
+}
+
+/// PBKDF2-HMAC-SHA256 with a 32-byte output.
+fn derive_key(passphrase: &[u8], salt: &[u8], rounds: u32) -> [u8; 32] {
+    let mut key = [0; 32];
+    pbkdf2_hmac::<Sha256>(passphrase, salt, rounds, &mut key);
+    key
+}
+
+/// Read a 32-byte `user` key from the Linux kernel keyring, e.g. one added
+/// with `keyctl padd user hoover @u < key.bin`.
+#[cfg(target_os = "linux")]
+fn keyring_key(description: &str) -> Result<[u8; 32]> {
+    const KEYCTL_READ: libc::c_long = 11;
+
+    let name = std::ffi::CString::new(description).map_err(|_| {
+        HooverError::Crypto(format!("invalid keyring key description: {description}"))
+    })?;
+    // Safety: request_key takes NUL-terminated strings and may be given no
+    // callout info and no destination keyring.
+    let serial = unsafe {
+        libc::syscall(
+            libc::SYS_request_key,
+            c"user".as_ptr(),
+            name.as_ptr(),
+            std::ptr::null::<libc::c_char>(),
+            0 as libc::c_long,
+        )
+    };
+    if serial < 0 {
+        return Err(HooverError::Crypto(format!(
+            "key '{description}' not found in the kernel keyring: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+
+    let mut key = [0u8; 32];
+    // Safety: keyctl writes at most `key.len()` bytes into `key`.
+    let len = unsafe {
+        libc::syscall(
+            libc::SYS_keyctl,
+            KEYCTL_READ,
+            serial,
+            key.as_mut_ptr(),
+            key.len(),
+        )
+    };
+    if len < 0 {
+        return Err(HooverError::Crypto(format!(
+            "failed to read key '{description}' from the kernel keyring: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+    if len != 32 {
+        return Err(HooverError::Crypto(format!(
+            "keyring key '{description}' must be exactly 32 bytes, got {len}"
+        )));
+    }
+    Ok(key)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn keyring_key(_description: &str) -> Result<[u8; 32]> {
+    Err(HooverError::Crypto(
+        "the kernel keyring is only available on Linux".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt::Write as _;
+
+    #[test]
+    fn files_round_trip_and_replace_plaintext() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let path = dir.path().join("2026-01-01.md");
+        fs::write(&path, "# Day\n\n").unwrap_or_else(|e| panic!("{e}"));
+
+        let vault = Vault::with_key(&[7u8; 32]);
+        assert!(vault.has_content(&path));
+        vault
+            .append(&path, "**Alice:** hello\n")
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        assert!(!path.exists());
+        let stored = fs::read(encrypted_path(&path)).unwrap_or_else(|e| panic!("{e}"));
+        assert!(stored.starts_with(MAGIC));
+        assert!(!String::from_utf8_lossy(&stored).contains("Alice"));
+        assert_eq!(
+            vault
+                .read(&path)
+                .unwrap_or_else(|e| panic!("{e}"))
+                .as_deref(),
+            Some("# Day\n\n**Alice:** hello\n")
+        );
+
+        // Without the key the file cannot be read, and isn't overwritten.
+        let plain = Vault::default();
+        assert!(plain.read(&path).is_err());
+        assert!(plain.append(&path, "more").is_err());
+        assert!(Vault::with_key(&[8u8; 32]).read(&path).is_err());
+
+        let empty = dir.path().join("empty.md");
+        vault.write(&empty, "").unwrap_or_else(|e| panic!("{e}"));
+        assert!(!vault.has_content(&empty));
+        assert_eq!(logical_path(&encrypted_path(&empty)), empty);
+    }
+
+    #[test]
+    fn failed_write_keeps_previous_ciphertext() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let path = dir.path().join("2026-01-01.md");
+        let vault = Vault::with_key(&[7u8; 32]);
+        vault
+            .write(&path, "**Alice:** hello\n")
+            .unwrap_or_else(|e| panic!("{e}"));
+        let before = fs::read(encrypted_path(&path)).unwrap_or_else(|e| panic!("{e}"));
+
+        // The temporary file can't be created, as if the disk were full.
+        let partial = dir.path().join(".2026-01-01.md.enc.partial");
+        fs::create_dir(&partial).unwrap_or_else(|e| panic!("{e}"));
+        assert!(vault.append(&path, "**Bob:** hi\n").is_err());
+
+        let after = fs::read(encrypted_path(&path)).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(after, before);
+        assert_eq!(
+            vault
+                .read(&path)
+                .unwrap_or_else(|e| panic!("{e}"))
+                .as_deref(),
+            Some("**Alice:** hello\n")
+        );
+    }
+
+    #[test]
+    fn seal_existing_encrypts_plaintext_days() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let output = dir.path().join("out");
+        fs::create_dir_all(&output).unwrap_or_else(|e| panic!("{e}"));
+        let day = output.join("2026-01-01.md");
+        fs::write(&day, "# Day\n\n**Alice:** hello\n").unwrap_or_else(|e| panic!("{e}"));
+        fs::write(output.join("2026-01-01.jsonl"), "{}\n").unwrap_or_else(|e| panic!("{e}"));
+        let key_file = dir.path().join("key");
+        fs::write(&key_file, [1u8; 32]).unwrap_or_else(|e| panic!("{e}"));
+
+        let yaml = format!(
+            "output:\n  directory: {}\n  encryption:\n    key:\n      type: file\n      path: {}\n",
+            output.display(),
+            key_file.display()
+        );
+        let config: Config = serde_yaml_ng::from_str(&yaml).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(seal_existing(&config).unwrap_or_else(|e| panic!("{e}")), 2);
+        assert_eq!(seal_existing(&config).unwrap_or_else(|e| panic!("{e}")), 0);
+
+        assert!(!day.exists());
+        let vault = Vault::new(&config.output).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(
+            vault
+                .read(&day)
+                .unwrap_or_else(|e| panic!("{e}"))
+                .as_deref(),
+            Some("# Day\n\n**Alice:** hello\n")
+        );
+    }
+
+    #[test]
+    fn passphrase_keys_follow_pbkdf2() {
+        let hex = |key: [u8; 32]| {
+            key.iter().fold(String::new(), |mut hex, b| {
+                let _ = write!(hex, "{b:02x}");
+                hex
+            })
+        };
+        let key = derive_key(b"password", b"salt", 1);
+        assert_eq!(
+            hex(key),
+            "120fb6cffcf8b32c43e7225256c4f837a86548c92ccc35480805987cb70be17b"
+        );
+        let key = derive_key(b"password", b"salt", 4096);
+        assert_eq!(
+            hex(key),
+            "c5e478d59288c841aa530db6845c4c8d962893a001ce4e11a4963873aa98134a"
+        );
+    }
+}
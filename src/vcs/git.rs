@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::path::Path;
 
 use git2::{Repository, Signature};
@@ -13,12 +14,13 @@ pub fn open_or_init(path: &Path) -> Result<Repository> {
     }
 }
 
-/// Stage all changes and create a commit.
+/// Stage all changes, including deletions, and create a commit.
 pub fn add_and_commit(path: &Path, message: &str) -> Result<()> {
     let repo = open_or_init(path)?;
 
     let mut index = repo.index()?;
     index.add_all(std::iter::once("*"), git2::IndexAddOption::DEFAULT, None)?;
+    index.update_all(std::iter::once("*"), None)?;
     index.write()?;
 
     let tree_oid = index.write_tree()?;
@@ -42,6 +44,89 @@ pub fn add_and_commit(path: &Path, message: &str) -> Result<()> {
     Ok(())
 }
 
+/// Rewrite every commit on the current branch so the given repo-relative
+/// paths no longer hold their old content: a `None` replacement removes the
+/// path, `Some` replaces it wherever it exists.
+///
+/// Author, committer and message are kept.  Returns the number of commits
+/// rewritten; the branch then has to be force-pushed.
+pub fn rewrite_paths(path: &Path, changes: &[(String, Option<Vec<u8>>)]) -> Result<usize> {
+    let repo = Repository::open(path)?;
+    let head = repo.head()?;
+    let Some(head_oid) = head.target() else {
+        return Ok(0);
+    };
+
+    let changes = changes
+        .iter()
+        .map(|(file, content)| {
+            let blob = content.as_deref().map(|c| repo.blob(c)).transpose()?;
+            Ok((file.as_str(), blob))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(head_oid)?;
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)?;
+
+    let mut rewritten = HashMap::new();
+    let mut count = 0;
+    for oid in revwalk {
+        let oid = oid?;
+        let commit = repo.find_commit(oid)?;
+        let tree = commit.tree()?;
+
+        let mut update = git2::build::TreeUpdateBuilder::new();
+        for (file, blob) in &changes {
+            if tree.get_path(Path::new(file)).is_err() {
+                continue;
+            }
+            match blob {
+                Some(blob) => update.upsert(*file, *blob, git2::FileMode::Blob),
+                None => update.remove(*file),
+            };
+        }
+        let new_tree = update.create_updated(&repo, &tree)?;
+
+        let parent_ids: Vec<git2::Oid> = commit
+            .parent_ids()
+            .map(|p| rewritten.get(&p).copied().unwrap_or(p))
+            .collect();
+        if new_tree == tree.id() && commit.parent_ids().eq(parent_ids.iter().copied()) {
+            continue;
+        }
+
+        let parents = parent_ids
+            .iter()
+            .map(|p| repo.find_commit(*p))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let parents: Vec<&git2::Commit> = parents.iter().collect();
+        let new_oid = repo.commit(
+            None,
+            &commit.author(),
+            &commit.committer(),
+            commit.message_raw().unwrap_or_default(),
+            &repo.find_tree(new_tree)?,
+            &parents,
+        )?;
+        rewritten.insert(oid, new_oid);
+        count += 1;
+    }
+
+    if let Some(&new_head) = rewritten.get(&head_oid) {
+        if head.is_branch()
+            && let Some(name) = head.name()
+        {
+            repo.reference(name, new_head, true, "hoover: rewrite pruned transcripts")?;
+        } else {
+            repo.set_head_detached(new_head)?;
+        }
+    }
+
+    tracing::info!("rewrote {count} commits");
+    Ok(count)
+}
+
 /// Push to a named remote.
 ///
 /// When `token` is provided the push authenticates over HTTPS using
@@ -166,4 +251,54 @@ mod tests {
         assert!(log[0].contains("second"));
         assert!(log[1].contains("first"));
     }
+
+    #[test]
+    fn rewrite_paths_removes_and_replaces_history() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let write = |name: &str, content: &str| {
+            std::fs::write(dir.path().join(name), content).unwrap_or_else(|e| panic!("{e}"));
+        };
+        write("old.md", "secret");
+        write("kept.md", "full transcript");
+        add_and_commit(dir.path(), "first").unwrap_or_else(|e| panic!("{e}"));
+        write("new.md", "today");
+        add_and_commit(dir.path(), "second").unwrap_or_else(|e| panic!("{e}"));
+
+        std::fs::remove_file(dir.path().join("old.md")).unwrap_or_else(|e| panic!("{e}"));
+        write("kept.md", "summary");
+        add_and_commit(dir.path(), "prune").unwrap_or_else(|e| panic!("{e}"));
+
+        let rewritten = rewrite_paths(
+            dir.path(),
+            &[
+                ("old.md".to_string(), None),
+                ("kept.md".to_string(), Some(b"summary".to_vec())),
+            ],
+        )
+        .unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(rewritten, 3);
+
+        let repo = Repository::open(dir.path()).unwrap_or_else(|e| panic!("{e}"));
+        let mut revwalk = repo.revwalk().unwrap_or_else(|e| panic!("{e}"));
+        revwalk.push_head().unwrap_or_else(|e| panic!("{e}"));
+        revwalk
+            .set_sorting(git2::Sort::TOPOLOGICAL)
+            .unwrap_or_else(|e| panic!("{e}"));
+        let mut messages = Vec::new();
+        for oid in revwalk {
+            let commit = oid
+                .and_then(|oid| repo.find_commit(oid))
+                .unwrap_or_else(|e| panic!("{e}"));
+            let tree = commit.tree().unwrap_or_else(|e| panic!("{e}"));
+            assert!(tree.get_path(Path::new("old.md")).is_err());
+            let kept = tree
+                .get_path(Path::new("kept.md"))
+                .and_then(|entry| entry.to_object(&repo))
+                .unwrap_or_else(|e| panic!("{e}"));
+            let blob = kept.as_blob().unwrap_or_else(|| panic!("not a blob"));
+            assert_eq!(blob.content(), b"summary");
+            messages.push(commit.message().unwrap_or_default().to_string());
+        }
+        assert_eq!(messages, ["prune", "second", "first"]);
+    }
 }